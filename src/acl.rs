@@ -0,0 +1,137 @@
+use quick_xml::{events::Event, Reader};
+use reqwest::header::HeaderMap;
+use reqwest::Method;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::errors::{Error, ObjectError};
+use crate::oss::OSS;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ObjectAcl {
+    // Inherits the bucket ACL.
+    Default,
+    Private,
+    PublicRead,
+    PublicReadWrite,
+}
+
+impl FromStr for ObjectAcl {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "default" => Ok(ObjectAcl::Default),
+            "private" => Ok(ObjectAcl::Private),
+            "public-read" => Ok(ObjectAcl::PublicRead),
+            "public-read-write" => Ok(ObjectAcl::PublicReadWrite),
+            _ => Err(Error::E(format!("unknown object acl: {}", s))),
+        }
+    }
+}
+
+impl fmt::Display for ObjectAcl {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            ObjectAcl::Default => "default",
+            ObjectAcl::Private => "private",
+            ObjectAcl::PublicRead => "public-read",
+            ObjectAcl::PublicReadWrite => "public-read-write",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+impl OSS {
+    // https://help.aliyun.com/document_detail/31986.html
+    pub async fn put_object_acl<S>(&self, object: S, acl: ObjectAcl) -> Result<(), Error>
+    where
+        S: AsRef<str>,
+    {
+        let object = object.as_ref();
+        self.with_context("put_object_acl", object, async {
+            let mut headers = HeaderMap::new();
+            headers.insert("x-oss-object-acl", acl.to_string().parse()?);
+            let params = [("acl", Some(String::new()))];
+            let resp = self
+                .signed_request(Method::PUT, object, &params, headers, Vec::new())
+                .await?;
+            if resp.status().is_success() {
+                Ok(())
+            } else {
+                Err(self
+                    .api_error(resp, |reason| {
+                        Error::Object(ObjectError::PutError {
+                            msg: format!("can not put object acl, reason: {:?}", reason),
+                        })
+                    })
+                    .await)
+            }
+        })
+        .await
+    }
+
+    // https://help.aliyun.com/document_detail/31987.html
+    pub async fn get_object_acl<S>(&self, object: S) -> Result<ObjectAcl, Error>
+    where
+        S: AsRef<str>,
+    {
+        let object = object.as_ref();
+        self.with_context("get_object_acl", object, async {
+            let params = [("acl", Some(String::new()))];
+            let resp = self.signed_get(object, &params, HeaderMap::new()).await?;
+            parse_grant(&self.xml_text(resp).await?)
+        })
+        .await
+    }
+}
+
+fn parse_grant(xml_str: &str) -> Result<ObjectAcl, Error> {
+    let mut reader = Reader::from_str(xml_str);
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event(&mut buf) {
+            Ok(Event::Start(ref e)) if e.name() == b"Grant" => {
+                return reader.read_text(e.name(), &mut Vec::new())?.parse();
+            }
+            Ok(Event::Eof) => return Err(Error::E("missing Grant element".to_owned())),
+            Err(e) => return Err(e.into()),
+            _ => (),
+        }
+        buf.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_grant() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<AccessControlPolicy>
+  <Owner>
+    <ID>0022012****</ID>
+    <DisplayName>0022012****</DisplayName>
+  </Owner>
+  <AccessControlList>
+    <Grant>public-read</Grant>
+  </AccessControlList>
+</AccessControlPolicy>"#;
+        assert_eq!(parse_grant(xml).unwrap(), ObjectAcl::PublicRead);
+        assert!(parse_grant("<AccessControlPolicy/>").is_err());
+    }
+
+    #[test]
+    fn test_object_acl_round_trip() {
+        for acl in [
+            ObjectAcl::Default,
+            ObjectAcl::Private,
+            ObjectAcl::PublicRead,
+            ObjectAcl::PublicReadWrite,
+        ] {
+            assert_eq!(acl.to_string().parse::<ObjectAcl>().unwrap(), acl);
+        }
+    }
+}
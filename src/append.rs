@@ -0,0 +1,112 @@
+use reqwest::header::{HeaderMap, CONTENT_LENGTH};
+use reqwest::Method;
+
+use crate::errors::{Error, ObjectError};
+use crate::oss::OSS;
+
+impl OSS {
+    // Appends `data` at `position`, which has to be the current length of the
+    // object (0 creates it). Returns the position of the next append.
+    // https://help.aliyun.com/document_detail/31981.html
+    pub async fn append_object<S>(
+        &self,
+        object: S,
+        position: u64,
+        data: &[u8],
+    ) -> Result<u64, Error>
+    where
+        S: AsRef<str>,
+    {
+        let object = object.as_ref();
+        self.with_context("append_object", object, async {
+            let mut headers = HeaderMap::new();
+            headers.insert(CONTENT_LENGTH, data.len().to_string().parse()?);
+            let params = [
+                ("append", Some(String::new())),
+                ("position", Some(position.to_string())),
+            ];
+            let resp = self
+                .signed_request(Method::POST, object, &params, headers, data.to_owned())
+                .await?;
+            if !resp.status().is_success() {
+                return Err(self
+                    .api_error(resp, |reason| {
+                        Error::Object(ObjectError::PutError {
+                            msg: format!("can not append object, reason: {:?}", reason),
+                        })
+                    })
+                    .await);
+            }
+            next_append_position(resp.headers())
+        })
+        .await
+    }
+
+    pub fn append_writer<S>(&self, object: S, position: u64) -> AppendWriter<'_>
+    where
+        S: AsRef<str>,
+    {
+        AppendWriter {
+            oss: self,
+            object: object.as_ref().to_owned(),
+            position,
+        }
+    }
+}
+
+// Appends to an Appendable object, carrying the position from one append to
+// the next.
+#[derive(Debug)]
+pub struct AppendWriter<'a> {
+    oss: &'a OSS,
+    object: String,
+    position: u64,
+}
+
+impl AppendWriter<'_> {
+    pub fn object(&self) -> &str {
+        &self.object
+    }
+
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    // On failure the position is left unchanged, e.g. for a retry.
+    pub async fn append(&mut self, data: &[u8]) -> Result<u64, Error> {
+        self.position = self
+            .oss
+            .append_object(&self.object, self.position, data)
+            .await?;
+        Ok(self.position)
+    }
+}
+
+fn next_append_position(headers: &HeaderMap) -> Result<u64, Error> {
+    headers
+        .get("x-oss-next-append-position")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| Error::E("missing x-oss-next-append-position header".to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_append_position() {
+        let mut headers = HeaderMap::new();
+        assert!(next_append_position(&headers).is_err());
+        headers.insert("x-oss-next-append-position", "1717".parse().unwrap());
+        assert_eq!(next_append_position(&headers).unwrap(), 1717);
+    }
+
+    #[test]
+    fn test_append_writer_position() {
+        let oss = OSS::new(String::new(), String::new(), String::new(), String::new());
+        let writer = oss.append_writer("log.txt", 42);
+        assert_eq!(writer.object(), "log.txt");
+        assert_eq!(writer.position(), 42);
+    }
+}
@@ -1,90 +1,619 @@
 use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
-use reqwest::header::{CONTENT_TYPE, DATE};
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, DATE};
 
 use base64::encode;
-use crypto::hmac::Hmac;
-use crypto::mac::Mac;
-use crypto::sha1::Sha1;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
 
 use super::oss::OSS;
+use crate::config::SignatureVersion;
+use crate::credentials::Credentials;
+use crate::utils::{path_encode, url_decode, url_encode};
+
+pub(crate) const SECURITY_TOKEN: &str = "x-oss-security-token";
 
 pub trait Auth {
+    // Returns the Authorization header for the request. Adds the STS security
+    // token to `headers` first when `credentials` carry one, so it is signed,
+    // and for V4 the x-oss-date and x-oss-content-sha256 headers as well.
+    // `oss_resources` are the sub-resources for V1 and the whole query for V4.
+    #[allow(clippy::too_many_arguments)]
     fn oss_sign(
         &self,
         verb: &str,
-        key_id: &str,
-        key_secret: &str,
+        credentials: &Credentials,
         bucket: &str,
         object: &str,
         oss_resources: &str,
-        headers: &HeaderMap,
+        headers: &mut HeaderMap,
     ) -> String;
 }
 
 impl Auth for OSS {
+    #[allow(clippy::too_many_arguments)]
     fn oss_sign(
         &self,
         verb: &str,
-        key_id: &str,
-        key_secret: &str,
+        credentials: &Credentials,
         bucket: &str,
         object: &str,
         oss_resources: &str,
-        headers: &HeaderMap,
+        headers: &mut HeaderMap,
     ) -> String {
-        let date = headers
-            .get(DATE)
-            .and_then(|d| Some(d.to_str().unwrap_or_default()))
-            .unwrap_or_default();
-        let content_type = headers
-            .get(CONTENT_TYPE)
-            .and_then(|c| Some(c.to_str().unwrap_or_default()))
-            .unwrap_or_default();
-        let content_md5 = headers
-            .get("Content-MD5")
-            .and_then(|md5| Some(encode(md5.to_str().unwrap_or_default())))
-            .unwrap_or_default();
-
-        let mut oss_headers: Vec<(&HeaderName, &HeaderValue)> = headers
-            .iter()
-            .filter(|(k, _)| k.as_str().contains("x-oss-"))
-            .collect();
-        oss_headers.sort_by(|a, b| a.0.to_string().cmp(&b.0.to_string()));
-        let mut oss_headers_str = String::new();
-        for (k, v) in oss_headers {
-            oss_headers_str += &format!(
-                "{}:{}\n",
-                k.to_owned().as_str(),
-                v.to_owned().to_str().unwrap_or("")
+        if let Some(token) = credentials.security_token() {
+            if let Ok(token) = HeaderValue::from_str(token) {
+                headers.insert(SECURITY_TOKEN, token);
+            }
+        }
+        if self.signature_version() == SignatureVersion::V4 {
+            let region = self.region().unwrap_or_default();
+            return sign_v4(
+                verb,
+                credentials,
+                region,
+                bucket,
+                object,
+                oss_resources,
+                headers,
+                self.now(),
             );
         }
+        let sign_str = string_to_sign(verb, bucket, object, oss_resources, headers);
+        let sign_str_base64 = hmac_sha1_base64(credentials.key_secret(), &sign_str);
 
-        let oss_resource_str = get_oss_resource_str(bucket, object, oss_resources);
-        let sign_str = format!(
-            "{}\n{}\n{}\n{}\n{}{}",
-            verb, content_md5, content_type, date, oss_headers_str, oss_resource_str
-        );
-
-        let mut hasher = Hmac::new(Sha1::new(), key_secret.as_bytes());
-        hasher.input(sign_str.as_bytes());
-        let sign_str_base64 = encode(hasher.result().code());
-
-        let authorization = format!("OSS {}:{}", key_id, sign_str_base64);
+        let authorization = format!("OSS {}:{}", credentials.key_id(), sign_str_base64);
         debug!("authorization: {}", authorization);
         authorization
     }
 }
 
+const V4_ALGORITHM: &str = "OSS4-HMAC-SHA256";
+const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+
+// https://help.aliyun.com/document_detail/2412367.html
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn sign_v4(
+    verb: &str,
+    credentials: &Credentials,
+    region: &str,
+    bucket: &str,
+    object: &str,
+    query: &str,
+    headers: &mut HeaderMap,
+    now: DateTime<Utc>,
+) -> String {
+    let timestamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = &timestamp[..8];
+    headers.insert("x-oss-date", HeaderValue::from_str(&timestamp).unwrap());
+    headers.insert(
+        "x-oss-content-sha256",
+        HeaderValue::from_static(UNSIGNED_PAYLOAD),
+    );
+
+    let scope = format!("{}/{}/oss/aliyun_v4_request", date, region);
+    let canonical_request = canonical_request_v4(verb, bucket, object, query, headers);
+    let sign_str = format!(
+        "{}\n{}\n{}\n{}",
+        V4_ALGORITHM,
+        timestamp,
+        scope,
+        sha256_hex(&canonical_request)
+    );
+
+    let mut key = hmac_sha256(
+        format!("aliyun_v4{}", credentials.key_secret()).as_bytes(),
+        date,
+    );
+    for part in &[region, "oss", "aliyun_v4_request"] {
+        key = hmac_sha256(&key, part);
+    }
+    let signature: String = hmac_sha256(&key, &sign_str)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+
+    let authorization = format!(
+        "{} Credential={}/{},Signature={}",
+        V4_ALGORITHM,
+        credentials.key_id(),
+        scope,
+        signature
+    );
+    debug!("authorization: {}", authorization);
+    authorization
+}
+
+pub(crate) fn canonical_request_v4(
+    verb: &str,
+    bucket: &str,
+    object: &str,
+    query: &str,
+    headers: &HeaderMap,
+) -> String {
+    let uri = if bucket.is_empty() {
+        "/".to_owned()
+    } else {
+        format!("/{}/{}", bucket, object)
+    };
+    let uri = path_encode(&uri);
+
+    // The query may arrive percent-encoded or raw, it is decoded first so
+    // both canonicalize the same.
+    let mut params: Vec<String> = query
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .map(|p| match p.split_once('=') {
+            Some((k, v)) => format!(
+                "{}={}",
+                url_encode(&url_decode(k)),
+                url_encode(&url_decode(v))
+            ),
+            None => url_encode(&url_decode(p)),
+        })
+        .collect();
+    params.sort();
+
+    let mut signed: Vec<(&str, &str)> = headers
+        .iter()
+        .filter(|(k, _)| {
+            k.as_str().starts_with("x-oss-") || *k == CONTENT_TYPE || k.as_str() == "content-md5"
+        })
+        .map(|(k, v)| (k.as_str(), v.to_str().unwrap_or_default().trim()))
+        .collect();
+    signed.sort();
+    let canonical_headers: String = signed
+        .iter()
+        .map(|(k, v)| format!("{}:{}\n", k, v))
+        .collect();
+
+    // The empty line holds the additional signed headers, none are used.
+    format!(
+        "{}\n{}\n{}\n{}\n\n{}",
+        verb,
+        uri,
+        params.join("&"),
+        canonical_headers,
+        UNSIGNED_PAYLOAD
+    )
+}
+
+fn sha256_hex(s: &str) -> String {
+    format!("{:x}", Sha256::digest(s.as_bytes()))
+}
+
+fn hmac_sha256(key: &[u8], s: &str) -> Vec<u8> {
+    // HMAC takes keys of any length.
+    let mut hmac = Hmac::<Sha256>::new_from_slice(key).unwrap();
+    hmac.update(s.as_bytes());
+    hmac.finalize().into_bytes().to_vec()
+}
+
+pub(crate) fn string_to_sign(
+    verb: &str,
+    bucket: &str,
+    object: &str,
+    oss_resources: &str,
+    headers: &HeaderMap,
+) -> String {
+    let date = headers
+        .get(DATE)
+        .map(|d| d.to_str().unwrap_or_default())
+        .unwrap_or_default();
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .map(|c| c.to_str().unwrap_or_default())
+        .unwrap_or_default();
+    let content_md5 = headers
+        .get("Content-MD5")
+        .map(|md5| md5.to_str().unwrap_or_default())
+        .unwrap_or_default();
+
+    let oss_headers_str = canonicalized_oss_headers(headers);
+
+    let oss_resource_str = get_oss_resource_str(bucket, object, oss_resources);
+    format!(
+        "{}\n{}\n{}\n{}\n{}{}",
+        verb, content_md5, content_type, date, oss_headers_str, oss_resource_str
+    )
+}
+
+// Checks the `Authorization` header of a V1 signed request against the
+// signature computed with `key_secret`, e.g. in a proxy or test server.
+pub fn verify_signature(
+    key_id: &str,
+    key_secret: &str,
+    verb: &str,
+    bucket: &str,
+    object: &str,
+    oss_resources: &str,
+    headers: &HeaderMap,
+) -> bool {
+    let authorization = match headers.get(AUTHORIZATION).and_then(|a| a.to_str().ok()) {
+        Some(a) => a,
+        None => return false,
+    };
+    let sign_str = string_to_sign(verb, bucket, object, oss_resources, headers);
+    let expected = format!("OSS {}:{}", key_id, hmac_sha1_base64(key_secret, &sign_str));
+    // Constant time comparison, the signature must not leak through timing.
+    expected.len() == authorization.len()
+        && expected
+            .bytes()
+            .zip(authorization.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+// Every x-oss-* header as a `name:value` line, sorted by name. Names are
+// lowercase already, values are trimmed and repeated headers joined by
+// commas into one line.
+pub(crate) fn canonicalized_oss_headers(headers: &HeaderMap) -> String {
+    let mut names: Vec<&HeaderName> = headers
+        .keys()
+        .filter(|k| k.as_str().starts_with("x-oss-"))
+        .collect();
+    names.sort_by_key(|k| k.as_str());
+    let mut oss_headers_str = String::new();
+    for name in names {
+        let values: Vec<&str> = headers
+            .get_all(name)
+            .iter()
+            .map(|v| v.to_str().unwrap_or_default().trim())
+            .collect();
+        oss_headers_str += &format!("{}:{}\n", name, values.join(","));
+    }
+    oss_headers_str
+}
+
+#[inline]
+pub(crate) fn hmac_sha1_base64(key_secret: &str, sign_str: &str) -> String {
+    let mut hmac = Hmac::<Sha1>::new_from_slice(key_secret.as_bytes()).unwrap();
+    hmac.update(sign_str.as_bytes());
+    encode(hmac.finalize().into_bytes())
+}
+
 #[inline]
 fn get_oss_resource_str(bucket: &str, object: &str, oss_resources: &str) -> String {
-    let oss_resources = if oss_resources != "" {
+    let oss_resources = if !oss_resources.is_empty() {
         String::from("?") + oss_resources
     } else {
         String::new()
     };
-    if bucket == "" {
+    if bucket.is_empty() {
         format!("/{}{}", bucket, oss_resources)
     } else {
         format!("/{}/{}{}", bucket, object, oss_resources)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY_ID: &str = "LTAI4GB6fwDbFvCBM5LkmQ1D";
+    const KEY_SECRET: &str = "nTnZ7Vm1cPWEMWtRmoTYpfLKjGnPAz";
+    const DATE: &str = "Wed, 28 Dec 2022 10:27:41 GMT";
+
+    struct Vector {
+        verb: &'static str,
+        bucket: &'static str,
+        object: &'static str,
+        resources: &'static str,
+        headers: &'static [(&'static str, &'static str)],
+        string_to_sign: &'static str,
+        signature: &'static str,
+    }
+
+    // Expected values were computed independently from the OSS V1 signature
+    // documentation: https://help.aliyun.com/document_detail/31951.html
+    const VECTORS: [Vector; 12] = [
+        Vector {
+            verb: "GET",
+            bucket: "",
+            object: "",
+            resources: "",
+            headers: &[],
+            string_to_sign: "GET\n\n\nWed, 28 Dec 2022 10:27:41 GMT\n/",
+            signature: "w65a/ErvD1sJgHgY1OZUPidiFac=",
+        },
+        Vector {
+            verb: "GET",
+            bucket: "examplebucket",
+            object: "example.txt",
+            resources: "",
+            headers: &[],
+            string_to_sign: "GET\n\n\nWed, 28 Dec 2022 10:27:41 GMT\n/examplebucket/example.txt",
+            signature: "PWvFECAdaLHf5T9Lbpb8L2h6MB8=",
+        },
+        Vector {
+            verb: "DELETE",
+            bucket: "examplebucket",
+            object: "example.txt",
+            resources: "tagging",
+            headers: &[],
+            string_to_sign:
+                "DELETE\n\n\nWed, 28 Dec 2022 10:27:41 GMT\n/examplebucket/example.txt?tagging",
+            signature: "+8LDA7Y7+Dc3oxi4wkNB/a5/VMs=",
+        },
+        Vector {
+            verb: "GET",
+            bucket: "examplebucket",
+            object: "example.txt",
+            resources: "acl",
+            headers: &[],
+            string_to_sign: "GET\n\n\nWed, 28 Dec 2022 10:27:41 GMT\n/examplebucket/example.txt?acl",
+            signature: "ioPGXty99PLNcHbzZoJ/xbipWYM=",
+        },
+        Vector {
+            verb: "PUT",
+            bucket: "examplebucket",
+            object: "example.txt",
+            resources: "acl",
+            headers: &[("x-oss-object-acl", "private")],
+            string_to_sign: "PUT\n\n\nWed, 28 Dec 2022 10:27:41 GMT\nx-oss-object-acl:private\n/examplebucket/example.txt?acl",
+            signature: "55ixWtkJUOzIdXUC7ZYZQ0XyIUE=",
+        },
+        Vector {
+            verb: "GET",
+            bucket: "examplebucket",
+            object: "",
+            resources: "acl",
+            headers: &[],
+            string_to_sign: "GET\n\n\nWed, 28 Dec 2022 10:27:41 GMT\n/examplebucket/?acl",
+            signature: "/pOiW2Bcrs116p80g+x9KEgJHAw=",
+        },
+        Vector {
+            verb: "POST",
+            bucket: "examplebucket",
+            object: "example.txt",
+            resources: "uploads",
+            headers: &[],
+            string_to_sign:
+                "POST\n\n\nWed, 28 Dec 2022 10:27:41 GMT\n/examplebucket/example.txt?uploads",
+            signature: "yHuFg4FxOyEXZ6ajhqrigm+OZsI=",
+        },
+        Vector {
+            verb: "PUT",
+            bucket: "examplebucket",
+            object: "example.txt",
+            resources: "partNumber=1&uploadId=0004B9895DBBB6EC98E",
+            headers: &[],
+            string_to_sign: "PUT\n\n\nWed, 28 Dec 2022 10:27:41 GMT\n/examplebucket/example.txt?partNumber=1&uploadId=0004B9895DBBB6EC98E",
+            signature: "IYlgWJcFisN2aBTsfgw0/YiR+xc=",
+        },
+        Vector {
+            verb: "DELETE",
+            bucket: "examplebucket",
+            object: "example.txt",
+            resources: "uploadId=0004B9895DBBB6EC98E",
+            headers: &[],
+            string_to_sign: "DELETE\n\n\nWed, 28 Dec 2022 10:27:41 GMT\n/examplebucket/example.txt?uploadId=0004B9895DBBB6EC98E",
+            signature: "ilulCMywbRaEJmpxCbgeljbLSTo=",
+        },
+        Vector {
+            verb: "PUT",
+            bucket: "examplebucket",
+            object: "example.txt",
+            resources: "",
+            headers: &[
+                ("x-oss-storage-class", "IA"),
+                ("content-type", "text/plain"),
+                ("x-oss-meta-author", "alice"),
+            ],
+            string_to_sign: "PUT\n\ntext/plain\nWed, 28 Dec 2022 10:27:41 GMT\nx-oss-meta-author:alice\nx-oss-storage-class:IA\n/examplebucket/example.txt",
+            signature: "ivOGqMWHaVAtXmgGSA8BZ212Owk=",
+        },
+        Vector {
+            verb: "PUT",
+            bucket: "examplebucket",
+            object: "example.txt",
+            resources: "",
+            headers: &[
+                ("X-OSS-Server-Side-Encryption", "AES256"),
+                ("x-oss-meta-tag", "a"),
+                ("cache-control", "no-cache"),
+                ("x-oss-meta-tag", " b "),
+            ],
+            string_to_sign: "PUT\n\n\nWed, 28 Dec 2022 10:27:41 GMT\nx-oss-meta-tag:a,b\nx-oss-server-side-encryption:AES256\n/examplebucket/example.txt",
+            signature: "krz4uP85guJHXk6G3/SIgk+lWlM=",
+        },
+        Vector {
+            verb: "POST",
+            bucket: "examplebucket",
+            object: "",
+            resources: "delete",
+            headers: &[
+                ("content-md5", "eB5eJF1ptWaXm4bijSPyxw=="),
+                ("content-type", "application/xml"),
+            ],
+            string_to_sign: "POST\neB5eJF1ptWaXm4bijSPyxw==\napplication/xml\nWed, 28 Dec 2022 10:27:41 GMT\n/examplebucket/?delete",
+            signature: "UGfjk9h1ouLFlwefZw8ib1cv8t0=",
+        },
+    ];
+
+    fn headers(vector: &Vector) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(reqwest::header::DATE, DATE.parse().unwrap());
+        for (k, v) in vector.headers {
+            headers.append(
+                HeaderName::from_bytes(k.as_bytes()).unwrap(),
+                v.parse().unwrap(),
+            );
+        }
+        headers
+    }
+
+    #[test]
+    fn test_signature_vectors() {
+        for vector in VECTORS.iter() {
+            let mut headers = headers(vector);
+            let sign_str = string_to_sign(
+                vector.verb,
+                vector.bucket,
+                vector.object,
+                vector.resources,
+                &headers,
+            );
+            assert_eq!(sign_str, vector.string_to_sign);
+            assert_eq!(hmac_sha1_base64(KEY_SECRET, &sign_str), vector.signature);
+
+            let authorization = format!("OSS {}:{}", KEY_ID, vector.signature);
+            headers.insert(AUTHORIZATION, authorization.parse().unwrap());
+            assert!(verify_signature(
+                KEY_ID,
+                KEY_SECRET,
+                vector.verb,
+                vector.bucket,
+                vector.object,
+                vector.resources,
+                &headers
+            ));
+        }
+    }
+
+    #[test]
+    fn test_verify_signature_rejects_tampering() {
+        let vector = &VECTORS[2];
+        let mut headers = headers(vector);
+        let authorization = format!("OSS {}:{}", KEY_ID, vector.signature);
+        headers.insert(AUTHORIZATION, authorization.parse().unwrap());
+        // Same signature replayed against a different sub-resource.
+        assert!(!verify_signature(
+            KEY_ID,
+            KEY_SECRET,
+            "DELETE",
+            vector.bucket,
+            vector.object,
+            "acl",
+            &headers
+        ));
+        assert!(!verify_signature(
+            KEY_ID,
+            KEY_SECRET,
+            vector.verb,
+            vector.bucket,
+            vector.object,
+            vector.resources,
+            &HeaderMap::new()
+        ));
+    }
+
+    #[test]
+    fn test_sign_with_security_token() {
+        let oss_instance = OSS::new_with_sts(
+            KEY_ID.to_owned(),
+            KEY_SECRET.to_owned(),
+            "sts-token".to_owned(),
+            "oss-cn-hangzhou.aliyuncs.com".to_owned(),
+            "examplebucket".to_owned(),
+        );
+        let mut headers = HeaderMap::new();
+        headers.insert(reqwest::header::DATE, DATE.parse().unwrap());
+        let credentials = Credentials::new(
+            KEY_ID.to_owned(),
+            KEY_SECRET.to_owned(),
+            oss_instance.security_token().map(|t| t.to_owned()),
+        );
+        let authorization = oss_instance.oss_sign(
+            "GET",
+            &credentials,
+            "examplebucket",
+            "example.txt",
+            "",
+            &mut headers,
+        );
+        assert_eq!(headers.get(SECURITY_TOKEN).unwrap(), "sts-token");
+        headers.insert(AUTHORIZATION, authorization.parse().unwrap());
+        assert!(verify_signature(
+            KEY_ID,
+            KEY_SECRET,
+            "GET",
+            "examplebucket",
+            "example.txt",
+            "",
+            &headers
+        ));
+        assert!(
+            string_to_sign("GET", "examplebucket", "example.txt", "", &headers)
+                .contains("\nx-oss-security-token:sts-token\n")
+        );
+    }
+
+    // Expected values were computed independently from the OSS V4 signature
+    // documentation: https://help.aliyun.com/document_detail/2412367.html
+    #[test]
+    fn test_sign_v4() {
+        let credentials = Credentials::new(KEY_ID.to_owned(), KEY_SECRET.to_owned(), None);
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, "text/plain".parse().unwrap());
+        headers.insert("x-oss-meta-a", " b ".parse().unwrap());
+        // Neither x-oss-* nor a default signed header.
+        headers.insert("cache-control", "no-cache".parse().unwrap());
+        let now = DateTime::parse_from_rfc3339("2022-12-28T10:27:41Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let authorization = sign_v4(
+            "PUT",
+            &credentials,
+            "cn-hangzhou",
+            "examplebucket",
+            "exampleobject a.txt",
+            "prefix=a/b&acl",
+            &mut headers,
+            now,
+        );
+        assert_eq!(headers.get("x-oss-date").unwrap(), "20221228T102741Z");
+        assert_eq!(
+            canonical_request_v4(
+                "PUT",
+                "examplebucket",
+                "exampleobject a.txt",
+                "prefix=a%2Fb&acl",
+                &headers
+            ),
+            "PUT\n/examplebucket/exampleobject%20a.txt\nacl&prefix=a%2Fb\n\
+             content-type:text/plain\nx-oss-content-sha256:UNSIGNED-PAYLOAD\n\
+             x-oss-date:20221228T102741Z\nx-oss-meta-a:b\n\n\nUNSIGNED-PAYLOAD"
+        );
+        assert_eq!(
+            authorization,
+            "OSS4-HMAC-SHA256 Credential=LTAI4GB6fwDbFvCBM5LkmQ1D/20221228/cn-hangzhou/oss/aliyun_v4_request,\
+             Signature=9aeb2bbd759bbff21b64795b5435986208fe1caf58aff763a4bc9a07f8129e99"
+        );
+    }
+
+    #[test]
+    fn test_canonical_request_v4_service() {
+        assert_eq!(
+            canonical_request_v4("GET", "", "", "", &HeaderMap::new()),
+            "GET\n/\n\n\n\nUNSIGNED-PAYLOAD"
+        );
+    }
+
+    #[test]
+    fn test_client_signs_v4() {
+        let oss_instance = OSS::builder(
+            KEY_ID.to_owned(),
+            KEY_SECRET.to_owned(),
+            "oss-cn-hangzhou.aliyuncs.com".to_owned(),
+            "examplebucket".to_owned(),
+        )
+        .signature_version(SignatureVersion::V4)
+        .build();
+        assert_eq!(oss_instance.region(), Some("cn-hangzhou"));
+        let credentials = Credentials::new(KEY_ID.to_owned(), KEY_SECRET.to_owned(), None);
+        let mut headers = HeaderMap::new();
+        let authorization = oss_instance.oss_sign(
+            "GET",
+            &credentials,
+            "examplebucket",
+            "example.txt",
+            "",
+            &mut headers,
+        );
+        assert!(authorization.starts_with("OSS4-HMAC-SHA256 Credential=LTAI4GB6fwDbFvCBM5LkmQ1D/"));
+        assert!(authorization.contains("/cn-hangzhou/oss/aliyun_v4_request,Signature="));
+        assert!(headers.contains_key("x-oss-date"));
+    }
+}
@@ -0,0 +1,245 @@
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::future::Future;
+use tokio::runtime::{Builder, Runtime};
+
+use crate::bucket::{ListObjects, ListObjectsOptions};
+use crate::errors::Error;
+use crate::object::{
+    CompleteMultipartUploadResult, DeleteObjectResult, HeadObjectResult, PutObjectResult,
+};
+use crate::oss::{self, CompleteMultipartUpload, Part};
+use crate::tuning::Concurrency;
+
+// Synchronous client for programs that don't run an async runtime, such as
+// CLI tools and build scripts. Enabled by the `blocking` feature.
+//
+// Like `reqwest::blocking`, it drives the async client on a runtime of its
+// own, so signing, retries and checksums work exactly as in `oss::OSS`.
+// Calling it from inside an async runtime panics; use `oss::OSS` there.
+#[derive(Debug)]
+pub struct OSS {
+    inner: oss::OSS,
+    runtime: Runtime,
+}
+
+impl OSS {
+    pub fn new(
+        key_id: String,
+        key_secret: String,
+        endpoint: String,
+        bucket: String,
+    ) -> Result<Self, Error> {
+        Self::from_async(oss::OSS::builder(key_id, key_secret, endpoint, bucket).try_build()?)
+    }
+
+    // Wraps a client set up with `oss::OSS::builder`.
+    pub fn from_async(inner: oss::OSS) -> Result<Self, Error> {
+        let runtime = Builder::new_current_thread().enable_all().build()?;
+        Ok(OSS { inner, runtime })
+    }
+
+    // The wrapped client, to reach operations not mirrored here through
+    // `block_on`.
+    pub fn as_async(&self) -> &oss::OSS {
+        &self.inner
+    }
+
+    // Runs `future` to completion on this client's runtime.
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    pub fn get_object<S>(
+        &self,
+        object: S,
+        headers: Option<HashMap<S, S>>,
+        resources: Option<HashMap<S, Option<S>>>,
+    ) -> Result<Bytes, Error>
+    where
+        S: AsRef<str>,
+    {
+        self.block_on(self.inner.get_object(object, headers, resources))
+    }
+
+    pub fn head_object<S>(
+        &self,
+        object: S,
+        headers: Option<HashMap<S, S>>,
+        resources: Option<HashMap<S, Option<S>>>,
+    ) -> Result<HeadObjectResult, Error>
+    where
+        S: AsRef<str>,
+    {
+        self.block_on(self.inner.head_object(object, headers, resources))
+    }
+
+    pub fn put_object_from_buffer<S1, S2, H, R>(
+        &self,
+        buf: &[u8],
+        object: S1,
+        headers: H,
+        resources: R,
+    ) -> Result<PutObjectResult, Error>
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>,
+        H: Into<Option<HashMap<S2, S2>>>,
+        R: Into<Option<HashMap<S2, Option<S2>>>>,
+    {
+        self.block_on(
+            self.inner
+                .put_object_from_buffer(buf, object, headers, resources),
+        )
+    }
+
+    pub fn put_object_from_file<S1, S2, S3, H, R>(
+        &self,
+        file: S1,
+        object_name: S2,
+        headers: H,
+        resources: R,
+    ) -> Result<PutObjectResult, Error>
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>,
+        S3: AsRef<str>,
+        H: Into<Option<HashMap<S3, S3>>>,
+        R: Into<Option<HashMap<S3, Option<S3>>>>,
+    {
+        self.block_on(
+            self.inner
+                .put_object_from_file(file, object_name, headers, resources),
+        )
+    }
+
+    pub fn delete_object<S>(&self, object_name: S) -> Result<DeleteObjectResult, Error>
+    where
+        S: AsRef<str>,
+    {
+        self.block_on(self.inner.delete_object(object_name))
+    }
+
+    pub fn list_objects(&self, options: &ListObjectsOptions) -> Result<ListObjects, Error> {
+        self.block_on(self.inner.list_objects(options))
+    }
+
+    pub fn initiate_multipart_upload<S2, S3, H>(
+        &self,
+        object_name: S2,
+        headers: H,
+    ) -> Result<String, Error>
+    where
+        S2: AsRef<str>,
+        S3: AsRef<str>,
+        H: Into<Option<HashMap<S3, S3>>>,
+    {
+        self.block_on(self.inner.initiate_multipart_upload(object_name, headers))
+    }
+
+    pub fn upload_part<S>(
+        &self,
+        object_name: S,
+        upload_id: &str,
+        part_number: u64,
+        data: Vec<u8>,
+    ) -> Result<Part, Error>
+    where
+        S: AsRef<str>,
+    {
+        self.block_on(
+            self.inner
+                .upload_part(object_name, upload_id, part_number, data),
+        )
+    }
+
+    pub fn complete_multipart_upload<S1, S3, H>(
+        &self,
+        object_name: S1,
+        upload_id: String,
+        complete: CompleteMultipartUpload,
+        headers: H,
+    ) -> Result<CompleteMultipartUploadResult, Error>
+    where
+        S1: AsRef<str>,
+        S3: AsRef<str>,
+        H: Into<Option<HashMap<S3, S3>>>,
+    {
+        self.block_on(self.inner.complete_multipart_upload(
+            object_name,
+            upload_id,
+            complete,
+            headers,
+        ))
+    }
+
+    pub fn abort_multipart_upload<S1>(
+        &self,
+        object_name: S1,
+        upload_id: String,
+    ) -> Result<(), Error>
+    where
+        S1: AsRef<str>,
+    {
+        self.block_on(self.inner.abort_multipart_upload(object_name, upload_id))
+    }
+
+    pub fn chunk_upload_by_size<S1, H>(
+        &self,
+        object_name: S1,
+        file: S1,
+        chunk_size: u64,
+        headers: H,
+    ) -> Result<(), Error>
+    where
+        S1: AsRef<str>,
+        H: Into<Option<HashMap<S1, S1>>>,
+    {
+        self.block_on(
+            self.inner
+                .chunk_upload_by_size(object_name, file, chunk_size, headers),
+        )
+    }
+
+    // Uploads the parts concurrently, on this client's single thread.
+    pub fn parallel_upload<S1, H>(
+        &self,
+        object_name: S1,
+        file: S1,
+        chunk_size: u64,
+        concurrency: Concurrency,
+        headers: H,
+    ) -> Result<(), Error>
+    where
+        S1: AsRef<str>,
+        H: Into<Option<HashMap<S1, S1>>>,
+    {
+        self.block_on(self.inner.parallel_upload(
+            object_name,
+            file,
+            chunk_size,
+            concurrency,
+            headers,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocking_upload_part() {
+        let client = OSS::new(
+            "xxx".to_string(),
+            "xxx".to_string(),
+            "xxx.aliyuncs.com".to_string(),
+            "xxx".to_string(),
+        )
+        .unwrap();
+        assert_eq!(client.as_async().bucket(), "xxx");
+        // Rejected before anything is sent.
+        let err = client.upload_part("big.bin", "id", 0, vec![]).unwrap_err();
+        assert!(err.to_string().contains("part number 0"));
+    }
+}
@@ -1,3 +1,8 @@
+use chrono::{DateTime, Utc};
+
+use crate::errors::Error;
+use crate::object::StorageClass;
+
 #[derive(Clone, Debug)]
 pub struct ListBuckets {
     prefix: String,
@@ -13,6 +18,7 @@ pub struct ListBuckets {
 }
 
 impl ListBuckets {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         prefix: String,
         marker: String,
@@ -121,3 +127,281 @@ impl Bucket {
         &self.storage_class
     }
 }
+
+#[derive(Clone, Debug, Default)]
+pub struct ListObjectsOptions {
+    pub prefix: Option<String>,
+    pub marker: Option<String>,
+    pub max_keys: Option<u32>,
+    pub delimiter: Option<String>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ListObjects {
+    name: String,
+    prefix: String,
+    marker: String,
+    max_keys: String,
+    delimiter: String,
+    is_truncated: bool,
+    next_marker: String,
+
+    objects: Vec<Object>,
+    common_prefixes: Vec<String>,
+}
+
+impl ListObjects {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        name: String,
+        prefix: String,
+        marker: String,
+        max_keys: String,
+        delimiter: String,
+        is_truncated: bool,
+        next_marker: String,
+        objects: Vec<Object>,
+        common_prefixes: Vec<String>,
+    ) -> Self {
+        ListObjects {
+            name,
+            prefix,
+            marker,
+            max_keys,
+            delimiter,
+            is_truncated,
+            next_marker,
+            objects,
+            common_prefixes,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    pub fn marker(&self) -> &str {
+        &self.marker
+    }
+
+    pub fn max_keys(&self) -> &str {
+        &self.max_keys
+    }
+
+    pub fn delimiter(&self) -> &str {
+        &self.delimiter
+    }
+
+    pub fn is_truncated(&self) -> bool {
+        self.is_truncated
+    }
+
+    pub fn next_marker(&self) -> &str {
+        &self.next_marker
+    }
+
+    pub fn objects(&self) -> &Vec<Object> {
+        &self.objects
+    }
+
+    pub fn common_prefixes(&self) -> &Vec<String> {
+        &self.common_prefixes
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Object {
+    key: String,
+    last_modified: String,
+    etag: String,
+    size: u64,
+    storage_class: String,
+    owner: Owner,
+}
+
+impl Object {
+    pub fn new(
+        key: String,
+        last_modified: String,
+        etag: String,
+        size: u64,
+        storage_class: String,
+        owner: Owner,
+    ) -> Self {
+        Object {
+            key,
+            last_modified,
+            etag,
+            size,
+            storage_class,
+            owner,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn last_modified(&self) -> &str {
+        &self.last_modified
+    }
+
+    // LastModified parsed from its ISO 8601 form, e.g. 2012-02-24T08:42:32.000Z.
+    pub fn last_modified_time(&self) -> Result<DateTime<Utc>, Error> {
+        DateTime::parse_from_rfc3339(&self.last_modified)
+            .map(|t| t.with_timezone(&Utc))
+            .map_err(|e| {
+                Error::E(format!(
+                    "invalid LastModified {:?}: {}",
+                    self.last_modified, e
+                ))
+            })
+    }
+
+    pub fn etag(&self) -> &str {
+        &self.etag
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn storage_class(&self) -> &str {
+        &self.storage_class
+    }
+
+    // None for classes this version does not know of.
+    pub fn storage_class_kind(&self) -> Option<StorageClass> {
+        self.storage_class.parse().ok()
+    }
+
+    pub fn owner(&self) -> &Owner {
+        &self.owner
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Owner {
+    id: String,
+    display_name: String,
+}
+
+impl Owner {
+    pub fn new(id: String, display_name: String) -> Self {
+        Owner { id, display_name }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn display_name(&self) -> &str {
+        &self.display_name
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ListObjectsV2Options {
+    pub prefix: Option<String>,
+    pub continuation_token: Option<String>,
+    pub start_after: Option<String>,
+    pub max_keys: Option<u32>,
+    pub delimiter: Option<String>,
+    pub fetch_owner: bool,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ListObjectsV2 {
+    name: String,
+    prefix: String,
+    start_after: String,
+    continuation_token: String,
+    next_continuation_token: String,
+    max_keys: String,
+    delimiter: String,
+    key_count: u64,
+    is_truncated: bool,
+
+    objects: Vec<Object>,
+    common_prefixes: Vec<String>,
+}
+
+impl ListObjectsV2 {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        name: String,
+        prefix: String,
+        start_after: String,
+        continuation_token: String,
+        next_continuation_token: String,
+        max_keys: String,
+        delimiter: String,
+        key_count: u64,
+        is_truncated: bool,
+        objects: Vec<Object>,
+        common_prefixes: Vec<String>,
+    ) -> Self {
+        ListObjectsV2 {
+            name,
+            prefix,
+            start_after,
+            continuation_token,
+            next_continuation_token,
+            max_keys,
+            delimiter,
+            key_count,
+            is_truncated,
+            objects,
+            common_prefixes,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    pub fn start_after(&self) -> &str {
+        &self.start_after
+    }
+
+    pub fn continuation_token(&self) -> &str {
+        &self.continuation_token
+    }
+
+    // Pass as `continuation_token` to fetch the next page while `is_truncated`.
+    pub fn next_continuation_token(&self) -> &str {
+        &self.next_continuation_token
+    }
+
+    pub fn max_keys(&self) -> &str {
+        &self.max_keys
+    }
+
+    pub fn delimiter(&self) -> &str {
+        &self.delimiter
+    }
+
+    pub fn key_count(&self) -> u64 {
+        self.key_count
+    }
+
+    pub fn is_truncated(&self) -> bool {
+        self.is_truncated
+    }
+
+    pub fn objects(&self) -> &Vec<Object> {
+        &self.objects
+    }
+
+    pub fn common_prefixes(&self) -> &Vec<String> {
+        &self.common_prefixes
+    }
+}
@@ -0,0 +1,336 @@
+use futures::stream::{self, StreamExt};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+
+use crate::copy::{CopyObjectResult, CopyOptions};
+use crate::errors::Error;
+use crate::object::DeleteObjectResult;
+use crate::oss::OSS;
+use crate::rt;
+
+// What a bulk operation does when one of its keys fails.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FailurePolicy {
+    // Stop scheduling keys after the first failure, pending keys are cancelled.
+    FailFast,
+    // Keep going and report every failure at the end.
+    ContinueAndCollect,
+    // Retry a failing key up to `attempts` times in total, then skip it and go on.
+    RetryThenSkip { attempts: u32 },
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct BulkOptions {
+    pub concurrency: usize,
+    pub policy: FailurePolicy,
+}
+
+impl Default for BulkOptions {
+    fn default() -> Self {
+        BulkOptions {
+            concurrency: 8,
+            policy: FailurePolicy::FailFast,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum KeyOutcome<T> {
+    Succeeded(T),
+    Failed(Error),
+    // Never attempted, or abandoned in flight, because of a fail-fast failure.
+    Cancelled,
+}
+
+// Per key outcomes of a bulk operation, in the order the keys were scheduled.
+#[derive(Debug)]
+pub struct BulkResult<T> {
+    outcomes: Vec<(String, KeyOutcome<T>)>,
+}
+
+impl<T> BulkResult<T> {
+    pub fn outcomes(&self) -> &[(String, KeyOutcome<T>)] {
+        &self.outcomes
+    }
+
+    pub fn into_outcomes(self) -> Vec<(String, KeyOutcome<T>)> {
+        self.outcomes
+    }
+
+    pub fn succeeded(&self) -> impl Iterator<Item = (&str, &T)> {
+        self.outcomes.iter().filter_map(|(k, o)| match o {
+            KeyOutcome::Succeeded(v) => Some((k.as_str(), v)),
+            _ => None,
+        })
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = (&str, &Error)> {
+        self.outcomes.iter().filter_map(|(k, o)| match o {
+            KeyOutcome::Failed(e) => Some((k.as_str(), e)),
+            _ => None,
+        })
+    }
+
+    pub fn cancelled(&self) -> impl Iterator<Item = &str> {
+        self.outcomes.iter().filter_map(|(k, o)| match o {
+            KeyOutcome::Cancelled => Some(k.as_str()),
+            _ => None,
+        })
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.outcomes
+            .iter()
+            .all(|(_, o)| matches!(o, KeyOutcome::Succeeded(_)))
+    }
+}
+
+pub(crate) async fn run_bulk<T, F, Fut>(
+    keys: Vec<String>,
+    options: BulkOptions,
+    f: F,
+) -> BulkResult<T>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<T, Error>>,
+{
+    let attempts = match options.policy {
+        FailurePolicy::RetryThenSkip { attempts } => attempts.max(1),
+        _ => 1,
+    };
+    let f = &f;
+    let mut results: Vec<Option<KeyOutcome<T>>> = keys.iter().map(|_| None).collect();
+    let mut tasks = stream::iter(keys.iter().cloned().enumerate())
+        .map(|(i, key)| async move {
+            let mut attempt = 1;
+            loop {
+                match f(key.clone()).await {
+                    Ok(v) => return (i, Ok(v)),
+                    Err(e) if attempt < attempts => {
+                        debug!("bulk key {} failed on attempt {}: {}", key, attempt, e);
+                        attempt += 1;
+                    }
+                    Err(e) => return (i, Err(e)),
+                }
+            }
+        })
+        .buffer_unordered(options.concurrency.max(1));
+
+    while let Some((i, result)) = tasks.next().await {
+        match result {
+            Ok(v) => results[i] = Some(KeyOutcome::Succeeded(v)),
+            Err(e) => {
+                results[i] = Some(KeyOutcome::Failed(e));
+                if options.policy == FailurePolicy::FailFast {
+                    break;
+                }
+            }
+        }
+    }
+    // Dropping the stream cancels whatever is still in flight.
+    drop(tasks);
+
+    BulkResult {
+        outcomes: keys
+            .into_iter()
+            .zip(results)
+            .map(|(k, o)| (k, o.unwrap_or(KeyOutcome::Cancelled)))
+            .collect(),
+    }
+}
+
+impl OSS {
+    // Uploads every file below `dir`, keyed by `prefix` followed by the path
+    // relative to `dir` with `/` separators.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn upload_dir<P, S>(
+        &self,
+        dir: P,
+        prefix: S,
+        options: BulkOptions,
+    ) -> Result<BulkResult<()>, Error>
+    where
+        P: AsRef<Path>,
+        S: AsRef<str>,
+    {
+        let (keys, paths) = local_keys(dir.as_ref(), prefix.as_ref()).await?;
+        let paths = &paths;
+        Ok(run_bulk(keys, options, |key| async move {
+            let file = paths[&key].to_string_lossy();
+            self.put_object_from_file(
+                file.as_ref(),
+                key.as_str(),
+                None::<HashMap<&str, &str>>,
+                None,
+            )
+            .await
+            .map(|_| ())
+        })
+        .await)
+    }
+
+    // Server side copies every object under `src_prefix` in `src_bucket` to
+    // this bucket, replacing `src_prefix` with `dst_prefix` in the key.
+    pub async fn copy_prefix<S>(
+        &self,
+        src_bucket: S,
+        src_prefix: S,
+        dst_prefix: S,
+        copy_options: &CopyOptions,
+        options: BulkOptions,
+    ) -> Result<BulkResult<CopyObjectResult>, Error>
+    where
+        S: AsRef<str>,
+    {
+        let (src_bucket, src_prefix, dst_prefix) = (
+            src_bucket.as_ref(),
+            src_prefix.as_ref(),
+            dst_prefix.as_ref(),
+        );
+        let keys = self.bucket_handle(src_bucket).list_keys(src_prefix).await?;
+        let headers = &copy_options.to_headers()?;
+
+        Ok(run_bulk(keys, options, |key| async move {
+            let dst_key = format!("{}{}", dst_prefix, &key[src_prefix.len()..]);
+            self.with_context("copy_prefix", &dst_key, async {
+                self.copy_object_request(src_bucket, &key, None, &dst_key, headers.clone())
+                    .await
+            })
+            .await
+        })
+        .await)
+    }
+
+    pub async fn delete_prefix<S>(
+        &self,
+        prefix: S,
+        options: BulkOptions,
+    ) -> Result<BulkResult<DeleteObjectResult>, Error>
+    where
+        S: AsRef<str>,
+    {
+        let keys = self.list_keys(prefix.as_ref()).await?;
+        Ok(run_bulk(
+            keys,
+            options,
+            |key| async move { self.delete_object(key).await },
+        )
+        .await)
+    }
+}
+
+// Keys for every file below `dir`: `prefix` followed by the path relative
+// to `dir` with `/` separators, along with the file behind each key.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn local_keys(
+    dir: &Path,
+    prefix: &str,
+) -> Result<(Vec<String>, HashMap<String, PathBuf>), Error> {
+    let files = walk_dir(dir).await?;
+    let mut keys = Vec::with_capacity(files.len());
+    let mut paths = HashMap::with_capacity(files.len());
+    for file in files {
+        let relative = file
+            .strip_prefix(dir)
+            .map_err(|e| Error::E(e.to_string()))?
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let key = format!("{}{}", prefix, relative);
+        keys.push(key.clone());
+        paths.insert(key, file);
+    }
+    Ok((keys, paths))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn walk_dir(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut files = Vec::new();
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for (path, file_type) in rt::read_dir(&dir).await? {
+            if file_type.is_dir() {
+                dirs.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("key-{}", i)).collect()
+    }
+
+    #[tokio::test]
+    async fn test_run_bulk_continue_and_collect() {
+        let options = BulkOptions {
+            concurrency: 2,
+            policy: FailurePolicy::ContinueAndCollect,
+        };
+        let result = run_bulk(keys(4), options, |key| async move {
+            if key == "key-1" {
+                Err(Error::E("boom".to_owned()))
+            } else {
+                Ok(key.len())
+            }
+        })
+        .await;
+        assert_eq!(result.outcomes().len(), 4);
+        assert_eq!(result.succeeded().count(), 3);
+        assert_eq!(
+            result.failed().map(|(k, _)| k).collect::<Vec<_>>(),
+            ["key-1"]
+        );
+        assert!(!result.is_complete());
+    }
+
+    #[tokio::test]
+    async fn test_run_bulk_fail_fast() {
+        let options = BulkOptions {
+            concurrency: 1,
+            policy: FailurePolicy::FailFast,
+        };
+        let result = run_bulk(keys(4), options, |key| async move {
+            if key == "key-1" {
+                Err(Error::E("boom".to_owned()))
+            } else {
+                Ok(())
+            }
+        })
+        .await;
+        assert_eq!(result.succeeded().count(), 1);
+        assert_eq!(result.failed().count(), 1);
+        assert_eq!(result.cancelled().collect::<Vec<_>>(), ["key-2", "key-3"]);
+    }
+
+    #[tokio::test]
+    async fn test_run_bulk_retry_then_skip() {
+        let calls = std::sync::atomic::AtomicU32::new(0);
+        let options = BulkOptions {
+            concurrency: 1,
+            policy: FailurePolicy::RetryThenSkip { attempts: 3 },
+        };
+        let calls = &calls;
+        let result = run_bulk(keys(2), options, |key| async move {
+            calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            if key == "key-0" {
+                Err::<(), _>(Error::E("boom".to_owned()))
+            } else {
+                Ok(())
+            }
+        })
+        .await;
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 4);
+        assert_eq!(result.failed().count(), 1);
+        assert_eq!(result.succeeded().count(), 1);
+    }
+}
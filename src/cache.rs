@@ -0,0 +1,205 @@
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, ETAG, IF_NONE_MATCH};
+use reqwest::{Method, StatusCode};
+use std::time::Duration;
+
+use crate::errors::{Error, ObjectError};
+use crate::oss::OSS;
+
+#[derive(Clone, Debug)]
+pub(crate) struct CachedObject {
+    data: Bytes,
+    etag: String,
+    fetched_at: DateTime<Utc>,
+    // Set while a background revalidation is in flight, so concurrent stale
+    // reads spawn at most one.
+    revalidating: bool,
+}
+
+impl CachedObject {
+    fn is_fresh(&self, now: DateTime<Utc>, ttl: Duration) -> bool {
+        match (now - self.fetched_at).to_std() {
+            Ok(age) => age < ttl,
+            // Fetched "in the future" after a clock step back.
+            Err(_) => true,
+        }
+    }
+}
+
+impl OSS {
+    // Serves `key` from an in-memory cache shared by this client and its
+    // bucket handles. Within `ttl` of the last fetch the cached copy is
+    // returned as is; after that it is still returned, and a background task
+    // revalidates it with If-None-Match. Only the first read of a key waits on
+    // OSS. Meant for small, hot objects such as configuration or feature flag
+    // files; a failed revalidation keeps serving the stale copy.
+    pub async fn get_object_cached<S>(&self, key: S, ttl: Duration) -> Result<Bytes, Error>
+    where
+        S: AsRef<str>,
+    {
+        let key = key.as_ref();
+        let cache_key = format!("{}/{}", self.bucket(), key);
+        let now = self.now();
+
+        let stale = {
+            let mut cache = self.object_cache().lock().unwrap();
+            match cache.get_mut(&cache_key) {
+                Some(entry) if entry.is_fresh(now, ttl) || entry.revalidating => {
+                    return Ok(entry.data.clone())
+                }
+                Some(entry) => {
+                    entry.revalidating = true;
+                    Some((entry.data.clone(), entry.etag.clone()))
+                }
+                None => None,
+            }
+        };
+
+        match stale {
+            Some((data, etag)) => {
+                let oss = self.clone();
+                let key = key.to_owned();
+                self.spawn_named("revalidate", async move {
+                    let result = oss.fetch_if_none_match(&key, Some(&etag)).await;
+                    let mut cache = oss.object_cache().lock().unwrap();
+                    if let Some(entry) = cache.get_mut(&cache_key) {
+                        entry.revalidating = false;
+                        match result {
+                            Ok(Some((data, etag))) => {
+                                entry.data = data;
+                                entry.etag = etag;
+                                entry.fetched_at = oss.now();
+                            }
+                            Ok(None) => entry.fetched_at = oss.now(),
+                            Err(e) => warn!("can not revalidate {}: {}", cache_key, e),
+                        }
+                    }
+                })
+                .detach();
+                Ok(data)
+            }
+            None => {
+                self.with_context("get_object_cached", key, async {
+                    let (data, etag) =
+                        self.fetch_if_none_match(key, None).await?.ok_or_else(|| {
+                            Error::E("unexpected 304 without If-None-Match".to_owned())
+                        })?;
+                    self.object_cache().lock().unwrap().insert(
+                        cache_key,
+                        CachedObject {
+                            data: data.clone(),
+                            etag,
+                            fetched_at: now,
+                            revalidating: false,
+                        },
+                    );
+                    Ok(data)
+                })
+                .await
+            }
+        }
+    }
+
+    // Drops the cached copy of `key`, the next `get_object_cached` refetches it.
+    pub fn invalidate_cached<S: AsRef<str>>(&self, key: S) {
+        let cache_key = format!("{}/{}", self.bucket(), key.as_ref());
+        self.object_cache().lock().unwrap().remove(&cache_key);
+    }
+
+    // None when the object still matches `etag`.
+    async fn fetch_if_none_match(
+        &self,
+        key: &str,
+        etag: Option<&str>,
+    ) -> Result<Option<(Bytes, String)>, Error> {
+        let mut headers = HeaderMap::new();
+        if let Some(etag) = etag {
+            headers.insert(IF_NONE_MATCH, etag.parse()?);
+        }
+        let resp = self
+            .signed_request(Method::GET, key, &[], headers, Vec::new())
+            .await?;
+        if resp.status() == StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+        if !resp.status().is_success() {
+            return Err(self
+                .api_error(resp, |reason| {
+                    Error::Object(ObjectError::GetError {
+                        msg: format!("can not get object, reason: {:?}", reason),
+                    })
+                })
+                .await);
+        }
+        let etag = resp
+            .headers()
+            .get(ETAG)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default()
+            .to_owned();
+        Ok(Some((resp.bytes().await?, etag)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+    use chrono::TimeZone;
+
+    fn cached(fetched_at: DateTime<Utc>) -> CachedObject {
+        CachedObject {
+            data: Bytes::from_static(b"flags"),
+            etag: "\"etag\"".to_owned(),
+            fetched_at,
+            revalidating: false,
+        }
+    }
+
+    #[test]
+    fn test_is_fresh() {
+        let fetched_at = Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap();
+        let entry = cached(fetched_at);
+        let ttl = Duration::from_secs(30);
+        assert!(entry.is_fresh(fetched_at + chrono::Duration::seconds(29), ttl));
+        assert!(!entry.is_fresh(fetched_at + chrono::Duration::seconds(30), ttl));
+        assert!(entry.is_fresh(fetched_at - chrono::Duration::seconds(5), ttl));
+    }
+
+    #[tokio::test]
+    async fn test_get_object_cached_serves_fresh_copy() {
+        let now = Utc.with_ymd_and_hms(2023, 1, 1, 0, 0, 0).unwrap();
+        let oss_instance = OSS::builder(
+            "xxx".to_string(),
+            "xxx".to_string(),
+            "xxx.aliyuncs.com".to_string(),
+            "xxx".to_string(),
+        )
+        .clock(FixedClock(now))
+        .build();
+        oss_instance
+            .object_cache()
+            .lock()
+            .unwrap()
+            .insert("xxx/flags.json".to_owned(), cached(now));
+
+        let data = oss_instance
+            .get_object_cached("flags.json", Duration::from_secs(30))
+            .await
+            .unwrap();
+        assert_eq!(&data[..], b"flags");
+        assert_eq!(oss_instance.active_tasks(), 0);
+
+        // Bucket handles share the cache under their own bucket name.
+        assert!(oss_instance
+            .bucket_handle("other")
+            .object_cache()
+            .lock()
+            .unwrap()
+            .contains_key("xxx/flags.json"));
+
+        oss_instance.invalidate_cached("flags.json");
+        assert!(oss_instance.object_cache().lock().unwrap().is_empty());
+    }
+}
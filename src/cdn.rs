@@ -0,0 +1,76 @@
+use reqwest::header::{HeaderMap, ETAG};
+use std::fmt;
+
+use crate::errors::Error;
+use crate::oss::OSS;
+use crate::transport::BoxFuture;
+
+// Called after an object was written through the client, to purge it from a
+// CDN in front of the bucket. Set with `OSSBuilder::invalidate_after_put`.
+// `url` is the object's OSS URL, map it to the CDN domain as needed.
+pub trait PurgeHook: fmt::Debug + Send + Sync {
+    fn purge<'a>(&'a self, url: &'a str, etag: &'a str) -> BoxFuture<'a, Result<(), Error>>;
+}
+
+impl OSS {
+    // Runs the purge hook, if any, for `object` which now has `etag`. Its
+    // error fails the write that triggered it even though the object was
+    // written, a stale CDN copy is what the hook exists to prevent.
+    pub(crate) async fn invalidate(&self, object: &str, etag: Option<&str>) -> Result<(), Error> {
+        let hook = match self.purge_hook() {
+            Some(hook) => hook,
+            None => return Ok(()),
+        };
+        let url = self.host(self.bucket(), object, "");
+        let url = url.trim_end_matches('?');
+        hook.purge(url, etag.unwrap_or_default()).await
+    }
+}
+
+// The ETag header of a write response.
+pub(crate) fn etag(headers: &HeaderMap) -> Option<&str> {
+    headers.get(ETAG).and_then(|v| v.to_str().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, Default)]
+    struct Recorder(Arc<Mutex<Vec<(String, String)>>>);
+
+    impl PurgeHook for Recorder {
+        fn purge<'a>(&'a self, url: &'a str, etag: &'a str) -> BoxFuture<'a, Result<(), Error>> {
+            self.0
+                .lock()
+                .unwrap()
+                .push((url.to_owned(), etag.to_owned()));
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invalidate() {
+        let purged = Arc::new(Mutex::new(Vec::new()));
+        let oss_instance = OSS::builder(
+            "xxx".to_string(),
+            "xxx".to_string(),
+            "https://oss-cn-hangzhou.aliyuncs.com".to_string(),
+            "examplebucket".to_string(),
+        )
+        .invalidate_after_put(Recorder(purged.clone()))
+        .build();
+        oss_instance
+            .invalidate("img/a b.png", Some("\"5B3C1A2E\""))
+            .await
+            .unwrap();
+        assert_eq!(
+            purged.lock().unwrap()[..],
+            [(
+                "https://examplebucket.oss-cn-hangzhou.aliyuncs.com/img/a%20b.png".to_owned(),
+                "\"5B3C1A2E\"".to_owned()
+            )]
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use futures::TryStreamExt;
+use md5::{Digest, Md5};
+use reqwest::header::{HeaderMap, CONTENT_LENGTH};
+use std::path::Path;
+
+use crate::bulk::{run_bulk, BulkOptions, BulkResult};
+use crate::errors::Error;
+use crate::oss::OSS;
+use crate::rt::{self, AsyncWriteExt};
+use crate::utils::Crc64;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChecksumAlgorithm {
+    // Uses the stored x-oss-hash-crc64ecma header when the object has one.
+    Crc64,
+    Md5,
+}
+
+impl ChecksumAlgorithm {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChecksumAlgorithm::Crc64 => "crc64ecma",
+            ChecksumAlgorithm::Md5 => "md5",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChecksumEntry {
+    key: String,
+    checksum: String,
+    size: u64,
+}
+
+impl ChecksumEntry {
+    pub fn new(key: String, checksum: String, size: u64) -> Self {
+        ChecksumEntry {
+            key,
+            checksum,
+            size,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    // Decimal for CRC64 (as in the OSS header), lowercase hex for MD5.
+    pub fn checksum(&self) -> &str {
+        &self.checksum
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+}
+
+impl OSS {
+    // Computes a checksum for every object under `prefix` and writes a
+    // manifest to `manifest`, one `<algo>:<checksum>\t<size>\t<key>` line
+    // per object in key order. Tabs, newlines and backslashes in keys are
+    // backslash escaped.
+    pub async fn checksum_prefix<S, P>(
+        &self,
+        prefix: S,
+        algo: ChecksumAlgorithm,
+        manifest: P,
+        options: BulkOptions,
+    ) -> Result<BulkResult<ChecksumEntry>, Error>
+    where
+        S: AsRef<str>,
+        P: AsRef<Path>,
+    {
+        let prefix = prefix.as_ref();
+        let keys = self.list_keys(prefix).await?;
+        let result = run_bulk(keys, options, |key| async move {
+            self.with_context("checksum_prefix", &key, self.checksum_object(&key, algo))
+                .await
+        })
+        .await;
+
+        let mut file = rt::create(manifest).await?;
+        for (_, entry) in result.succeeded() {
+            let line = format!(
+                "{}:{}\t{}\t{}\n",
+                algo.as_str(),
+                entry.checksum(),
+                entry.size(),
+                escape_key(entry.key())
+            );
+            file.write_all(line.as_bytes()).await?;
+        }
+        file.flush().await?;
+        Ok(result)
+    }
+
+    async fn checksum_object(
+        &self,
+        key: &str,
+        algo: ChecksumAlgorithm,
+    ) -> Result<ChecksumEntry, Error> {
+        if algo == ChecksumAlgorithm::Crc64 {
+            let head = self.head_object(key, None, None).await?;
+            let header = |name| {
+                head.headers()
+                    .get(name)
+                    .and_then(|v| v.to_str().ok())
+                    .map(|v| v.to_owned())
+            };
+            if let (Some(crc), Some(size)) = (
+                header("x-oss-hash-crc64ecma"),
+                header(CONTENT_LENGTH.as_str()).and_then(|v| v.parse().ok()),
+            ) {
+                return Ok(ChecksumEntry::new(key.to_owned(), crc, size));
+            }
+        }
+
+        let resp = self.signed_get(key, &[], HeaderMap::new()).await?;
+
+        let mut size = 0;
+        let mut crc = Crc64::new();
+        let mut md5 = Md5::new();
+        let chunks = resp.bytes_stream();
+        futures::pin_mut!(chunks);
+        while let Some(chunk) = chunks.try_next().await? {
+            size += chunk.len() as u64;
+            match algo {
+                ChecksumAlgorithm::Crc64 => crc.update(&chunk),
+                ChecksumAlgorithm::Md5 => md5.update(&chunk),
+            }
+        }
+        let checksum = match algo {
+            ChecksumAlgorithm::Crc64 => crc.value().to_string(),
+            ChecksumAlgorithm::Md5 => format!("{:x}", md5.finalize()),
+        };
+        Ok(ChecksumEntry::new(key.to_owned(), checksum, size))
+    }
+}
+
+fn escape_key(key: &str) -> String {
+    let mut escaped = String::with_capacity(key.len());
+    for c in key.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_key() {
+        assert_eq!(escape_key("a/b c.txt"), "a/b c.txt");
+        assert_eq!(escape_key("a\tb\nc\\d"), "a\\tb\\nc\\\\d");
+    }
+}
@@ -0,0 +1,174 @@
+use serde_derive::Serialize;
+use std::fmt;
+
+use crate::oss::OSS;
+
+// How requests are signed. V4 (OSS4-HMAC-SHA256) is scoped to a region and
+// required by some newer regions and features.
+// https://help.aliyun.com/document_detail/2412367.html
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SignatureVersion {
+    V1,
+    V4,
+}
+
+impl SignatureVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignatureVersion::V1 => "v1",
+            SignatureVersion::V4 => "v4",
+        }
+    }
+}
+
+impl fmt::Display for SignatureVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+// The effective settings of a client, safe to log: the AccessKey ID is
+// masked and the secret left out.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ConfigSnapshot {
+    endpoint: String,
+    region: Option<String>,
+    bucket: String,
+    access_key_id: String,
+    addressing_style: &'static str,
+    signature_version: &'static str,
+    timeout_ms: Option<u64>,
+    max_attempts: u32,
+    hedged_read_budget_ms: Option<u64>,
+}
+
+impl ConfigSnapshot {
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    pub fn region(&self) -> Option<&str> {
+        self.region.as_deref()
+    }
+
+    pub fn bucket(&self) -> &str {
+        &self.bucket
+    }
+
+    pub fn access_key_id(&self) -> &str {
+        &self.access_key_id
+    }
+
+    pub fn addressing_style(&self) -> &str {
+        self.addressing_style
+    }
+
+    pub fn signature_version(&self) -> &str {
+        self.signature_version
+    }
+
+    // None when requests may wait indefinitely, or when the client was given
+    // to `OSSBuilder::client` and its timeout is unknown.
+    pub fn timeout_ms(&self) -> Option<u64> {
+        self.timeout_ms
+    }
+
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    pub fn hedged_read_budget_ms(&self) -> Option<u64> {
+        self.hedged_read_budget_ms
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+}
+
+impl OSS {
+    pub fn config(&self) -> ConfigSnapshot {
+        ConfigSnapshot {
+            endpoint: self.endpoint().to_owned(),
+            region: self.region().map(|r| r.to_owned()),
+            bucket: self.bucket().to_owned(),
+            access_key_id: redact(self.key_id()),
+            addressing_style: "virtual-hosted",
+            signature_version: self.signature_version().as_str(),
+            timeout_ms: self.timeout().map(|t| t.as_millis() as u64),
+            max_attempts: self.retry_policy().max_attempts.max(1),
+            hedged_read_budget_ms: self.hedged_read_budget().map(|b| b.as_millis() as u64),
+        }
+    }
+}
+
+// The region of an OSS endpoint, e.g. cn-hangzhou for
+// https://oss-cn-hangzhou-internal.aliyuncs.com.
+pub(crate) fn region(endpoint: &str) -> Option<String> {
+    let host = endpoint
+        .trim_start_matches("https://")
+        .trim_start_matches("http://");
+    let label = host.split('.').next()?;
+    let region = label.strip_prefix("oss-")?;
+    Some(region.trim_end_matches("-internal").to_owned())
+}
+
+fn redact(key_id: &str) -> String {
+    let visible: String = key_id.chars().take(4).collect();
+    format!("{}****", visible)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retry::RetryPolicy;
+    use std::time::Duration;
+
+    #[test]
+    fn test_region() {
+        assert_eq!(
+            region("oss-cn-hangzhou.aliyuncs.com").as_deref(),
+            Some("cn-hangzhou")
+        );
+        assert_eq!(
+            region("https://oss-cn-beijing-internal.aliyuncs.com").as_deref(),
+            Some("cn-beijing")
+        );
+        assert_eq!(region("static.example.com"), None);
+    }
+
+    #[test]
+    fn test_config_snapshot_is_redacted() {
+        let mut oss = OSS::new(
+            "LTAI4GB6fwDbFvCBM5LkmQ1D".to_owned(),
+            "nTnZ7Vm1cPWEMWtRmoTYpfLKjGnPAz".to_owned(),
+            "https://oss-cn-hangzhou.aliyuncs.com".to_owned(),
+            "examplebucket".to_owned(),
+        );
+        oss.set_hedged_read_budget(Some(Duration::from_millis(250)));
+        let config = oss.config();
+        assert_eq!(config.access_key_id(), "LTAI****");
+        assert_eq!(config.region(), Some("cn-hangzhou"));
+        assert_eq!(config.hedged_read_budget_ms(), Some(250));
+        assert_eq!(config.timeout_ms(), None);
+        assert_eq!(config.max_attempts(), 3);
+
+        let config = OSS::builder(
+            "LTAI4GB6fwDbFvCBM5LkmQ1D".to_owned(),
+            "nTnZ7Vm1cPWEMWtRmoTYpfLKjGnPAz".to_owned(),
+            "https://oss-cn-hangzhou.aliyuncs.com".to_owned(),
+            "examplebucket".to_owned(),
+        )
+        .timeout(Duration::from_secs(30))
+        .retry_policy(RetryPolicy::none())
+        .build()
+        .config();
+        assert_eq!(config.timeout_ms(), Some(30_000));
+        assert_eq!(config.max_attempts(), 1);
+
+        let json = config.to_json().unwrap();
+        assert!(json.contains("\"access_key_id\":\"LTAI****\""));
+        assert!(!json.contains("LTAI4GB6"));
+        assert!(!json.contains("nTnZ7Vm1"));
+    }
+}
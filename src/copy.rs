@@ -0,0 +1,629 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_LENGTH, DATE};
+use reqwest::Method;
+use serde_derive::Deserialize;
+use serde_xml_rs::from_str;
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::errors::{Error, ObjectError};
+use crate::oss::{CompleteMultipartUpload, Part, OSS};
+use crate::tuning::{run_tuned, Concurrency};
+use crate::utils::url_encode;
+
+// Standard headers that, like x-oss-meta-*, are dropped by a REPLACE copy
+// unless they are sent again.
+const REPLACED_HEADERS: [&str; 6] = [
+    "content-type",
+    "content-encoding",
+    "content-language",
+    "content-disposition",
+    "cache-control",
+    "expires",
+];
+
+// Largest source CopyObject accepts.
+const COPY_OBJECT_LIMIT: u64 = 1024 * 1024 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MetadataDirective {
+    Copy,
+    Replace,
+}
+
+impl MetadataDirective {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MetadataDirective::Copy => "COPY",
+            MetadataDirective::Replace => "REPLACE",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TaggingDirective {
+    // Keep the tags of the source object.
+    Copy,
+    // Replace the tags with the given set, an empty set removes all tags.
+    Replace(Vec<(String, String)>),
+}
+
+impl TaggingDirective {
+    fn apply(&self, headers: &mut HeaderMap) -> Result<(), Error> {
+        match self {
+            TaggingDirective::Copy => {
+                headers.insert("x-oss-tagging-directive", "Copy".parse()?);
+            }
+            TaggingDirective::Replace(tags) => {
+                let tagging = tags
+                    .iter()
+                    .map(|(k, v)| format!("{}={}", url_encode(k), url_encode(v)))
+                    .collect::<Vec<_>>()
+                    .join("&");
+                headers.insert("x-oss-tagging-directive", "Replace".parse()?);
+                headers.insert("x-oss-tagging", tagging.parse()?);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CopyOptions {
+    // Copies this version of the source instead of the current one.
+    pub source_version_id: Option<String>,
+    // OSS defaults to COPY when unset.
+    pub metadata_directive: Option<MetadataDirective>,
+    pub tagging_directive: Option<TaggingDirective>,
+    // Sent as is, e.g. x-oss-meta-* or Content-Type for a REPLACE copy.
+    pub headers: HashMap<String, String>,
+    // Conditions on the source object, the copy fails when one isn't met.
+    pub if_match: Option<String>,
+    pub if_none_match: Option<String>,
+    pub if_modified_since: Option<DateTime<Utc>>,
+    pub if_unmodified_since: Option<DateTime<Utc>>,
+}
+
+impl CopyOptions {
+    pub(crate) fn to_headers(&self) -> Result<HeaderMap, Error> {
+        let mut headers = HeaderMap::new();
+        for (k, v) in &self.headers {
+            headers.insert(
+                HeaderName::from_bytes(k.to_lowercase().as_bytes())?,
+                HeaderValue::from_str(v)?,
+            );
+        }
+        if let Some(metadata_directive) = self.metadata_directive {
+            headers.insert(
+                "x-oss-metadata-directive",
+                metadata_directive.as_str().parse()?,
+            );
+        }
+        if let Some(tagging_directive) = &self.tagging_directive {
+            tagging_directive.apply(&mut headers)?;
+        }
+        if let Some(etag) = &self.if_match {
+            headers.insert("x-oss-copy-source-if-match", etag.parse()?);
+        }
+        if let Some(etag) = &self.if_none_match {
+            headers.insert("x-oss-copy-source-if-none-match", etag.parse()?);
+        }
+        let http_date = |t: &DateTime<Utc>| t.format("%a, %d %b %Y %T GMT").to_string();
+        if let Some(t) = &self.if_modified_since {
+            headers.insert("x-oss-copy-source-if-modified-since", http_date(t).parse()?);
+        }
+        if let Some(t) = &self.if_unmodified_since {
+            headers.insert(
+                "x-oss-copy-source-if-unmodified-since",
+                http_date(t).parse()?,
+            );
+        }
+        Ok(headers)
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct CopyObjectResult {
+    #[serde(rename = "ETag")]
+    etag: String,
+    last_modified: String,
+    // From the x-oss-version-id and x-oss-copy-source-version-id headers.
+    #[serde(skip)]
+    version_id: Option<String>,
+    #[serde(skip)]
+    source_version_id: Option<String>,
+    #[serde(skip)]
+    request_id: Option<String>,
+}
+
+impl CopyObjectResult {
+    pub fn new(
+        etag: String,
+        last_modified: String,
+        version_id: Option<String>,
+        source_version_id: Option<String>,
+    ) -> Self {
+        CopyObjectResult {
+            etag,
+            last_modified,
+            version_id,
+            source_version_id,
+            request_id: None,
+        }
+    }
+
+    pub fn etag(&self) -> &str {
+        &self.etag
+    }
+
+    pub fn last_modified(&self) -> &str {
+        &self.last_modified
+    }
+
+    // Version of the new object, when versioning is enabled on this bucket.
+    pub fn version_id(&self) -> Option<&str> {
+        self.version_id.as_deref()
+    }
+
+    // Version of the source that was copied, when versioning is enabled on
+    // the source bucket.
+    pub fn source_version_id(&self) -> Option<&str> {
+        self.source_version_id.as_deref()
+    }
+
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MultipartCopyOptions {
+    // Copies this version of the source instead of the current one.
+    pub source_version_id: Option<String>,
+    // Raised when needed to stay within 10000 parts. Sources up to this size
+    // (and at most 1 GB) are copied with a single CopyObject.
+    pub part_size: u64,
+    pub concurrency: Concurrency,
+    // Applied on top of the source's Content-Type, x-oss-meta-* and other
+    // metadata, which the copy keeps. A `None` value removes the header.
+    pub metadata_changes: HashMap<String, Option<String>>,
+}
+
+impl Default for MultipartCopyOptions {
+    fn default() -> Self {
+        MultipartCopyOptions {
+            source_version_id: None,
+            part_size: 100 * 1024 * 1024,
+            concurrency: Concurrency::default(),
+            metadata_changes: HashMap::new(),
+        }
+    }
+}
+
+impl OSS {
+    // Server side copies `src_key` in `src_bucket` to `dst_key` in this bucket.
+    pub async fn copy_object<S>(
+        &self,
+        src_bucket: S,
+        src_key: S,
+        dst_key: S,
+        options: &CopyOptions,
+    ) -> Result<CopyObjectResult, Error>
+    where
+        S: AsRef<str>,
+    {
+        let (src_bucket, src_key, dst_key) =
+            (src_bucket.as_ref(), src_key.as_ref(), dst_key.as_ref());
+        self.with_context("copy_object", dst_key, async {
+            self.copy_object_request(
+                src_bucket,
+                src_key,
+                options.source_version_id.as_deref(),
+                dst_key,
+                options.to_headers()?,
+            )
+            .await
+        })
+        .await
+    }
+
+    // Server side copies objects of any size, CopyObject is limited to 1 GB.
+    // The parts are copied in parallel with UploadPartCopy and the upload is
+    // aborted if one of them fails. The metadata of the source is kept.
+    pub async fn copy_large_object<S>(
+        &self,
+        src_bucket: S,
+        src_key: S,
+        dst_key: S,
+        options: MultipartCopyOptions,
+    ) -> Result<CopyObjectResult, Error>
+    where
+        S: AsRef<str>,
+    {
+        let (src_bucket, src_key, dst_key) =
+            (src_bucket.as_ref(), src_key.as_ref(), dst_key.as_ref());
+        self.with_context("copy_large_object", dst_key, async {
+            let resources = options
+                .source_version_id
+                .as_deref()
+                .map(|v| vec![("versionId", Some(v))].into_iter().collect());
+            let head = self
+                .bucket_handle(src_bucket)
+                .head_object(src_key, None, resources)
+                .await?;
+            // The parts are all copied from the version that was HEADed, even
+            // if the source is overwritten in the meantime.
+            let source_version_id = options.source_version_id.clone().or_else(|| {
+                head.headers()
+                    .get("x-oss-version-id")
+                    .and_then(|v| v.to_str().ok())
+                    .map(|v| v.to_owned())
+            });
+            let size: u64 = head
+                .headers()
+                .get(CONTENT_LENGTH)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse().ok())
+                .ok_or_else(|| Error::E("source object has no Content-Length".to_owned()))?;
+            let metadata = merge_metadata(head.headers(), &options.metadata_changes)?;
+
+            // Also covers empty sources, which have no part to copy.
+            if size <= options.part_size.min(COPY_OBJECT_LIMIT) {
+                let mut headers = metadata;
+                headers.insert(
+                    "x-oss-metadata-directive",
+                    MetadataDirective::Replace.as_str().parse()?,
+                );
+                return self
+                    .copy_object_request(
+                        src_bucket,
+                        src_key,
+                        source_version_id.as_deref(),
+                        dst_key,
+                        headers,
+                    )
+                    .await;
+            }
+
+            let ranges = part_ranges(size, options.part_size);
+            let metadata = metadata
+                .iter()
+                .map(|(k, v)| {
+                    let v = String::from_utf8(v.as_bytes().to_vec())
+                        .map_err(|_| Error::E(format!("{} is not UTF-8", k)))?;
+                    Ok((k.as_str().to_owned(), v))
+                })
+                .collect::<Result<HashMap<_, _>, Error>>()?;
+            let upload_id = self.initiate_multipart_upload(dst_key, metadata).await?;
+            let source = copy_source(src_bucket, src_key, source_version_id.as_deref());
+            let ranges: Vec<_> = ranges.into_iter().enumerate().collect();
+            let parts = run_tuned(
+                ranges,
+                options.concurrency,
+                |(_, range)| range.end - range.start,
+                |(i, range)| {
+                    let oss = self.clone();
+                    let (source, dst_key) = (source.clone(), dst_key.to_owned());
+                    let upload_id = upload_id.clone();
+                    self.spawn_child("copy-part", async move {
+                        let number = i as u64 + 1;
+                        let etag = oss
+                            .upload_part_copy(&source, &dst_key, &upload_id, number, range)
+                            .await?;
+                        Ok(Part::new(number, etag))
+                    })
+                },
+            )
+            .await;
+            let parts = match parts {
+                Ok(parts) => parts,
+                Err(e) => return Err(self.abort_after(dst_key, upload_id, e).await),
+            };
+
+            let (etag, headers) = self
+                .complete_upload(
+                    dst_key,
+                    &upload_id,
+                    CompleteMultipartUpload::new(parts),
+                    HeaderMap::new(),
+                )
+                .await?;
+            let header = |name| {
+                headers
+                    .get(name)
+                    .and_then(|v| v.to_str().ok())
+                    .map(|v| v.to_owned())
+            };
+            let last_modified = header(DATE.as_str())
+                .and_then(|date| DateTime::parse_from_rfc2822(&date).ok())
+                .map(|date| {
+                    date.with_timezone(&Utc)
+                        .to_rfc3339_opts(SecondsFormat::Millis, true)
+                })
+                .unwrap_or_default();
+            Ok(CopyObjectResult {
+                etag: etag.unwrap_or_default(),
+                last_modified,
+                version_id: header("x-oss-version-id"),
+                source_version_id,
+                request_id: header("x-oss-request-id"),
+            })
+        })
+        .await
+    }
+
+    // https://help.aliyun.com/document_detail/31994.html
+    async fn upload_part_copy(
+        &self,
+        source: &str,
+        dst_key: &str,
+        upload_id: &str,
+        part_number: u64,
+        range: Range<u64>,
+    ) -> Result<String, Error> {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-oss-copy-source", source.parse()?);
+        headers.insert(
+            "x-oss-copy-source-range",
+            format!("bytes={}-{}", range.start, range.end - 1).parse()?,
+        );
+        let params = [
+            ("partNumber", Some(part_number.to_string())),
+            ("uploadId", Some(upload_id.to_owned())),
+        ];
+        let resp = self
+            .signed_request(Method::PUT, dst_key, &params, headers, Vec::new())
+            .await?;
+        if resp.status().is_success() {
+            let result: CopyObjectResult = from_str(&self.xml_text(resp).await?)?;
+            Ok(result.etag().to_owned())
+        } else {
+            Err(self
+                .api_error(resp, |reason| {
+                    Error::Object(ObjectError::CopyError {
+                        msg: format!("can not copy part, reason: {:?}", reason),
+                    })
+                })
+                .await)
+        }
+    }
+
+    // Copies `src_key` to `dst_key` with the REPLACE directive while keeping
+    // the source metadata: the source is HEADed first and `changes` are
+    // applied on top of its metadata. A `None` value removes the header.
+    pub async fn copy_object_merge_metadata<S>(
+        &self,
+        src_bucket: S,
+        src_key: S,
+        dst_key: S,
+        changes: HashMap<String, Option<String>>,
+    ) -> Result<CopyObjectResult, Error>
+    where
+        S: AsRef<str>,
+    {
+        let (src_bucket, src_key, dst_key) =
+            (src_bucket.as_ref(), src_key.as_ref(), dst_key.as_ref());
+        self.with_context("copy_object_merge_metadata", dst_key, async {
+            let head = self
+                .bucket_handle(src_bucket)
+                .head_object(src_key, None, None)
+                .await?;
+
+            let mut headers = merge_metadata(head.headers(), &changes)?;
+            headers.insert(
+                "x-oss-metadata-directive",
+                MetadataDirective::Replace.as_str().parse()?,
+            );
+            self.copy_object_request(src_bucket, src_key, None, dst_key, headers)
+                .await
+        })
+        .await
+    }
+
+    // https://help.aliyun.com/document_detail/31979.html
+    pub(crate) async fn copy_object_request(
+        &self,
+        src_bucket: &str,
+        src_key: &str,
+        src_version_id: Option<&str>,
+        dst_key: &str,
+        mut headers: HeaderMap,
+    ) -> Result<CopyObjectResult, Error> {
+        let host = &self.host(self.bucket(), dst_key, "");
+        headers.insert(
+            "x-oss-copy-source",
+            copy_source(src_bucket, src_key, src_version_id).parse()?,
+        );
+        let headers = &headers;
+        let resp = self
+            .retrying(&Method::PUT, dst_key, || async move {
+                let mut headers = headers.clone();
+                headers.insert(DATE, self.date().parse()?);
+                let authorization = self
+                    .sign("PUT", self.bucket(), dst_key, "", &mut headers)
+                    .await?;
+                headers.insert("Authorization", authorization.parse()?);
+                self.send(self.client.put(host).headers(headers)).await
+            })
+            .await?;
+
+        if resp.status().is_success() {
+            let header = |name| {
+                resp.headers()
+                    .get(name)
+                    .and_then(|v| v.to_str().ok())
+                    .map(|v| v.to_owned())
+            };
+            let version_id = header("x-oss-version-id");
+            let source_version_id = header("x-oss-copy-source-version-id");
+            let request_id = header("x-oss-request-id");
+            let result: CopyObjectResult = from_str(&self.xml_text(resp).await?)?;
+            self.invalidate(dst_key, Some(result.etag())).await?;
+            Ok(CopyObjectResult {
+                version_id,
+                source_version_id,
+                request_id,
+                ..result
+            })
+        } else {
+            Err(self
+                .api_error(resp, |reason| {
+                    Error::Object(ObjectError::CopyError {
+                        msg: format!("can not copy object, reason: {:?}", reason),
+                    })
+                })
+                .await)
+        }
+    }
+}
+
+fn copy_source(bucket: &str, key: &str, version_id: Option<&str>) -> String {
+    match version_id {
+        Some(version_id) => format!(
+            "/{}/{}?versionId={}",
+            bucket,
+            url_encode(key),
+            url_encode(version_id)
+        ),
+        None => format!("/{}/{}", bucket, url_encode(key)),
+    }
+}
+
+// Splits `size` bytes into consecutive ranges of `part_size`, raised so that
+// there are at most 10000 parts.
+pub(crate) fn part_ranges(size: u64, part_size: u64) -> Vec<Range<u64>> {
+    let part_size = part_size.max(size.div_ceil(10000)).max(1);
+    (0..size)
+        .step_by(part_size as usize)
+        .map(|start| start..(start + part_size).min(size))
+        .collect()
+}
+
+fn merge_metadata(
+    existing: &HeaderMap,
+    changes: &HashMap<String, Option<String>>,
+) -> Result<HeaderMap, Error> {
+    let mut headers: HeaderMap = existing
+        .iter()
+        .filter(|(k, _)| {
+            k.as_str().starts_with("x-oss-meta-") || REPLACED_HEADERS.contains(&k.as_str())
+        })
+        .map(|(k, v)| (k.clone(), v.clone()))
+        .collect();
+    for (k, v) in changes {
+        let name = HeaderName::from_bytes(k.to_lowercase().as_bytes())?;
+        match v {
+            Some(v) => {
+                headers.insert(name, HeaderValue::from_str(v)?);
+            }
+            None => {
+                headers.remove(name);
+            }
+        }
+    }
+    Ok(headers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_merge_metadata() {
+        let mut existing = HeaderMap::new();
+        existing.insert("x-oss-meta-owner", "alice".parse().unwrap());
+        existing.insert("x-oss-meta-team", "infra".parse().unwrap());
+        existing.insert("content-type", "text/plain".parse().unwrap());
+        existing.insert("etag", "\"abc\"".parse().unwrap());
+
+        let mut changes = HashMap::new();
+        changes.insert("X-Oss-Meta-Owner".to_owned(), Some("bob".to_owned()));
+        changes.insert("x-oss-meta-team".to_owned(), None);
+        changes.insert("Cache-Control".to_owned(), Some("no-cache".to_owned()));
+
+        let headers = merge_metadata(&existing, &changes).unwrap();
+        assert_eq!(headers.len(), 3);
+        assert_eq!(headers["x-oss-meta-owner"], "bob");
+        assert_eq!(headers["content-type"], "text/plain");
+        assert_eq!(headers["cache-control"], "no-cache");
+        assert!(headers.get("etag").is_none());
+    }
+
+    #[test]
+    fn test_copy_options_tagging() {
+        let options = CopyOptions {
+            tagging_directive: Some(TaggingDirective::Replace(vec![
+                ("project".to_owned(), "a&b".to_owned()),
+                ("cost center".to_owned(), "42".to_owned()),
+            ])),
+            ..Default::default()
+        };
+        let headers = options.to_headers().unwrap();
+        assert_eq!(headers["x-oss-tagging-directive"], "Replace");
+        assert_eq!(headers["x-oss-tagging"], "project=a%26b&cost%20center=42");
+
+        let options = CopyOptions {
+            tagging_directive: Some(TaggingDirective::Copy),
+            ..Default::default()
+        };
+        let headers = options.to_headers().unwrap();
+        assert_eq!(headers["x-oss-tagging-directive"], "Copy");
+        assert!(headers.get("x-oss-tagging").is_none());
+        assert!(CopyOptions::default().to_headers().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_copy_options_conditions() {
+        let options = CopyOptions {
+            metadata_directive: Some(MetadataDirective::Replace),
+            headers: vec![("Content-Type".to_owned(), "text/plain".to_owned())]
+                .into_iter()
+                .collect(),
+            if_match: Some("\"abc\"".to_owned()),
+            if_unmodified_since: Some(Utc.with_ymd_and_hms(2022, 12, 28, 10, 27, 41).unwrap()),
+            ..Default::default()
+        };
+        let headers = options.to_headers().unwrap();
+        assert_eq!(headers["x-oss-metadata-directive"], "REPLACE");
+        assert_eq!(headers["content-type"], "text/plain");
+        assert_eq!(headers["x-oss-copy-source-if-match"], "\"abc\"");
+        assert_eq!(
+            headers["x-oss-copy-source-if-unmodified-since"],
+            "Wed, 28 Dec 2022 10:27:41 GMT"
+        );
+        assert!(headers.get("x-oss-copy-source-if-none-match").is_none());
+    }
+
+    #[test]
+    fn test_parse_copy_object_result() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<CopyObjectResult>
+  <ETag>"F2064A169EE92E9775EE5324D0B1****"</ETag>
+  <LastModified>2018-02-24T09:41:56.000Z</LastModified>
+</CopyObjectResult>"#;
+        let result: CopyObjectResult = from_str(xml).unwrap();
+        assert_eq!(result.etag(), "\"F2064A169EE92E9775EE5324D0B1****\"");
+        assert_eq!(result.last_modified(), "2018-02-24T09:41:56.000Z");
+    }
+
+    #[test]
+    fn test_part_ranges() {
+        assert_eq!(part_ranges(10, 4), vec![0..4, 4..8, 8..10]);
+        assert_eq!(part_ranges(8, 4), vec![0..4, 4..8]);
+        assert!(part_ranges(0, 4).is_empty());
+        let ranges = part_ranges(100_000, 1);
+        assert_eq!(ranges.len(), 10000);
+        assert_eq!(ranges[9999], 99_990..100_000);
+    }
+
+    #[test]
+    fn test_copy_source() {
+        assert_eq!(copy_source("bucket", "a b.txt", None), "/bucket/a%20b.txt");
+        assert_eq!(
+            copy_source("bucket", "a.txt", Some("CAEQNhiBgM0BYiIGE1ZWQ0")),
+            "/bucket/a.txt?versionId=CAEQNhiBgM0BYiIGE1ZWQ0"
+        );
+    }
+}
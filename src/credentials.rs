@@ -0,0 +1,381 @@
+use chrono::{DateTime, Utc};
+use reqwest::{Client, Method, Request};
+use serde_derive::Deserialize;
+use std::fmt;
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+use crate::errors::Error;
+use crate::transport::{BoxFuture, HttpTransport};
+
+#[derive(Clone, PartialEq)]
+pub struct Credentials {
+    key_id: String,
+    key_secret: String,
+    security_token: Option<String>,
+    // When temporary credentials stop working, None for long-lived keys.
+    expiration: Option<DateTime<Utc>>,
+}
+
+impl Credentials {
+    pub fn new(key_id: String, key_secret: String, security_token: Option<String>) -> Self {
+        Credentials {
+            key_id,
+            key_secret,
+            security_token,
+            expiration: None,
+        }
+    }
+
+    pub fn with_expiration(mut self, expiration: DateTime<Utc>) -> Self {
+        self.expiration = Some(expiration);
+        self
+    }
+
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    pub fn key_secret(&self) -> &str {
+        &self.key_secret
+    }
+
+    pub fn security_token(&self) -> Option<&str> {
+        self.security_token.as_deref()
+    }
+
+    pub fn expiration(&self) -> Option<DateTime<Utc>> {
+        self.expiration
+    }
+
+    // Whether the credentials expire within `margin` of `now`.
+    pub fn expires_within(&self, now: DateTime<Utc>, margin: Duration) -> bool {
+        match self.expiration {
+            Some(expiration) => match (expiration - now).to_std() {
+                Ok(left) => left <= margin,
+                Err(_) => true,
+            },
+            None => false,
+        }
+    }
+}
+
+// Keeps the secret and token out of logs.
+impl fmt::Debug for Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Credentials")
+            .field("key_id", &self.key_id)
+            .field("expiration", &self.expiration)
+            .finish()
+    }
+}
+
+// Source of the keys requests are signed with, consulted before signing each
+// request so rotated credentials are picked up without rebuilding the client.
+// Implementations return a boxed future, i.e. an
+// `async fn credentials(&self) -> Result<Credentials, Error>`.
+pub trait CredentialsProvider: fmt::Debug + Send + Sync {
+    fn credentials(&self) -> BoxFuture<'_, Result<Credentials, Error>>;
+
+    // Called when OSS rejected the credentials as expired or revoked, so the
+    // next call fetches new ones instead of returning cached ones.
+    fn invalidate(&self) {}
+
+    // Called by `OSSBuilder::try_build` with the client's transport, for
+    // providers that fetch credentials over HTTP to send their requests
+    // through instead of a client of their own.
+    fn bind_transport(&self, _transport: Arc<dyn HttpTransport>) {}
+}
+
+// Always hands out the same credentials, the default for `OSS::new`.
+#[derive(Clone, Debug)]
+pub struct StaticProvider {
+    credentials: Credentials,
+}
+
+impl StaticProvider {
+    pub fn new(credentials: Credentials) -> Self {
+        StaticProvider { credentials }
+    }
+}
+
+impl CredentialsProvider for StaticProvider {
+    fn credentials(&self) -> BoxFuture<'_, Result<Credentials, Error>> {
+        Box::pin(async move { Ok(self.credentials.clone()) })
+    }
+}
+
+// Caches the credentials of `inner` and fetches new ones once they expire
+// within `margin`, so temporary credentials are refreshed ahead of expiry
+// and concurrent requests share a single refresh.
+#[derive(Debug)]
+pub struct RefreshingProvider<P> {
+    inner: P,
+    margin: Duration,
+    cached: Arc<Mutex<Option<Credentials>>>,
+}
+
+impl<P: CredentialsProvider> RefreshingProvider<P> {
+    pub fn new(inner: P, margin: Duration) -> Self {
+        RefreshingProvider {
+            inner,
+            margin,
+            cached: Arc::new(Mutex::new(None)),
+        }
+    }
+}
+
+impl<P: CredentialsProvider> CredentialsProvider for RefreshingProvider<P> {
+    fn credentials(&self) -> BoxFuture<'_, Result<Credentials, Error>> {
+        Box::pin(async move {
+            // Held across the fetch, later callers wait for it instead of
+            // refreshing again.
+            let mut cached = self.cached.lock().await;
+            match &*cached {
+                Some(c) if !c.expires_within(Utc::now(), self.margin) => Ok(c.clone()),
+                _ => {
+                    let fresh = self.inner.credentials().await?;
+                    *cached = Some(fresh.clone());
+                    Ok(fresh)
+                }
+            }
+        })
+    }
+
+    fn invalidate(&self) {
+        // A refresh holding the lock is fetching new credentials anyway.
+        if let Ok(mut cached) = self.cached.try_lock() {
+            *cached = None;
+        }
+        self.inner.invalidate();
+    }
+
+    fn bind_transport(&self, transport: Arc<dyn HttpTransport>) {
+        self.inner.bind_transport(transport)
+    }
+}
+
+const ECS_METADATA: &str = "http://100.100.100.200/latest/meta-data/ram/security-credentials/";
+
+// Temporary credentials of the RAM role attached to the ECS instance, read
+// from the instance metadata service and refreshed 5 minutes before they
+// expire, so nothing secret has to be deployed with the application.
+// https://help.aliyun.com/document_detail/54579.html
+#[derive(Debug)]
+pub struct EcsRoleProvider {
+    inner: RefreshingProvider<EcsMetadata>,
+}
+
+impl EcsRoleProvider {
+    pub fn new<S: Into<String>>(role: S) -> Self {
+        EcsRoleProvider {
+            inner: RefreshingProvider::new(
+                EcsMetadata {
+                    url: format!("{}{}", ECS_METADATA, role.into()),
+                    transport: StdMutex::new(None),
+                },
+                Duration::from_secs(300),
+            ),
+        }
+    }
+}
+
+impl CredentialsProvider for EcsRoleProvider {
+    fn credentials(&self) -> BoxFuture<'_, Result<Credentials, Error>> {
+        self.inner.credentials()
+    }
+
+    fn invalidate(&self) {
+        self.inner.invalidate()
+    }
+
+    fn bind_transport(&self, transport: Arc<dyn HttpTransport>) {
+        self.inner.bind_transport(transport)
+    }
+}
+
+#[derive(Debug)]
+struct EcsMetadata {
+    url: String,
+    // The transport of the client the provider was given to, a default
+    // reqwest client until then.
+    transport: StdMutex<Option<Arc<dyn HttpTransport>>>,
+}
+
+impl CredentialsProvider for EcsMetadata {
+    fn credentials(&self) -> BoxFuture<'_, Result<Credentials, Error>> {
+        Box::pin(async move {
+            let transport = self
+                .transport
+                .lock()
+                .unwrap()
+                .get_or_insert_with(|| Arc::new(Client::new()))
+                .clone();
+            let url = self
+                .url
+                .parse()
+                .map_err(|e| Error::E(format!("invalid ECS metadata URL {}: {}", self.url, e)))?;
+            let resp = transport.execute(Request::new(Method::GET, url)).await?;
+            if !resp.status().is_success() {
+                return Err(Error::E(format!(
+                    "can not get ECS role credentials, status: {}",
+                    resp.status()
+                )));
+            }
+            parse_ecs_credentials(&resp.text().await?)
+        })
+    }
+
+    fn bind_transport(&self, transport: Arc<dyn HttpTransport>) {
+        *self.transport.lock().unwrap() = Some(transport);
+    }
+}
+
+fn parse_ecs_credentials(body: &str) -> Result<Credentials, Error> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct EcsCredentials {
+        code: String,
+        access_key_id: String,
+        access_key_secret: String,
+        security_token: String,
+        expiration: String,
+    }
+
+    let c: EcsCredentials = serde_json::from_str(body)
+        .map_err(|e| Error::E(format!("invalid ECS role credentials: {}", e)))?;
+    if c.code != "Success" {
+        return Err(Error::E(format!(
+            "can not get ECS role credentials, code: {}",
+            c.code
+        )));
+    }
+    let expiration = DateTime::parse_from_rfc3339(&c.expiration)
+        .map_err(|e| Error::E(format!("invalid Expiration {:?}: {}", c.expiration, e)))?;
+    Ok(
+        Credentials::new(c.access_key_id, c.access_key_secret, Some(c.security_token))
+            .with_expiration(expiration.with_timezone(&Utc)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    // Hands out credentials that expire `ttl` after each fetch.
+    #[derive(Debug)]
+    struct CountingProvider {
+        fetches: AtomicUsize,
+        ttl: chrono::Duration,
+    }
+
+    impl CredentialsProvider for CountingProvider {
+        fn credentials(&self) -> BoxFuture<'_, Result<Credentials, Error>> {
+            Box::pin(async move {
+                let n = self.fetches.fetch_add(1, Ordering::SeqCst);
+                Ok(
+                    Credentials::new(format!("key-{}", n), "secret".to_owned(), None)
+                        .with_expiration(Utc::now() + self.ttl),
+                )
+            })
+        }
+    }
+
+    #[test]
+    fn test_expires_within() {
+        let now = Utc::now();
+        let c = Credentials::new("id".to_owned(), "secret".to_owned(), None);
+        assert!(!c.expires_within(now, Duration::from_secs(300)));
+        let c = c.with_expiration(now + chrono::Duration::seconds(600));
+        assert!(!c.expires_within(now, Duration::from_secs(300)));
+        assert!(c.expires_within(now, Duration::from_secs(600)));
+        assert!(c.expires_within(now + chrono::Duration::seconds(700), Duration::ZERO));
+        assert!(!format!("{:?}", c).contains("secret"));
+    }
+
+    #[tokio::test]
+    async fn test_refreshing_provider() {
+        let provider = RefreshingProvider::new(
+            CountingProvider {
+                fetches: AtomicUsize::new(0),
+                ttl: chrono::Duration::hours(1),
+            },
+            Duration::from_secs(300),
+        );
+        assert_eq!(provider.credentials().await.unwrap().key_id(), "key-0");
+        assert_eq!(provider.credentials().await.unwrap().key_id(), "key-0");
+
+        // Expiring within the margin right away, every call refreshes.
+        let provider = RefreshingProvider::new(
+            CountingProvider {
+                fetches: AtomicUsize::new(0),
+                ttl: chrono::Duration::minutes(1),
+            },
+            Duration::from_secs(300),
+        );
+        assert_eq!(provider.credentials().await.unwrap().key_id(), "key-0");
+        assert_eq!(provider.credentials().await.unwrap().key_id(), "key-1");
+    }
+
+    #[test]
+    fn test_parse_ecs_credentials() {
+        let body = r#"{
+  "AccessKeyId" : "STS.J8XXXXXXXXXX4",
+  "AccessKeySecret" : "9PjfXXXXXXXXXBf2XAW",
+  "Expiration" : "2017-11-01T05:20:01Z",
+  "SecurityToken" : "CAISXXXXXXXXXXXwmBkleCTkyI+",
+  "LastUpdated" : "2017-10-31T23:20:01Z",
+  "Code" : "Success"
+}"#;
+        let c = parse_ecs_credentials(body).unwrap();
+        assert_eq!(c.key_id(), "STS.J8XXXXXXXXXX4");
+        assert_eq!(c.key_secret(), "9PjfXXXXXXXXXBf2XAW");
+        assert_eq!(c.security_token(), Some("CAISXXXXXXXXXXXwmBkleCTkyI+"));
+        assert_eq!(
+            c.expiration().unwrap().to_rfc3339(),
+            "2017-11-01T05:20:01+00:00"
+        );
+
+        let failed = body.replace("\"Success\"", "\"Failed\"");
+        assert!(parse_ecs_credentials(&failed).is_err());
+    }
+
+    // Answers every request with `body` and keeps the URLs asked for.
+    #[derive(Debug)]
+    struct Answer {
+        body: &'static str,
+        urls: Arc<StdMutex<Vec<String>>>,
+    }
+
+    impl HttpTransport for Answer {
+        fn execute(&self, request: Request) -> BoxFuture<'_, Result<reqwest::Response, Error>> {
+            self.urls.lock().unwrap().push(request.url().to_string());
+            let resp = http::Response::builder().body(self.body).unwrap();
+            Box::pin(async move { Ok(reqwest::Response::from(resp)) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ecs_role_provider_uses_client_transport() {
+        let urls = Arc::new(StdMutex::new(Vec::new()));
+        let oss_instance = crate::oss::OSS::builder(
+            String::new(),
+            String::new(),
+            "oss-cn-hangzhou.aliyuncs.com".to_owned(),
+            "examplebucket".to_owned(),
+        )
+        .credentials_provider(EcsRoleProvider::new("oss-role"))
+        .transport(Answer {
+            body: r#"{"Code":"Success","AccessKeyId":"STS.id","AccessKeySecret":"secret",
+                "SecurityToken":"token","Expiration":"2099-01-01T00:00:00Z"}"#,
+            urls: urls.clone(),
+        })
+        .build();
+
+        let c = oss_instance.credentials().await.unwrap();
+        assert_eq!(c.key_id(), "STS.id");
+        assert_eq!(*urls.lock().unwrap(), [format!("{}oss-role", ECS_METADATA)]);
+    }
+}
@@ -0,0 +1,640 @@
+use bytes::Bytes;
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+use reqwest::header::{CONTENT_LENGTH, CONTENT_RANGE, ETAG, IF_MATCH, RANGE};
+use reqwest::{Method, Response, StatusCode};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io::{self, SeekFrom};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncRead;
+use tokio_util::io::StreamReader;
+use tokio_util::sync::CancellationToken;
+
+use crate::copy::part_ranges;
+use crate::errors::{ChecksumMismatch, Error, ObjectError};
+use crate::oss::{server_crc64, OSS};
+use crate::progress::Progress;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::rt::{self, AsyncSeekExt, AsyncWriteExt};
+use crate::tuning::{run_tuned, Concurrency};
+use crate::utils::{insert_traffic_limit, to_headers, until_cancelled, Crc64};
+
+#[derive(Clone, Debug, Default)]
+pub struct GetObjectOptions {
+    // Bytes to fetch, the whole object when unset.
+    pub range: Option<Range<u64>>,
+    pub version_id: Option<String>,
+    // Extra request headers, e.g. If-Match or If-Modified-Since.
+    pub headers: HashMap<String, String>,
+    // Has OSS throttle the response to this many bits per second, between
+    // 819200 (100 KB/s) and 838860800 (100 MB/s).
+    pub traffic_limit: Option<u64>,
+    // Overrides the client's `OSSBuilder::timeout`, the body included.
+    pub timeout: Option<Duration>,
+}
+
+#[derive(Clone, Debug)]
+pub struct DownloadOptions {
+    // Bytes fetched by each ranged GET.
+    pub part_size: u64,
+    pub concurrency: Concurrency,
+    // Stops the download when cancelled, the temporary file is removed.
+    pub cancel: Option<CancellationToken>,
+    // Bits per second for each ranged GET, see `GetObjectOptions`.
+    pub traffic_limit: Option<u64>,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        DownloadOptions {
+            part_size: 8 * 1024 * 1024,
+            concurrency: Concurrency::default(),
+            cancel: None,
+            traffic_limit: None,
+        }
+    }
+}
+
+// A Content-Range response header, "bytes 0-99/1000".
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContentRange {
+    range: Range<u64>,
+    total: u64,
+}
+
+impl ContentRange {
+    pub fn new(range: Range<u64>, total: u64) -> Self {
+        ContentRange { range, total }
+    }
+
+    // The bytes returned, end exclusive like `GetObjectOptions::range`.
+    pub fn range(&self) -> Range<u64> {
+        self.range.clone()
+    }
+
+    // Size of the whole object.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+}
+
+impl FromStr for ContentRange {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::E(format!("invalid Content-Range: {}", s));
+        let (range, total) = s
+            .strip_prefix("bytes ")
+            .and_then(|s| s.split_once('/'))
+            .ok_or_else(invalid)?;
+        let (start, end) = range.split_once('-').ok_or_else(invalid)?;
+        let parse = |v: &str| v.parse::<u64>().map_err(|_| invalid());
+        let (start, end, total) = (parse(start)?, parse(end)?, parse(total)?);
+        if start > end || end >= total {
+            return Err(invalid());
+        }
+        Ok(ContentRange::new(start..end + 1, total))
+    }
+}
+
+// Part of an object fetched by `get_object_range`.
+#[derive(Clone, Debug)]
+pub struct ObjectRange {
+    data: Bytes,
+    content_range: ContentRange,
+}
+
+impl ObjectRange {
+    pub fn data(&self) -> &Bytes {
+        &self.data
+    }
+
+    pub fn into_data(self) -> Bytes {
+        self.data
+    }
+
+    pub fn content_range(&self) -> &ContentRange {
+        &self.content_range
+    }
+}
+
+impl OSS {
+    // Fetches `range` of `object`. Fails unless OSS answers 206 Partial
+    // Content: it ignores ranges it can not satisfy and sends the whole object
+    // instead, which this reports as an error rather than returning it. The
+    // returned range is clipped to the object size.
+    // https://help.aliyun.com/document_detail/31980.html
+    pub async fn get_object_range<S>(
+        &self,
+        object: S,
+        range: Range<u64>,
+    ) -> Result<ObjectRange, Error>
+    where
+        S: AsRef<str>,
+    {
+        let object = object.as_ref();
+        self.with_context("get_object_range", object, async {
+            let options = GetObjectOptions {
+                range: Some(range.clone()),
+                ..Default::default()
+            };
+            let resp = self.get_object_response(object, options).await?;
+            if resp.status() != StatusCode::PARTIAL_CONTENT {
+                return Err(Error::E(format!(
+                    "range {:?} was not honored, status: {}",
+                    range,
+                    resp.status()
+                )));
+            }
+            let content_range: ContentRange = resp
+                .headers()
+                .get(CONTENT_RANGE)
+                .and_then(|v| v.to_str().ok())
+                .ok_or_else(|| Error::E("response has no Content-Range".to_owned()))?
+                .parse()?;
+            Ok(ObjectRange {
+                data: resp.bytes().await?,
+                content_range,
+            })
+        })
+        .await
+    }
+
+    // Streams the body of `object` as it arrives instead of buffering it like
+    // `get_object`, so multi-GB objects are processed in bounded memory. A
+    // failed request is the stream's only item. Unlike `read_lines` it is a
+    // single GET, a dropped connection ends the stream with an error.
+    // https://help.aliyun.com/document_detail/31980.html
+    pub fn get_object_stream<'a, S>(
+        &'a self,
+        object: S,
+        options: &GetObjectOptions,
+    ) -> impl Stream<Item = Result<Bytes, Error>> + 'a
+    where
+        S: AsRef<str>,
+    {
+        let object = object.as_ref().to_owned();
+        let options = options.clone();
+        stream::once(async move {
+            self.with_context("get_object_stream", &object, async {
+                let resp = self.get_object_response(&object, options).await?;
+                // A ranged response still carries the CRC of the whole object.
+                let expected = match resp.status() {
+                    StatusCode::OK if self.verify_crc64_enabled() => server_crc64(resp.headers()),
+                    _ => None,
+                };
+                let chunks =
+                    resp.bytes_stream()
+                        .map_err(Error::from)
+                        .and_then(move |chunk| async move {
+                            self.throttle(chunk.len() as u64).await;
+                            Ok(chunk)
+                        });
+                Ok(verify_crc64(chunks, expected))
+            })
+            .await
+        })
+        .try_flatten()
+    }
+
+    // The body of `object` as a `tokio::io::AsyncRead`, to hand downloads to
+    // `tokio::io::copy`, decompressors or parsers without buffering them. A
+    // failed request surfaces as an `io::Error` wrapping the SDK error.
+    pub fn get_object_reader<S>(&self, object: S) -> impl AsyncRead + Unpin + '_
+    where
+        S: AsRef<str>,
+    {
+        let stream = self
+            .get_object_stream(object, &GetObjectOptions::default())
+            .map_err(io::Error::other);
+        StreamReader::new(Box::pin(stream))
+    }
+
+    // Downloads `object` to `path` through a temporary file next to it, which
+    // is renamed into place only once the body matched Content-Length and, when
+    // the object has one, its CRC64. A failed download never leaves a partial
+    // file at `path`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn get_object_to_file<S, P>(&self, object: S, path: P) -> Result<(), Error>
+    where
+        S: AsRef<str>,
+        P: AsRef<Path>,
+    {
+        let object = object.as_ref();
+        let path = path.as_ref();
+        self.with_context("get_object_to_file", object, async {
+            let resp = self
+                .get_object_response(object, GetObjectOptions::default())
+                .await?;
+            let tmp = temp_path(path);
+            let progress = resp
+                .content_length()
+                .and_then(|len| self.progress(object, len));
+            let result = write_verified(self, resp, &tmp, progress).await;
+            match result {
+                Ok(()) => rt::rename(&tmp, path).await.map_err(Error::from),
+                Err(e) => {
+                    let _ = rt::remove_file(&tmp).await;
+                    Err(e)
+                }
+            }
+        })
+        .await
+    }
+
+    // Downloads `object` to `path` as concurrent ranged GETs of
+    // `part_size` bytes, each written at its offset; the download-side
+    // counterpart to `parallel_upload`. Every range is pinned to the ETag
+    // from the initial HEAD, so an overwrite midway fails the download
+    // instead of mixing versions. Like `get_object_to_file`, `path` only
+    // appears once everything was written.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn download_file<S, P>(
+        &self,
+        object: S,
+        path: P,
+        options: DownloadOptions,
+    ) -> Result<(), Error>
+    where
+        S: AsRef<str>,
+        P: AsRef<Path>,
+    {
+        let object = object.as_ref();
+        let path = path.as_ref();
+        self.with_context("download_file", object, async {
+            let head = self.head_object(object, None, None).await?;
+            let header = |name| {
+                head.headers()
+                    .get(name)
+                    .and_then(|v| v.to_str().ok())
+                    .map(|v| v.to_owned())
+            };
+            let size: u64 = header(CONTENT_LENGTH.as_str())
+                .and_then(|v| v.parse().ok())
+                .ok_or_else(|| Error::E("object has no Content-Length".to_owned()))?;
+            let mut headers = HashMap::new();
+            if let Some(etag) = header(ETAG.as_str()) {
+                headers.insert(IF_MATCH.as_str().to_owned(), etag);
+            }
+
+            let tmp = temp_path(path);
+            let progress = self.progress(object, size);
+            let result = async {
+                let file = rt::create(&tmp).await?;
+                file.set_len(size).await?;
+                let traffic_limit = options.traffic_limit;
+                let run = run_tuned(
+                    part_ranges(size, options.part_size),
+                    options.concurrency,
+                    |range| range.end - range.start,
+                    |range| {
+                        let oss = self.clone();
+                        let (tmp, object) = (tmp.clone(), object.to_owned());
+                        let (headers, progress) = (headers.clone(), progress.clone());
+                        self.spawn_child("download-part", async move {
+                            let options = GetObjectOptions {
+                                range: Some(range.clone()),
+                                headers,
+                                traffic_limit,
+                                ..Default::default()
+                            };
+                            let mut file = rt::open_write(&tmp).await?;
+                            file.seek(SeekFrom::Start(range.start)).await?;
+                            let stream = oss.get_object_stream(&object, &options);
+                            futures::pin_mut!(stream);
+                            let mut written = 0;
+                            let mut crc = Crc64::new();
+                            while let Some(chunk) = stream.try_next().await? {
+                                written += chunk.len() as u64;
+                                crc.update(&chunk);
+                                file.write_all(&chunk).await?;
+                                if let Some(progress) = &progress {
+                                    progress.advance(chunk.len() as u64);
+                                }
+                            }
+                            if written != range.end - range.start {
+                                return Err(Error::E(format!(
+                                    "range {:?} returned {} bytes",
+                                    range, written
+                                )));
+                            }
+                            file.flush().await?;
+                            file.sync_all().await?;
+                            Ok((crc.value(), written))
+                        })
+                    },
+                );
+                let parts = match &options.cancel {
+                    Some(cancel) => until_cancelled(cancel, run).await?,
+                    None => run.await?,
+                };
+                // The ranges only carry the CRC of the whole object.
+                let crc = parts
+                    .iter()
+                    .fold(0, |crc, &(part, len)| Crc64::combine(crc, part, len));
+                self.check_crc64(crc, head.headers())
+            }
+            .await;
+            match result {
+                Ok(_) => rt::rename(&tmp, path).await.map_err(Error::from),
+                Err(e) => {
+                    let _ = rt::remove_file(&tmp).await;
+                    Err(e)
+                }
+            }
+        })
+        .await
+    }
+
+    async fn get_object_response(
+        &self,
+        object: &str,
+        options: GetObjectOptions,
+    ) -> Result<Response, Error> {
+        let mut headers = to_headers(options.headers)?;
+        insert_traffic_limit(&mut headers, options.traffic_limit)?;
+        if let Some(range) = &options.range {
+            if range.is_empty() {
+                return Err(Error::E(format!("empty range {:?}", range)));
+            }
+            headers.insert(
+                RANGE,
+                format!("bytes={}-{}", range.start, range.end - 1).parse()?,
+            );
+        }
+        let params = [("versionId", options.version_id)];
+        let request = self.signed_request(Method::GET, object, &params, headers, Vec::new());
+        let resp = match options.timeout {
+            Some(timeout) => self.with_timeout(timeout, request).await?,
+            None => request.await?,
+        };
+        if !resp.status().is_success() {
+            return Err(self
+                .api_error(resp, |reason| {
+                    Error::Object(ObjectError::GetError {
+                        msg: format!("can not get object, reason: {:?}", reason),
+                    })
+                })
+                .await);
+        }
+        Ok(resp)
+    }
+}
+
+// `<name>.download` in the same directory, so the rename stays on one
+// filesystem and is atomic.
+#[cfg(not(target_arch = "wasm32"))]
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(".download");
+    path.with_file_name(name)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn write_verified(
+    oss: &OSS,
+    resp: Response,
+    tmp: &Path,
+    progress: Option<Arc<Progress>>,
+) -> Result<(), Error> {
+    let header = |name: &str| {
+        resp.headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok())
+    };
+    let expected_size = header(CONTENT_LENGTH.as_str());
+    let expected_crc = header("x-oss-hash-crc64ecma");
+
+    let mut file = rt::create(tmp).await?;
+    let mut size = 0;
+    let mut crc = Crc64::new();
+    let chunks = resp.bytes_stream();
+    futures::pin_mut!(chunks);
+    while let Some(chunk) = chunks.try_next().await? {
+        oss.throttle(chunk.len() as u64).await;
+        size += chunk.len() as u64;
+        crc.update(&chunk);
+        file.write_all(&chunk).await?;
+        if let Some(progress) = &progress {
+            progress.advance(chunk.len() as u64);
+        }
+    }
+    file.flush().await?;
+    file.sync_all().await?;
+    verify(size, crc.value(), expected_size, expected_crc)
+}
+
+// Passes `stream` through, ending it with `Error::ChecksumMismatch` when
+// the CRC64 of its chunks differs from `expected`.
+fn verify_crc64<S>(stream: S, expected: Option<u64>) -> impl Stream<Item = Result<Bytes, Error>>
+where
+    S: Stream<Item = Result<Bytes, Error>>,
+{
+    let state = (Box::pin(stream.fuse()), Crc64::new(), expected);
+    stream::unfold(state, |(mut stream, mut crc, expected)| async move {
+        match stream.next().await {
+            Some(Ok(chunk)) => {
+                if expected.is_some() {
+                    crc.update(&chunk);
+                }
+                Some((Ok(chunk), (stream, crc, expected)))
+            }
+            // Nothing left to compare after a failure.
+            Some(Err(e)) => Some((Err(e), (stream, crc, None))),
+            None => match expected {
+                Some(server) if server != crc.value() => {
+                    let mismatch = ChecksumMismatch::new(crc.value(), server);
+                    Some((Err(Error::ChecksumMismatch(mismatch)), (stream, crc, None)))
+                }
+                _ => None,
+            },
+        }
+    })
+}
+
+fn verify(
+    size: u64,
+    crc: u64,
+    expected_size: Option<u64>,
+    expected_crc: Option<u64>,
+) -> Result<(), Error> {
+    if let Some(expected) = expected_size {
+        if size != expected {
+            return Err(Error::E(format!(
+                "downloaded {} bytes, Content-Length is {}",
+                size, expected
+            )));
+        }
+    }
+    if let Some(expected) = expected_crc {
+        if crc != expected {
+            return Err(Error::ChecksumMismatch(ChecksumMismatch::new(
+                crc, expected,
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+
+    #[tokio::test]
+    async fn test_get_object_stream_rejects_empty_range() {
+        let oss_instance = OSS::new(
+            "xxx".to_string(),
+            "xxx".to_string(),
+            "xxx.aliyuncs.com".to_string(),
+            "xxx".to_string(),
+        );
+        let options = GetObjectOptions {
+            range: Some(10..10),
+            ..Default::default()
+        };
+        let mut stream = Box::pin(oss_instance.get_object_stream("big.bin", &options));
+        assert!(stream.next().await.unwrap().is_err());
+        assert!(stream.next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_get_object_reader_surfaces_errors() {
+        let oss_instance = OSS::new(
+            "xxx".to_string(),
+            "xxx".to_string(),
+            "invalid host".to_string(),
+            "xxx".to_string(),
+        );
+        let mut reader = oss_instance.get_object_reader("big.bin");
+        let mut buf = Vec::new();
+        let err = tokio::io::copy(&mut reader, &mut buf).await.unwrap_err();
+        assert!(err.get_ref().unwrap().is::<Error>());
+    }
+
+    #[tokio::test]
+    async fn test_download_file_leaves_nothing_on_failure() {
+        let oss_instance = OSS::new(
+            "xxx".to_string(),
+            "xxx".to_string(),
+            "invalid host".to_string(),
+            "xxx".to_string(),
+        );
+        let path = std::env::temp_dir().join("oss-sdk-download-file.bin");
+        let result = oss_instance
+            .download_file("big.bin", &path, DownloadOptions::default())
+            .await;
+        assert!(result.is_err());
+        assert!(!path.exists());
+        assert!(!temp_path(&path).exists());
+    }
+
+    // Claims every object has a CRC64 of 1.
+    #[cfg(feature = "test-util")]
+    #[derive(Debug)]
+    struct WrongCrc;
+
+    #[cfg(feature = "test-util")]
+    impl crate::interceptor::RequestInterceptor for WrongCrc {
+        fn after_receive(&self, mut response: Response) -> Response {
+            if response.headers().contains_key("x-oss-hash-crc64ecma") {
+                response
+                    .headers_mut()
+                    .insert("x-oss-hash-crc64ecma", "1".parse().unwrap());
+            }
+            response
+        }
+    }
+
+    #[cfg(feature = "test-util")]
+    #[tokio::test]
+    async fn test_downloads_verify_crc64() {
+        let fake = crate::test_util::FakeOss::start().await.unwrap();
+        let data: Vec<u8> = (0..250 * 1024).map(|i| (i % 251) as u8).collect();
+        fake.insert("examplebucket", "big.bin", data.clone());
+        let path = std::env::temp_dir().join("oss-sdk-download-crc64.bin");
+        let options = || DownloadOptions {
+            part_size: 100 * 1024,
+            ..Default::default()
+        };
+
+        let oss_instance = fake.builder("examplebucket").verify_crc64(true).build();
+        oss_instance
+            .download_file("big.bin", &path, options())
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), data);
+        std::fs::remove_file(&path).unwrap();
+        let stream = oss_instance.get_object_stream("big.bin", &GetObjectOptions::default());
+        let streamed: Vec<Bytes> = stream.try_collect().await.unwrap();
+        assert_eq!(streamed.concat(), data);
+        let mut buf = Vec::new();
+        oss_instance
+            .get_object_into("big.bin", &mut buf)
+            .await
+            .unwrap();
+
+        let oss_instance = fake
+            .builder("examplebucket")
+            .verify_crc64(true)
+            .interceptor(WrongCrc)
+            .build();
+        let mismatch =
+            |e: &Error| matches!(e.kind(), Error::ChecksumMismatch(e) if e.server() == 1);
+        let err = oss_instance
+            .download_file("big.bin", &path, options())
+            .await
+            .unwrap_err();
+        assert!(mismatch(&err));
+        assert!(!path.exists());
+        let stream = oss_instance.get_object_stream("big.bin", &GetObjectOptions::default());
+        assert!(mismatch(&stream.try_collect::<Vec<_>>().await.unwrap_err()));
+        let mut reader = oss_instance.get_object_reader("big.bin");
+        let err = tokio::io::copy(&mut reader, &mut tokio::io::sink())
+            .await
+            .unwrap_err();
+        assert!(mismatch(err.get_ref().unwrap().downcast_ref().unwrap()));
+        let err = oss_instance
+            .get_object_into("big.bin", &mut buf)
+            .await
+            .unwrap_err();
+        assert!(mismatch(&err));
+    }
+
+    #[test]
+    fn test_parse_content_range() {
+        let range: ContentRange = "bytes 0-1023/4096".parse().unwrap();
+        assert_eq!(range.range(), 0..1024);
+        assert_eq!(range.total(), 4096);
+        assert!("bytes */4096".parse::<ContentRange>().is_err());
+        assert!("bytes 10-5/4096".parse::<ContentRange>().is_err());
+        assert!("bytes 0-4096/4096".parse::<ContentRange>().is_err());
+    }
+
+    #[test]
+    fn test_temp_path() {
+        assert_eq!(
+            temp_path(Path::new("/data/big.bin")),
+            PathBuf::from("/data/big.bin.download")
+        );
+    }
+
+    #[test]
+    fn test_verify() {
+        let mut crc = Crc64::new();
+        crc.update(b"hello");
+        let crc = crc.value();
+        assert!(verify(5, crc, Some(5), Some(crc)).is_ok());
+        assert!(verify(5, crc, None, None).is_ok());
+        assert!(verify(4, crc, Some(5), Some(crc)).is_err());
+        assert!(matches!(
+            verify(5, crc, Some(5), Some(crc + 1)),
+            Err(Error::ChecksumMismatch(e)) if e.server() == crc + 1
+        ));
+    }
+}
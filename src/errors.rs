@@ -1,22 +1,358 @@
 use quick_xml::Error as QxmlError;
+use reqwest::header::HeaderMap;
 use reqwest::header::InvalidHeaderName as HttpInvalidHeaderNameError;
 use reqwest::header::InvalidHeaderValue as HttpInvalidHeaderValueError;
 use reqwest::Error as ReqwestError;
+use reqwest::StatusCode;
+use serde_derive::Deserialize;
 use serde_xml_rs::Error as XmlError;
 use std::error::Error as StdError;
+use std::fmt;
 use std::io::Error as IoError;
 use std::string::FromUtf8Error;
 
 #[derive(Debug, Display)]
 pub enum Error {
+    Api(Box<OssApiError>),
     Object(ObjectError),
     Io(IoError),
     String(FromUtf8Error),
     Reqwest(ReqwestError),
     Qxml(QxmlError),
     Xml(XmlError),
+    XmlLimit(XmlLimitError),
+    ChecksumMismatch(ChecksumMismatch),
     Http(HttpError),
     E(String),
+    #[display(fmt = "{}: {}", _0, _1)]
+    Context(Box<ErrorContext>, Box<Error>),
+}
+
+impl Error {
+    // Attaches the request context unless the error already carries one, the
+    // innermost operation is the most precise.
+    pub fn with_context(self, context: ErrorContext) -> Error {
+        match self {
+            Error::Context(..) => self,
+            e => Error::Context(Box::new(context), Box::new(e)),
+        }
+    }
+
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            Error::Context(context, _) => Some(context),
+            _ => None,
+        }
+    }
+
+    // The OSS error code, e.g. NoSuchUpload, of an API error. Errors without
+    // a body, e.g. of HEAD requests, have none.
+    pub fn code(&self) -> Option<&str> {
+        match self.kind() {
+            Error::Api(e) if e.code().is_empty() => None,
+            Error::Api(e) => Some(e.code()),
+            _ => None,
+        }
+    }
+
+    // The x-oss-request-id of the response the error came from, or else of
+    // the last response the failed call received. Aliyun support needs it to
+    // trace a request.
+    pub fn request_id(&self) -> Option<&str> {
+        match self.kind() {
+            Error::Api(e) if !e.request_id().is_empty() => Some(e.request_id()),
+            _ => self.context().and_then(|c| c.request_id()),
+        }
+    }
+
+    // The EC diagnostic code, e.g. 0003-00000001, which pinpoints the cause
+    // more precisely than the error code.
+    pub fn ec(&self) -> Option<&str> {
+        match self.kind() {
+            Error::Api(e) => e.ec(),
+            _ => None,
+        }
+    }
+
+    // What to check first to fix the error, looked up by error code.
+    pub fn hint(&self) -> Option<&'static str> {
+        let code = self.code()?;
+        HINTS
+            .iter()
+            .find(|(c, _)| *c == code)
+            .map(|(_, hint)| *hint)
+    }
+
+    // The diagnosis page for the EC code on the Aliyun troubleshooting site.
+    pub fn troubleshoot_url(&self) -> Option<String> {
+        self.ec()
+            .map(|ec| format!("https://api.aliyun.com/troubleshoot?q={}", ec))
+    }
+
+    // Returns the underlying error without its context.
+    pub fn kind(&self) -> &Error {
+        match self {
+            Error::Context(_, e) => e.kind(),
+            e => e,
+        }
+    }
+}
+
+const HINTS: [(&str, &str); 12] = [
+    (
+        "AccessDenied",
+        "check the RAM policy and bucket policy grant this action on the bucket and key",
+    ),
+    (
+        "SignatureDoesNotMatch",
+        "check the AccessKey secret, and that headers and sub-resources were not changed after signing",
+    ),
+    (
+        "InvalidAccessKeyId",
+        "check the AccessKey ID exists and is enabled, STS credentials may have expired",
+    ),
+    (
+        "SecurityTokenExpired",
+        "request new STS credentials, the security token has expired",
+    ),
+    (
+        "RequestTimeTooSkewed",
+        "sync the local clock, requests must be dated within 15 minutes of server time",
+    ),
+    (
+        "NoSuchBucket",
+        "check the bucket name and that the endpoint is in the bucket's region",
+    ),
+    ("NoSuchKey", "check the object key, keys are case sensitive"),
+    (
+        "NoSuchUpload",
+        "the multipart upload was completed, aborted or expired, start a new one",
+    ),
+    (
+        "InvalidDigest",
+        "the body was modified in transit or Content-MD5 was computed over different bytes",
+    ),
+    (
+        "PositionNotEqualToLength",
+        "append at the object's current length, see x-oss-next-append-position",
+    ),
+    (
+        "EntityTooLarge",
+        "use a multipart upload, single PUT requests are limited to 5 GB",
+    ),
+    (
+        "PermanentRedirect",
+        "use the endpoint in the error's Endpoint element, the bucket is in another region",
+    ),
+];
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ErrorContext {
+    operation: String,
+    bucket: String,
+    key: String,
+    endpoint: String,
+    attempt: u32,
+    request_id: Option<String>,
+}
+
+impl ErrorContext {
+    pub fn new<S>(operation: S, bucket: S, key: S, endpoint: S, attempt: u32) -> Self
+    where
+        S: Into<String>,
+    {
+        ErrorContext {
+            operation: operation.into(),
+            bucket: bucket.into(),
+            key: key.into(),
+            endpoint: endpoint.into(),
+            attempt,
+            request_id: None,
+        }
+    }
+
+    pub fn with_request_id(mut self, request_id: Option<String>) -> Self {
+        self.request_id = request_id;
+        self
+    }
+
+    pub fn operation(&self) -> &str {
+        &self.operation
+    }
+
+    pub fn bucket(&self) -> &str {
+        &self.bucket
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    pub fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    // Of the last response received before the error.
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "operation={} bucket={} key={} endpoint={} attempt={}",
+            self.operation, self.bucket, self.key, self.endpoint, self.attempt
+        )?;
+        if let Some(request_id) = &self.request_id {
+            write!(f, " request_id={}", request_id)?;
+        }
+        Ok(())
+    }
+}
+
+// An error response from OSS, parsed from its
+// <Error><Code><Message><RequestId><HostId> body, or made from the status and
+// headers of a response without one, e.g. to a HEAD request.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct OssApiError {
+    #[serde(skip, default = "bad_request")]
+    status: StatusCode,
+    code: String,
+    #[serde(default)]
+    message: String,
+    #[serde(default)]
+    request_id: String,
+    #[serde(default)]
+    host_id: String,
+    #[serde(rename = "EC")]
+    ec: Option<String>,
+}
+
+fn bad_request() -> StatusCode {
+    StatusCode::BAD_REQUEST
+}
+
+impl OssApiError {
+    // The error in `body`, None if it holds none, e.g. a proxy's HTML page.
+    pub fn parse(status: StatusCode, body: &str) -> Option<Self> {
+        let error: OssApiError = serde_xml_rs::from_str(body).ok()?;
+        Some(OssApiError { status, ..error })
+    }
+
+    // The error of a response without a body, which has no code.
+    pub(crate) fn from_status(status: StatusCode, headers: &HeaderMap) -> Self {
+        OssApiError {
+            status,
+            code: String::new(),
+            message: status.canonical_reason().unwrap_or_default().to_owned(),
+            request_id: String::new(),
+            host_id: String::new(),
+            ec: None,
+        }
+        .with_headers(headers)
+    }
+
+    // Fills the request id and EC the body left out from the x-oss-request-id
+    // and x-oss-ec headers.
+    pub(crate) fn with_headers(mut self, headers: &HeaderMap) -> Self {
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_owned())
+        };
+        if self.request_id.is_empty() {
+            self.request_id = header("x-oss-request-id").unwrap_or_default();
+        }
+        if self.ec.is_none() {
+            self.ec = header("x-oss-ec");
+        }
+        self
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    // E.g. NoSuchKey, SignatureDoesNotMatch or BucketAlreadyExists, empty
+    // for responses without a body.
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn request_id(&self) -> &str {
+        &self.request_id
+    }
+
+    pub fn host_id(&self) -> &str {
+        &self.host_id
+    }
+
+    pub fn ec(&self) -> Option<&str> {
+        self.ec.as_deref()
+    }
+}
+
+impl fmt::Display for OssApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.code.is_empty() {
+            write!(f, "{}: ", self.code)?;
+        }
+        write!(
+            f,
+            "{} (status {}, request id {})",
+            self.message, self.status, self.request_id
+        )
+    }
+}
+
+// An XML response outside the client's `XmlLimits`.
+#[derive(Debug, Display, PartialEq)]
+pub enum XmlLimitError {
+    #[display(fmt = "XML response larger than {} bytes", limit)]
+    Size { limit: usize },
+    #[display(fmt = "XML response nested deeper than {} elements", limit)]
+    Depth { limit: usize },
+    #[display(fmt = "XML response with a DOCTYPE")]
+    DocType,
+}
+
+// The CRC64 of the bytes sent or received differs from the
+// x-oss-hash-crc64ecma the server reported, the data was corrupted on the way.
+#[derive(Clone, Copy, Debug, Display, PartialEq)]
+#[display(
+    fmt = "CRC64 mismatch, client computed {}, server reported {}",
+    client,
+    server
+)]
+pub struct ChecksumMismatch {
+    client: u64,
+    server: u64,
+}
+
+impl ChecksumMismatch {
+    pub fn new(client: u64, server: u64) -> Self {
+        ChecksumMismatch { client, server }
+    }
+
+    pub fn client(&self) -> u64 {
+        self.client
+    }
+
+    pub fn server(&self) -> u64 {
+        self.server
+    }
 }
 
 #[derive(Debug, Display)]
@@ -77,6 +413,141 @@ pub enum ObjectError {
     CopyError { msg: String },
     #[display(fmt = "DELETE ERROR: {}", msg)]
     DeleteError { msg: String },
+    #[display(fmt = "SELECT ERROR: {}", msg)]
+    SelectError { msg: String },
+}
+
+impl ObjectError {
+    pub fn msg(&self) -> &str {
+        match self {
+            ObjectError::PutError { msg }
+            | ObjectError::GetError { msg }
+            | ObjectError::CopyError { msg }
+            | ObjectError::DeleteError { msg }
+            | ObjectError::SelectError { msg } => msg,
+        }
+    }
 }
 
 impl StdError for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_context() {
+        let context = ErrorContext::new("put_object", "bucket", "key", "oss.aliyuncs.com", 1);
+        let err = Error::E("boom".to_owned()).with_context(context.clone());
+        assert_eq!(err.context(), Some(&context));
+        assert_eq!(
+            err.to_string(),
+            "operation=put_object bucket=bucket key=key endpoint=oss.aliyuncs.com attempt=1: boom"
+        );
+
+        assert_eq!(err.request_id(), None);
+
+        let outer = ErrorContext::new("chunk_upload", "bucket", "key", "oss.aliyuncs.com", 1);
+        let err = err.with_context(outer);
+        assert_eq!(err.context().unwrap().operation(), "put_object");
+        assert!(matches!(err.kind(), Error::E(_)));
+
+        let context = ErrorContext::new("put_object", "bucket", "key", "oss.aliyuncs.com", 1)
+            .with_request_id(Some("5C06A3B67B8B5A3DA422299D".to_owned()));
+        let err = Error::E("boom".to_owned()).with_context(context);
+        assert_eq!(err.request_id(), Some("5C06A3B67B8B5A3DA422299D"));
+        assert!(err
+            .to_string()
+            .contains("request_id=5C06A3B67B8B5A3DA422299D"));
+    }
+
+    // Only parsed error responses have a code, message text is not searched.
+    #[test]
+    fn test_error_code() {
+        let api = OssApiError::parse(
+            StatusCode::NOT_FOUND,
+            "<Error><Code>NoSuchUpload</Code></Error>",
+        )
+        .unwrap();
+        let context = ErrorContext::new("complete", "bucket", "key", "oss.aliyuncs.com", 1);
+        let err = Error::Api(Box::new(api)).with_context(context);
+        assert_eq!(err.code(), Some("NoSuchUpload"));
+        let err = Error::Object(ObjectError::PutError {
+            msg: "can not put object, reason: Ok(\"<Code>NoSuchUpload</Code>\")".to_owned(),
+        });
+        assert_eq!(err.code(), None);
+    }
+
+    #[test]
+    fn test_api_error() {
+        let body = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Error>\n  \
+                    <Code>NoSuchKey</Code>\n  <Message>The specified key does not exist.</Message>\n  \
+                    <RequestId>5C3D9175B6FC201293AD****</RequestId>\n  \
+                    <HostId>examplebucket.oss-cn-hangzhou.aliyuncs.com</HostId>\n  \
+                    <Key>a.txt</Key>\n  <EC>0026-00000001</EC>\n</Error>";
+        let api = OssApiError::parse(StatusCode::NOT_FOUND, body).unwrap();
+        assert_eq!(api.status(), StatusCode::NOT_FOUND);
+        assert_eq!(api.message(), "The specified key does not exist.");
+        assert_eq!(api.request_id(), "5C3D9175B6FC201293AD****");
+        assert_eq!(api.host_id(), "examplebucket.oss-cn-hangzhou.aliyuncs.com");
+        assert_eq!(
+            api.to_string(),
+            "NoSuchKey: The specified key does not exist. \
+             (status 404 Not Found, request id 5C3D9175B6FC201293AD****)"
+        );
+
+        let context = ErrorContext::new("get_object", "bucket", "a.txt", "oss.aliyuncs.com", 1);
+        let err = Error::Api(Box::new(api)).with_context(context);
+        assert_eq!(err.code(), Some("NoSuchKey"));
+        assert_eq!(err.ec(), Some("0026-00000001"));
+        assert!(matches!(err.kind(), Error::Api(e) if e.code() == "NoSuchKey"));
+
+        assert!(OssApiError::parse(StatusCode::BAD_GATEWAY, "<html>bad gateway</html>").is_none());
+    }
+
+    #[test]
+    fn test_error_diagnostics() {
+        let body = "<Error><Code>NoSuchKey</Code></Error>";
+        let mut headers = HeaderMap::new();
+        headers.insert("x-oss-ec", "0026-00000001".parse().unwrap());
+        headers.insert(
+            "x-oss-request-id",
+            "5C3D9175B6FC201293AD0000".parse().unwrap(),
+        );
+        let api = OssApiError::parse(StatusCode::NOT_FOUND, body)
+            .unwrap()
+            .with_headers(&headers);
+        let err = Error::Api(Box::new(api));
+        assert_eq!(err.ec(), Some("0026-00000001"));
+        assert_eq!(err.request_id(), Some("5C3D9175B6FC201293AD0000"));
+        assert_eq!(
+            err.hint(),
+            Some("check the object key, keys are case sensitive")
+        );
+        assert_eq!(
+            err.troubleshoot_url().unwrap(),
+            "https://api.aliyun.com/troubleshoot?q=0026-00000001"
+        );
+
+        // A HEAD response has only its status and headers.
+        let err = Error::Api(Box::new(OssApiError::from_status(
+            StatusCode::NOT_FOUND,
+            &headers,
+        )));
+        assert_eq!(err.code(), None);
+        assert_eq!(err.ec(), Some("0026-00000001"));
+        assert_eq!(
+            err.to_string(),
+            "Not Found (status 404 Not Found, request id 5C3D9175B6FC201293AD0000)"
+        );
+
+        let msg = "can not get object, reason: Ok(\"<Error><EC>0026-00000001</EC></Error>\")";
+        let err = Error::Object(ObjectError::GetError {
+            msg: msg.to_owned(),
+        });
+        assert_eq!(err.ec(), None);
+        let err = Error::E("boom".to_owned());
+        assert_eq!(err.ec(), None);
+        assert_eq!(err.hint(), None);
+    }
+}
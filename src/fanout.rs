@@ -0,0 +1,111 @@
+use futures::future::join_all;
+use reqwest::header::{HeaderMap, CONTENT_LENGTH};
+use reqwest::Method;
+use std::sync::Arc;
+
+use crate::errors::{Error, ObjectError};
+use crate::oss::OSS;
+
+// Writes the same object to several buckets, possibly in different regions,
+// concurrently. Every target is attempted; the result reports which ones
+// failed instead of stopping at the first failure.
+#[derive(Clone, Debug)]
+pub struct FanoutWriter {
+    targets: Vec<Arc<OSS>>,
+}
+
+#[derive(Debug)]
+pub struct FanoutResult {
+    outcomes: Vec<(Arc<OSS>, Result<(), Error>)>,
+}
+
+impl FanoutResult {
+    // Per target outcomes, in the order the targets were given.
+    pub fn outcomes(&self) -> &[(Arc<OSS>, Result<(), Error>)] {
+        &self.outcomes
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = (&OSS, &Error)> {
+        self.outcomes.iter().filter_map(|(target, r)| match r {
+            Err(e) => Some((target.as_ref(), e)),
+            Ok(()) => None,
+        })
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.outcomes.iter().all(|(_, r)| r.is_ok())
+    }
+}
+
+impl FanoutWriter {
+    // Each target writes to its own bucket, use `OSS::bucket_handle` for
+    // buckets in the same region.
+    pub fn new(targets: Vec<Arc<OSS>>) -> Self {
+        FanoutWriter { targets }
+    }
+
+    pub fn targets(&self) -> &[Arc<OSS>] {
+        &self.targets
+    }
+
+    pub async fn put_object<S>(&self, object: S, buf: &[u8]) -> FanoutResult
+    where
+        S: AsRef<str>,
+    {
+        let object = object.as_ref();
+        let writes = self.targets.iter().map(|target| async move {
+            let result = target
+                .with_context("fanout_put_object", object, put(target, object, buf))
+                .await;
+            (target.clone(), result)
+        });
+        FanoutResult {
+            outcomes: join_all(writes).await,
+        }
+    }
+}
+
+async fn put(target: &OSS, object: &str, buf: &[u8]) -> Result<(), Error> {
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_LENGTH, buf.len().to_string().parse()?);
+    let resp = target
+        .signed_request(Method::PUT, object, &[], headers, buf.to_owned())
+        .await?;
+    if resp.status().is_success() {
+        Ok(())
+    } else {
+        Err(target
+            .api_error(resp, |reason| {
+                Error::Object(ObjectError::PutError {
+                    msg: format!("can not put object, reason: {:?}", reason),
+                })
+            })
+            .await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_fanout_reports_every_target() {
+        // Nothing listens on these ports, both writes fail without network.
+        let target = |bucket: &str, port: u16| {
+            Arc::new(OSS::new(
+                "id".to_owned(),
+                "secret".to_owned(),
+                format!("http://127.0.0.1:{}", port),
+                bucket.to_owned(),
+            ))
+        };
+        let writer = FanoutWriter::new(vec![target("a", 1), target("b", 1)]);
+        let result = writer.put_object("key", b"data").await;
+        assert_eq!(result.outcomes().len(), 2);
+        assert!(!result.is_complete());
+        let failed: Vec<_> = result.failed().map(|(t, _)| t.bucket()).collect();
+        assert_eq!(failed, ["a", "b"]);
+        let (_, e) = result.failed().next().unwrap();
+        assert_eq!(e.context().unwrap().operation(), "fanout_put_object");
+    }
+}
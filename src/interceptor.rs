@@ -0,0 +1,134 @@
+use reqwest::{Request, RequestBuilder, Response};
+use std::fmt;
+use web_time::Instant;
+
+use crate::errors::Error;
+use crate::metrics;
+use crate::oss::OSS;
+use crate::raw::record;
+#[cfg(target_arch = "wasm32")]
+use crate::timeout::with_deadline;
+use crate::timeout::Timed;
+use crate::trace;
+
+// Sees every request a client sends to OSS and every response it gets back,
+// e.g. to add headers, keep an audit log or answer from fixtures in tests.
+// Added with `OSSBuilder::interceptor`; they run in the order they were
+// added before sending and in the reverse order after receiving.
+pub trait RequestInterceptor: fmt::Debug + Send + Sync {
+    // Called with the signed request, so headers added here are not signed.
+    // Returning a response skips sending, and the `before_send` of later
+    // interceptors; it is then handled as if OSS had returned it.
+    fn before_send(&self, request: &mut Request) -> Option<Response> {
+        let _ = request;
+        None
+    }
+
+    // Called with every response, including those from `before_send`.
+    // `response.url()` is the URL the request was sent to.
+    fn after_receive(&self, response: Response) -> Response {
+        response
+    }
+}
+
+impl OSS {
+    // Sends `req` through the interceptors, bounded by the timeout of an
+    // enclosing `with_timeout`, and reports it to tracing and the metrics
+    // recorder. Every request to OSS goes through here.
+    pub(crate) async fn send(&self, req: RequestBuilder) -> Result<Response, Error> {
+        let mut request = req.timed().build()?;
+        let intercepted = self
+            .interceptors()
+            .iter()
+            .find_map(|interceptor| interceptor.before_send(&mut request));
+        let start = Instant::now();
+        let bytes_sent = metrics::bytes_sent(&request);
+        let resp = match intercepted {
+            Some(resp) => resp,
+            #[cfg(not(target_arch = "wasm32"))]
+            None => match self.transport().execute(request).await {
+                Ok(resp) => resp,
+                Err(e) => {
+                    self.record_metrics(None, bytes_sent, start);
+                    return Err(e);
+                }
+            },
+            #[cfg(target_arch = "wasm32")]
+            None => match with_deadline(self.transport().execute(request)).await {
+                Ok(resp) => resp,
+                Err(e) => {
+                    self.record_metrics(None, bytes_sent, start);
+                    return Err(e);
+                }
+            },
+        };
+        let resp = self
+            .interceptors()
+            .iter()
+            .rev()
+            .fold(resp, |resp, interceptor| interceptor.after_receive(resp));
+        trace::record_response(&resp);
+        self.record_metrics(Some(&resp), bytes_sent, start);
+        Ok(record(resp))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, Default)]
+    struct Fixture(Arc<Mutex<Vec<String>>>);
+
+    impl RequestInterceptor for Fixture {
+        fn before_send(&self, request: &mut Request) -> Option<Response> {
+            request
+                .headers_mut()
+                .insert("x-audit", HeaderValue::from_static("1"));
+            self.0.lock().unwrap().push(format!(
+                "{} {} {}",
+                request.method(),
+                request.url().path(),
+                request.headers()["x-audit"].to_str().unwrap()
+            ));
+            let resp = http::Response::builder()
+                .status(200)
+                .header("x-oss-request-id", "5C06A3B67B8B5A3DA422299D")
+                .body("hello")
+                .unwrap();
+            Some(Response::from(resp))
+        }
+
+        fn after_receive(&self, response: Response) -> Response {
+            self.0
+                .lock()
+                .unwrap()
+                .push(response.status().as_str().to_owned());
+            response
+        }
+    }
+
+    #[tokio::test]
+    async fn test_interceptor() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let oss_instance = OSS::builder(
+            "xxx".to_string(),
+            "xxx".to_string(),
+            "xxx.aliyuncs.com".to_string(),
+            "xxx".to_string(),
+        )
+        .interceptor(Fixture(log.clone()))
+        .build();
+        let body = oss_instance
+            .get_object("hello.txt", None, None)
+            .await
+            .unwrap();
+        assert_eq!(body, "hello");
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec!["GET /hello.txt 1".to_owned(), "200".to_owned()]
+        );
+    }
+}
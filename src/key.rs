@@ -0,0 +1,160 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::errors::Error;
+
+// Longest key OSS accepts, in bytes of UTF-8.
+const MAX_KEY_LEN: usize = 1023;
+
+// Valid keys that OSS stores fine but the console file browser, ossutil and
+// most sync tools treat as directories in surprising ways.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeyWarning {
+    // "a//b", shows up as a directory with no name.
+    EmptySegment,
+    // "a/../b", is not resolved by OSS and can not be browsed to.
+    ParentSegment,
+    // "a /b", hard to tell apart from "a/b" in listings.
+    TrailingWhitespace,
+}
+
+// An object key checked against the OSS naming rules, so a bad key fails
+// here with a clear message instead of a bare 400 from the server. `./`
+// segments are dropped. Usable wherever a key is taken as `AsRef<str>`.
+// https://help.aliyun.com/document_detail/273129.html
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ObjectKey(String);
+
+impl ObjectKey {
+    pub fn new<S: AsRef<str>>(key: S) -> Result<Self, Error> {
+        let key = normalize(key.as_ref());
+        if key.is_empty() {
+            return Err(Error::E("object key is empty".to_owned()));
+        }
+        if key.len() > MAX_KEY_LEN {
+            return Err(Error::E(format!(
+                "object key is {} bytes, longer than {}",
+                key.len(),
+                MAX_KEY_LEN
+            )));
+        }
+        if key.starts_with('/') || key.starts_with('\\') {
+            return Err(Error::E(format!(
+                "object key {:?} starts with a slash",
+                key
+            )));
+        }
+        if let Some(c) = key.chars().find(|c| c.is_control()) {
+            return Err(Error::E(format!(
+                "object key {:?} contains control character {:?}",
+                key, c
+            )));
+        }
+        let key = ObjectKey(key);
+        for warning in key.warnings() {
+            warn!("object key {:?} may not be browsable: {:?}", key.0, warning);
+        }
+        Ok(key)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_string(self) -> String {
+        self.0
+    }
+
+    pub fn warnings(&self) -> Vec<KeyWarning> {
+        let mut warnings = Vec::new();
+        let segments: Vec<&str> = self.0.split('/').collect();
+        // The last segment is empty for directory keys like "a/".
+        let inner = &segments[..segments.len() - 1];
+        if inner.iter().any(|s| s.is_empty()) {
+            warnings.push(KeyWarning::EmptySegment);
+        }
+        if segments.contains(&"..") {
+            warnings.push(KeyWarning::ParentSegment);
+        }
+        if segments.iter().any(|s| s.ends_with(char::is_whitespace)) {
+            warnings.push(KeyWarning::TrailingWhitespace);
+        }
+        warnings
+    }
+}
+
+impl AsRef<str> for ObjectKey {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for ObjectKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for ObjectKey {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ObjectKey::new(s)
+    }
+}
+
+// Drops "." segments, keeping the trailing slash of directory keys.
+fn normalize(key: &str) -> String {
+    if !key.split('/').any(|s| s == ".") {
+        return key.to_owned();
+    }
+    let mut segments: Vec<&str> = key.split('/').filter(|s| *s != ".").collect();
+    if key.ends_with("/.") {
+        segments.push("");
+    }
+    segments.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("a/b.txt"), "a/b.txt");
+        assert_eq!(normalize("./a/./b.txt"), "a/b.txt");
+        assert_eq!(normalize("a/."), "a/");
+        assert_eq!(normalize("a/.hidden"), "a/.hidden");
+        assert_eq!(normalize("."), "");
+    }
+
+    #[test]
+    fn test_object_key_rules() {
+        assert_eq!(
+            ObjectKey::new("./photos/a.png").unwrap().as_str(),
+            "photos/a.png"
+        );
+        assert!(ObjectKey::new("").is_err());
+        assert!(ObjectKey::new("/photos/a.png").is_err());
+        assert!(ObjectKey::new("\\photos\\a.png").is_err());
+        assert!(ObjectKey::new("photos/a\n.png").is_err());
+        assert!(ObjectKey::new("a".repeat(1023)).is_ok());
+        assert!(ObjectKey::new("a".repeat(1024)).is_err());
+        // Multi-byte characters count by their UTF-8 length.
+        assert!(ObjectKey::new("中".repeat(342)).is_err());
+        assert!("photos/中文.png".parse::<ObjectKey>().is_ok());
+    }
+
+    #[test]
+    fn test_object_key_warnings() {
+        let warnings = |k: &str| ObjectKey::new(k).unwrap().warnings();
+        assert!(warnings("photos/").is_empty());
+        assert!(warnings("photos/a.png").is_empty());
+        assert_eq!(warnings("photos//a.png"), vec![KeyWarning::EmptySegment]);
+        assert_eq!(warnings("photos/../a.png"), vec![KeyWarning::ParentSegment]);
+        assert_eq!(
+            warnings("photos /a.png"),
+            vec![KeyWarning::TrailingWhitespace]
+        );
+    }
+}
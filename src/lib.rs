@@ -1,11 +1,77 @@
+// The file-based APIs are compiled out on wasm32, leaving what only they
+// use unused there.
+#![cfg_attr(target_arch = "wasm32", allow(dead_code, unused_imports))]
+
 #[macro_use]
 extern crate derive_more;
 #[macro_use]
 extern crate log;
 
+pub mod acl;
+pub mod append;
+#[cfg(feature = "blocking")]
+pub mod blocking;
 pub mod bucket;
+pub mod bulk;
+pub mod cache;
+pub mod cdn;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod checksum;
+pub mod clock;
+pub mod config;
+pub mod copy;
+pub mod credentials;
+pub mod download;
 pub mod errors;
+pub mod fanout;
+pub mod interceptor;
+pub mod key;
+pub mod lines;
+pub mod metrics;
+pub mod multipart;
+pub mod object;
 pub mod oss;
+pub mod pipe;
+#[cfg(feature = "crypto")]
+pub mod post_policy;
+#[cfg(feature = "presign")]
+pub mod presign;
+pub mod profile;
+pub mod progress;
+pub mod raw;
+pub mod replication;
+pub mod restore;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod resumable;
+pub mod retry;
+#[cfg(feature = "select")]
+pub mod select;
+pub mod signer;
+pub mod stat;
+pub mod stream_upload;
+#[cfg(feature = "crypto")]
+pub mod sts;
+pub mod symlink;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod sync;
+#[cfg(feature = "test-util")]
+pub mod test_util;
+pub mod timeout;
+#[cfg(feature = "crypto")]
+pub mod token_service;
+#[cfg(all(feature = "transfer-manager", not(target_arch = "wasm32")))]
+pub mod transfer;
+pub mod transport;
+pub mod tuning;
+pub mod udf;
+pub mod versions;
+pub mod xml;
 
 mod auth;
+mod rt;
+mod task;
+mod throttle;
+mod trace;
 mod utils;
+
+pub use auth::verify_signature;
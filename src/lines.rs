@@ -0,0 +1,130 @@
+use bytes::Bytes;
+use futures::stream::{self, Stream, TryStreamExt};
+use reqwest::header::{HeaderMap, CONTENT_RANGE, ETAG, IF_MATCH, RANGE};
+use reqwest::{Method, StatusCode};
+use std::io;
+use tokio_util::codec::{FramedRead, LinesCodec, LinesCodecError};
+use tokio_util::io::StreamReader;
+
+use crate::download::ContentRange;
+use crate::errors::{Error, ObjectError};
+use crate::oss::OSS;
+
+// Size of each ranged GET, a dropped connection only loses one window.
+const WINDOW: u64 = 8 * 1024 * 1024;
+
+impl OSS {
+    // Streams the object one line at a time, without the trailing "\n" or
+    // "\r\n", for NDJSON, CSV and log objects too large to buffer. The object
+    // is fetched in ranged windows pinned to the ETag of the first one, so a
+    // concurrent overwrite fails the stream instead of mixing two versions.
+    pub fn read_lines<S>(&self, key: S) -> impl Stream<Item = Result<String, Error>> + '_
+    where
+        S: AsRef<str>,
+    {
+        let chunks = self
+            .range_stream(key.as_ref().to_owned())
+            .map_err(io::Error::other);
+        FramedRead::new(StreamReader::new(chunks), LinesCodec::new()).map_err(|e| match e {
+            LinesCodecError::Io(e) => from_io(e),
+            LinesCodecError::MaxLineLengthExceeded => Error::E("line too long".to_owned()),
+        })
+    }
+
+    // Streams the body of `key` as consecutive ranged GETs of WINDOW bytes.
+    pub(crate) fn range_stream(
+        &self,
+        key: String,
+    ) -> impl Stream<Item = Result<Bytes, Error>> + '_ {
+        stream::try_unfold(Some((0, None)), move |state| {
+            let key = key.clone();
+            async move {
+                let (start, etag): (u64, Option<String>) = match state {
+                    Some(state) => state,
+                    None => return Ok(None),
+                };
+                let mut headers = HeaderMap::new();
+                headers.insert(
+                    RANGE,
+                    format!("bytes={}-{}", start, start + WINDOW - 1).parse()?,
+                );
+                if let Some(etag) = &etag {
+                    headers.insert(IF_MATCH, etag.parse()?);
+                }
+                let resp = self
+                    .signed_request(Method::GET, &key, &[], headers, Vec::new())
+                    .await?;
+                let next = match resp.status() {
+                    // The previous window ended exactly at the end, or the
+                    // object is empty.
+                    StatusCode::RANGE_NOT_SATISFIABLE => return Ok(None),
+                    StatusCode::PARTIAL_CONTENT => {
+                        let header = |name| resp.headers().get(name).and_then(|v| v.to_str().ok());
+                        let (end, total) = header(CONTENT_RANGE)
+                            .and_then(parse_content_range)
+                            .ok_or_else(|| Error::E("invalid Content-Range".to_owned()))?;
+                        let etag = etag.or_else(|| header(ETAG).map(|v| v.to_owned()));
+                        if end + 1 < total {
+                            Some((end + 1, etag))
+                        } else {
+                            None
+                        }
+                    }
+                    // The range was ignored and the whole object returned.
+                    StatusCode::OK if start == 0 => None,
+                    status => {
+                        return Err(self
+                            .api_error(resp, |reason| {
+                                Error::Object(ObjectError::GetError {
+                                    msg: format!(
+                                        "can not read range at {}, status: {}, reason: {:?}",
+                                        start, status, reason
+                                    ),
+                                })
+                            })
+                            .await)
+                    }
+                };
+                Ok::<_, Error>(Some((resp.bytes_stream().map_err(Error::from), next)))
+            }
+        })
+        .try_flatten()
+    }
+}
+
+// Parses "bytes 0-99/1000" into the last byte and the total size.
+fn parse_content_range(value: &str) -> Option<(u64, u64)> {
+    let range: ContentRange = value.parse().ok()?;
+    Some((range.range().end - 1, range.total()))
+}
+
+// Recovers errors that were wrapped to pass through the byte reader.
+fn from_io(e: io::Error) -> Error {
+    if !e.get_ref().is_some_and(|inner| inner.is::<Error>()) {
+        return Error::Io(e);
+    }
+    *e.into_inner()
+        .and_then(|inner| inner.downcast().ok())
+        .expect("checked above")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_content_range() {
+        assert_eq!(parse_content_range("bytes 0-99/1000"), Some((99, 1000)));
+        assert_eq!(parse_content_range("bytes 900-999/1000"), Some((999, 1000)));
+        assert_eq!(parse_content_range("bytes */1000"), None);
+        assert_eq!(parse_content_range("0-99/1000"), None);
+    }
+
+    #[test]
+    fn test_from_io() {
+        let e = io::Error::other(Error::E("boom".to_owned()));
+        assert!(matches!(from_io(e), Error::E(msg) if msg == "boom"));
+        let e = io::Error::new(io::ErrorKind::UnexpectedEof, "eof");
+        assert!(matches!(from_io(e), Error::Io(_)));
+    }
+}
@@ -0,0 +1,152 @@
+use reqwest::header::{HeaderMap, CONTENT_LENGTH};
+use reqwest::{Request, Response, StatusCode};
+use std::fmt;
+use std::future::Future;
+use std::time::Duration;
+use web_time::Instant;
+
+use crate::oss::OSS;
+
+tokio::task_local! {
+    static OPERATION: String;
+}
+
+// Told about every request a client sends, e.g. to export request counts,
+// bytes and latencies to Prometheus. Set with `OSSBuilder::metrics`. Called
+// from the task that sent the request, so keep it cheap.
+pub trait MetricsRecorder: fmt::Debug + Send + Sync {
+    fn record(&self, metrics: &RequestMetrics);
+}
+
+// One request, retries and the parts of multipart transfers each count as
+// one.
+#[derive(Clone, Debug)]
+pub struct RequestMetrics {
+    operation: String,
+    status: Option<StatusCode>,
+    bytes_sent: u64,
+    bytes_received: Option<u64>,
+    latency: Duration,
+}
+
+impl RequestMetrics {
+    // The API call the request was made for, e.g. `put_object`.
+    pub fn operation(&self) -> &str {
+        &self.operation
+    }
+
+    // None when no response was received.
+    pub fn status(&self) -> Option<StatusCode> {
+        self.status
+    }
+
+    // `2xx` to `5xx`, or `error` when no response was received.
+    pub fn status_class(&self) -> &'static str {
+        match self.status.map(|s| s.as_u16() / 100) {
+            Some(1) => "1xx",
+            Some(2) => "2xx",
+            Some(3) => "3xx",
+            Some(4) => "4xx",
+            Some(5) => "5xx",
+            _ => "error",
+        }
+    }
+
+    // The request body's length, 0 for streamed bodies sent without a
+    // Content-Length.
+    pub fn bytes_sent(&self) -> u64 {
+        self.bytes_sent
+    }
+
+    // The response's Content-Length, if it had one.
+    pub fn bytes_received(&self) -> Option<u64> {
+        self.bytes_received
+    }
+
+    // From sending the request to receiving the response headers.
+    pub fn latency(&self) -> Duration {
+        self.latency
+    }
+}
+
+// Runs `f` with requests it sends counted towards `operation`.
+pub(crate) async fn in_operation<F: Future>(operation: &str, f: F) -> F::Output {
+    OPERATION.scope(operation.to_owned(), f).await
+}
+
+pub(crate) fn bytes_sent(request: &Request) -> u64 {
+    match request.body().and_then(|b| b.as_bytes()) {
+        Some(bytes) => bytes.len() as u64,
+        None => content_length(request.headers()).unwrap_or(0),
+    }
+}
+
+fn content_length(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+}
+
+impl OSS {
+    // Reports a request sent at `start` to the metrics recorder, if any.
+    pub(crate) fn record_metrics(&self, resp: Option<&Response>, bytes_sent: u64, start: Instant) {
+        if let Some(recorder) = self.metrics_recorder() {
+            recorder.record(&RequestMetrics {
+                operation: OPERATION.try_with(|op| op.clone()).unwrap_or_default(),
+                status: resp.map(|r| r.status()),
+                bytes_sent,
+                bytes_received: resp.and_then(|r| content_length(r.headers())),
+                latency: start.elapsed(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, Default)]
+    struct Recorder(Arc<Mutex<Vec<RequestMetrics>>>);
+
+    impl MetricsRecorder for Recorder {
+        fn record(&self, metrics: &RequestMetrics) {
+            self.0.lock().unwrap().push(metrics.clone());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_metrics() {
+        let recorded = Arc::new(Mutex::new(Vec::new()));
+        let oss_instance = OSS::builder(
+            "xxx".to_string(),
+            "xxx".to_string(),
+            "xxx.aliyuncs.com".to_string(),
+            "xxx".to_string(),
+        )
+        .metrics(Recorder(recorded.clone()))
+        .build();
+        let resp = Response::from(
+            http::Response::builder()
+                .status(404)
+                .header(CONTENT_LENGTH, "230")
+                .body("")
+                .unwrap(),
+        );
+        in_operation("get_object", async {
+            oss_instance.record_metrics(Some(&resp), 0, Instant::now());
+        })
+        .await;
+        oss_instance.record_metrics(None, 5, Instant::now());
+
+        let recorded = recorded.lock().unwrap();
+        assert_eq!(recorded[0].operation(), "get_object");
+        assert_eq!(recorded[0].status_class(), "4xx");
+        assert_eq!(recorded[0].bytes_received(), Some(230));
+        assert_eq!(recorded[1].operation(), "");
+        assert_eq!(recorded[1].status_class(), "error");
+        assert_eq!(recorded[1].bytes_sent(), 5);
+    }
+}
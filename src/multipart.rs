@@ -0,0 +1,409 @@
+use chrono::{DateTime, Utc};
+use reqwest::header::HeaderMap;
+use serde_derive::Deserialize;
+use serde_xml_rs::from_str;
+use std::collections::HashMap;
+use std::future::Future;
+
+use crate::errors::Error;
+use crate::object::CompleteMultipartUploadResult;
+use crate::oss::{CompleteMultipartUpload, Part, OSS};
+use crate::transport::MaybeSend;
+
+// Part numbers OSS accepts.
+const MAX_PART_NUMBER: u64 = 10000;
+
+// A multipart upload whose parts the caller uploads on its own schedule,
+// e.g. from a priority queue or a work-stealing job system, instead of the
+// built-in concurrency of `parallel_upload`. Holds a clone of the client, so
+// part futures do not borrow it and can be spawned anywhere.
+#[derive(Clone, Debug)]
+pub struct MultipartUpload {
+    oss: OSS,
+    object_name: String,
+    upload_id: String,
+}
+
+impl MultipartUpload {
+    pub fn object_name(&self) -> &str {
+        &self.object_name
+    }
+
+    pub fn upload_id(&self) -> &str {
+        &self.upload_id
+    }
+
+    // Uploads `data` as part `part_number`, 1 to 10000. Every part but the
+    // last has to be at least 100 KiB. Nothing is sent until the future is
+    // polled, and it does not borrow `self`.
+    pub fn part_future(
+        &self,
+        part_number: u64,
+        data: Vec<u8>,
+    ) -> impl Future<Output = Result<Part, Error>> + MaybeSend + 'static {
+        let upload = self.clone();
+        async move {
+            upload
+                .oss
+                .upload_part(&upload.object_name, &upload.upload_id, part_number, data)
+                .await
+        }
+    }
+
+    // Assembles the object from `parts`, in any order.
+    pub async fn complete(
+        self,
+        mut parts: Vec<Part>,
+    ) -> Result<CompleteMultipartUploadResult, Error> {
+        parts.sort_by_key(|p| p.part_number());
+        self.oss
+            .complete_multipart_upload(
+                &self.object_name,
+                self.upload_id,
+                CompleteMultipartUpload::new(parts),
+                None::<HashMap<&str, &str>>,
+            )
+            .await
+    }
+
+    // Discards the upload and the parts uploaded so far.
+    pub async fn abort(self) -> Result<(), Error> {
+        self.oss
+            .abort_multipart_upload(&self.object_name, self.upload_id)
+            .await
+    }
+
+    // Aborts after `e` stopped the upload, logging a failed abort, and
+    // returns `e`.
+    #[cfg(feature = "transfer-manager")]
+    pub(crate) async fn abort_after(self, e: Error) -> Error {
+        self.oss
+            .abort_after(&self.object_name, self.upload_id, e)
+            .await
+    }
+}
+
+// A part already uploaded to a multipart upload, as listed by `list_parts`.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct UploadedPart {
+    part_number: u64,
+    #[serde(rename = "ETag")]
+    etag: String,
+    size: u64,
+    last_modified: String,
+}
+
+impl UploadedPart {
+    pub fn part_number(&self) -> u64 {
+        self.part_number
+    }
+
+    pub fn etag(&self) -> &str {
+        &self.etag
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn last_modified(&self) -> &str {
+        &self.last_modified
+    }
+
+    // The part as `complete` expects it.
+    pub fn to_part(&self) -> Part {
+        Part::new(self.part_number, self.etag.clone())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct ListPartsResult {
+    #[serde(default)]
+    next_part_number_marker: u64,
+    #[serde(default)]
+    is_truncated: bool,
+    #[serde(rename = "Part", default)]
+    parts: Vec<UploadedPart>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ListMultipartUploadsOptions {
+    pub prefix: Option<String>,
+    pub key_marker: Option<String>,
+    pub upload_id_marker: Option<String>,
+    // At most 1000.
+    pub max_uploads: Option<u32>,
+}
+
+// A multipart upload that was started and neither completed nor aborted.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct MultipartUploadEntry {
+    key: String,
+    upload_id: String,
+    initiated: String,
+}
+
+impl MultipartUploadEntry {
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn upload_id(&self) -> &str {
+        &self.upload_id
+    }
+
+    pub fn initiated(&self) -> &str {
+        &self.initiated
+    }
+
+    // Initiated parsed from its ISO 8601 form, e.g. 2012-02-23T04:18:23.000Z.
+    pub fn initiated_time(&self) -> Result<DateTime<Utc>, Error> {
+        DateTime::parse_from_rfc3339(&self.initiated)
+            .map(|t| t.with_timezone(&Utc))
+            .map_err(|e| Error::E(format!("invalid Initiated {:?}: {}", self.initiated, e)))
+    }
+
+    // A handle to finish or abort the upload through `oss`.
+    pub fn to_upload(&self, oss: &OSS) -> MultipartUpload {
+        MultipartUpload {
+            oss: oss.clone(),
+            object_name: self.key.clone(),
+            upload_id: self.upload_id.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct ListMultipartUploads {
+    #[serde(default)]
+    is_truncated: bool,
+    #[serde(default)]
+    next_key_marker: String,
+    #[serde(default)]
+    next_upload_id_marker: String,
+    #[serde(rename = "Upload", default)]
+    uploads: Vec<MultipartUploadEntry>,
+}
+
+impl ListMultipartUploads {
+    pub fn is_truncated(&self) -> bool {
+        self.is_truncated
+    }
+
+    // Pass as `key_marker` and `upload_id_marker` for the next page.
+    pub fn next_key_marker(&self) -> &str {
+        &self.next_key_marker
+    }
+
+    pub fn next_upload_id_marker(&self) -> &str {
+        &self.next_upload_id_marker
+    }
+
+    pub fn uploads(&self) -> &[MultipartUploadEntry] {
+        &self.uploads
+    }
+}
+
+impl OSS {
+    // One page of the multipart uploads in progress, to find stale ones.
+    // https://help.aliyun.com/document_detail/31997.html
+    pub async fn list_multipart_uploads(
+        &self,
+        options: &ListMultipartUploadsOptions,
+    ) -> Result<ListMultipartUploads, Error> {
+        self.with_context("list_multipart_uploads", "", async {
+            let params = [
+                ("uploads", Some(String::new())),
+                ("prefix", options.prefix.clone()),
+                ("key-marker", options.key_marker.clone()),
+                ("upload-id-marker", options.upload_id_marker.clone()),
+                ("max-uploads", options.max_uploads.map(|m| m.to_string())),
+            ];
+            let resp = self.signed_get("", &params, HeaderMap::new()).await?;
+            Ok(from_str(&self.xml_text(resp).await?)?)
+        })
+        .await
+    }
+
+    // Every part uploaded so far to `upload_id`, in part number order, to
+    // reconcile or resume an interrupted upload. Pages through the listing.
+    // https://help.aliyun.com/document_detail/31998.html
+    pub async fn list_parts<S>(
+        &self,
+        object_name: S,
+        upload_id: &str,
+    ) -> Result<Vec<UploadedPart>, Error>
+    where
+        S: AsRef<str>,
+    {
+        let object_name = object_name.as_ref();
+        self.with_context("list_parts", object_name, async {
+            let mut parts = Vec::new();
+            let mut marker = 0;
+            loop {
+                let params = [
+                    ("uploadId", Some(upload_id.to_owned())),
+                    ("part-number-marker", Some(marker.to_string())),
+                    ("max-parts", Some("1000".to_owned())),
+                ];
+                let resp = self
+                    .signed_get(object_name, &params, HeaderMap::new())
+                    .await?;
+                let page: ListPartsResult = from_str(&self.xml_text(resp).await?)?;
+                parts.extend(page.parts);
+                if !page.is_truncated || page.next_part_number_marker <= marker {
+                    return Ok(parts);
+                }
+                marker = page.next_part_number_marker;
+            }
+        })
+        .await
+    }
+
+    // Uploads `data` as part `part_number`, 1 to 10000, of the upload
+    // `upload_id`. Every part but the last has to be at least 100 KiB. The
+    // buffer can come from anywhere, use `upload_from_reader` to have a
+    // reader split into parts instead.
+    // https://help.aliyun.com/document_detail/31993.html
+    pub async fn upload_part<S>(
+        &self,
+        object_name: S,
+        upload_id: &str,
+        part_number: u64,
+        data: Vec<u8>,
+    ) -> Result<Part, Error>
+    where
+        S: AsRef<str>,
+    {
+        let object_name = object_name.as_ref();
+        self.with_context("upload_part", object_name, async {
+            if part_number == 0 || part_number > MAX_PART_NUMBER {
+                return Err(Error::E(format!(
+                    "part number {} is not within 1 to {}",
+                    part_number, MAX_PART_NUMBER
+                )));
+            }
+            self.upload_part_bytes(object_name, upload_id, part_number, data)
+                .await
+        })
+        .await
+    }
+
+    // `upload_part` for any in-memory buffer, a slice or `Bytes` included,
+    // alongside `put_object_from_buffer`.
+    pub async fn upload_part_from_buffer<S, B>(
+        &self,
+        object_name: S,
+        upload_id: &str,
+        part_number: u64,
+        buf: B,
+    ) -> Result<Part, Error>
+    where
+        S: AsRef<str>,
+        B: Into<Vec<u8>>,
+    {
+        self.upload_part(object_name, upload_id, part_number, buf.into())
+            .await
+    }
+
+    // https://help.aliyun.com/document_detail/31992.html
+    pub async fn start_multipart_upload<S, H>(
+        &self,
+        object_name: S,
+        headers: H,
+    ) -> Result<MultipartUpload, Error>
+    where
+        S: AsRef<str>,
+        H: Into<Option<HashMap<S, S>>>,
+    {
+        let object_name = object_name.as_ref();
+        let upload_id = self.initiate_multipart_upload(object_name, headers).await?;
+        Ok(MultipartUpload {
+            oss: self.clone(),
+            object_name: object_name.to_owned(),
+            upload_id,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_list_parts() {
+        let page: ListPartsResult = from_str(
+            "<ListPartsResult><Bucket>examplebucket</Bucket><Key>big.bin</Key>\
+             <UploadId>0004B9894A22E5B1888A1E29F823****</UploadId>\
+             <NextPartNumberMarker>2</NextPartNumberMarker><MaxParts>2</MaxParts>\
+             <IsTruncated>true</IsTruncated>\
+             <Part><PartNumber>1</PartNumber><LastModified>2012-02-23T07:01:34.000Z</LastModified>\
+             <ETag>\"3349DC700140D7F86A0784842780****\"</ETag><Size>6291456</Size></Part>\
+             <Part><PartNumber>2</PartNumber><LastModified>2012-02-23T07:01:12.000Z</LastModified>\
+             <ETag>\"3349DC700140D7F86A0784842780****\"</ETag><Size>6291456</Size></Part>\
+             </ListPartsResult>",
+        )
+        .unwrap();
+        assert!(page.is_truncated);
+        assert_eq!(page.next_part_number_marker, 2);
+        assert_eq!(page.parts.len(), 2);
+        assert_eq!(page.parts[1].part_number(), 2);
+        assert_eq!(page.parts[1].size(), 6291456);
+        assert_eq!(
+            page.parts[0].to_part().etag(),
+            "\"3349DC700140D7F86A0784842780****\""
+        );
+
+        let empty: ListPartsResult = from_str(
+            "<ListPartsResult><Bucket>examplebucket</Bucket><IsTruncated>false</IsTruncated></ListPartsResult>",
+        )
+        .unwrap();
+        assert!(empty.parts.is_empty());
+    }
+
+    #[test]
+    fn test_parse_list_multipart_uploads() {
+        let page: ListMultipartUploads = from_str(
+            "<ListMultipartUploadsResult><Bucket>examplebucket</Bucket><KeyMarker></KeyMarker>\
+             <UploadIdMarker></UploadIdMarker><NextKeyMarker>oss.avi</NextKeyMarker>\
+             <NextUploadIdMarker>0004B99B8E707874FC2D692FA5D77D3F</NextUploadIdMarker>\
+             <Delimiter></Delimiter><Prefix></Prefix><MaxUploads>1000</MaxUploads>\
+             <IsTruncated>true</IsTruncated>\
+             <Upload><Key>multipart.data</Key><UploadId>0004B999EF518A1FE585B0C9360DC4C8</UploadId>\
+             <Initiated>2012-02-23T04:18:23.000Z</Initiated></Upload>\
+             <Upload><Key>oss.avi</Key><UploadId>0004B99B8E707874FC2D692FA5D77D3F</UploadId>\
+             <Initiated>2012-02-23T06:14:27.000Z</Initiated></Upload>\
+             </ListMultipartUploadsResult>",
+        )
+        .unwrap();
+        assert!(page.is_truncated());
+        assert_eq!(page.next_key_marker(), "oss.avi");
+        assert_eq!(page.uploads().len(), 2);
+        assert_eq!(page.uploads()[0].key(), "multipart.data");
+        assert_eq!(
+            page.uploads()[0].initiated_time().unwrap().timestamp(),
+            1329970703
+        );
+    }
+
+    #[tokio::test]
+    async fn test_part_future_is_spawnable() {
+        let upload = MultipartUpload {
+            oss: OSS::new(
+                "xxx".to_string(),
+                "xxx".to_string(),
+                "xxx.aliyuncs.com".to_string(),
+                "xxx".to_string(),
+            ),
+            object_name: "big.bin".to_owned(),
+            upload_id: "0004B9894A22E5B1888A1E29F823****".to_owned(),
+        };
+        let part = tokio::spawn(upload.part_future(0, vec![0; 16]));
+        drop(upload);
+        assert!(part.await.unwrap().is_err());
+    }
+}
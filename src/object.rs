@@ -0,0 +1,461 @@
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, CONTENT_LENGTH, ETAG, LAST_MODIFIED};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::errors::Error;
+use crate::raw::request_id;
+
+#[derive(Clone, Debug)]
+pub struct DeleteObjectResult {
+    version_id: Option<String>,
+    delete_marker: bool,
+    request_id: Option<String>,
+}
+
+impl DeleteObjectResult {
+    pub fn new(version_id: Option<String>, delete_marker: bool) -> Self {
+        DeleteObjectResult {
+            version_id,
+            delete_marker,
+            request_id: None,
+        }
+    }
+
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let version_id = headers
+            .get("x-oss-version-id")
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_owned());
+        let delete_marker = headers
+            .get("x-oss-delete-marker")
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v == "true")
+            .unwrap_or_default();
+        DeleteObjectResult {
+            request_id: request_id(headers),
+            ..DeleteObjectResult::new(version_id, delete_marker)
+        }
+    }
+
+    pub fn version_id(&self) -> Option<&str> {
+        self.version_id.as_deref()
+    }
+
+    pub fn delete_marker(&self) -> bool {
+        self.delete_marker
+    }
+
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PutObjectResult {
+    etag: Option<String>,
+    version_id: Option<String>,
+    request_id: Option<String>,
+    body: Bytes,
+}
+
+impl PutObjectResult {
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        PutObjectResult {
+            etag: header(headers, ETAG.as_str()),
+            version_id: header(headers, "x-oss-version-id"),
+            request_id: request_id(headers),
+            body: Bytes::new(),
+        }
+    }
+
+    pub(crate) fn with_body(self, body: Bytes) -> Self {
+        PutObjectResult { body, ..self }
+    }
+
+    pub fn etag(&self) -> Option<&str> {
+        self.etag.as_deref()
+    }
+
+    // Version of the new object, when versioning is enabled on this bucket.
+    pub fn version_id(&self) -> Option<&str> {
+        self.version_id.as_deref()
+    }
+
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+
+    // The response body, which is the callback server's reply when the
+    // upload had an x-oss-callback and empty otherwise.
+    pub fn body(&self) -> &Bytes {
+        &self.body
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CompleteMultipartUploadResult {
+    etag: Option<String>,
+    version_id: Option<String>,
+    request_id: Option<String>,
+}
+
+impl CompleteMultipartUploadResult {
+    // `etag` comes from the response body, which has none when the upload
+    // had an x-oss-callback.
+    pub fn from_headers(etag: Option<String>, headers: &HeaderMap) -> Self {
+        CompleteMultipartUploadResult {
+            etag,
+            version_id: header(headers, "x-oss-version-id"),
+            request_id: request_id(headers),
+        }
+    }
+
+    pub fn etag(&self) -> Option<&str> {
+        self.etag.as_deref()
+    }
+
+    // Version of the new object, when versioning is enabled on this bucket.
+    pub fn version_id(&self) -> Option<&str> {
+        self.version_id.as_deref()
+    }
+
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+}
+
+fn header(headers: &HeaderMap, name: &str) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_owned())
+}
+
+// Returned by GetObjectMeta, a cheaper HEAD carrying only these fields.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjectMeta {
+    content_length: u64,
+    etag: String,
+    last_modified: DateTime<Utc>,
+}
+
+impl ObjectMeta {
+    pub fn new(content_length: u64, etag: String, last_modified: DateTime<Utc>) -> Self {
+        ObjectMeta {
+            content_length,
+            etag,
+            last_modified,
+        }
+    }
+
+    pub fn from_headers(headers: &HeaderMap) -> Result<Self, Error> {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .ok_or_else(|| Error::E(format!("missing {} header", name)))
+        };
+        let content_length = header(CONTENT_LENGTH.as_str())?
+            .parse()
+            .map_err(|_| Error::E("invalid Content-Length".to_owned()))?;
+        let last_modified = DateTime::parse_from_rfc2822(header(LAST_MODIFIED.as_str())?)
+            .map_err(|e| Error::E(format!("invalid Last-Modified: {}", e)))?
+            .with_timezone(&Utc);
+        Ok(ObjectMeta::new(
+            content_length,
+            header(ETAG.as_str())?.to_owned(),
+            last_modified,
+        ))
+    }
+
+    pub fn content_length(&self) -> u64 {
+        self.content_length
+    }
+
+    pub fn etag(&self) -> &str {
+        &self.etag
+    }
+
+    pub fn last_modified(&self) -> DateTime<Utc> {
+        self.last_modified
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DeletedObject {
+    key: String,
+    version_id: Option<String>,
+    delete_marker: bool,
+}
+
+impl DeletedObject {
+    pub fn new(key: String, version_id: Option<String>, delete_marker: bool) -> Self {
+        DeletedObject {
+            key,
+            version_id,
+            delete_marker,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn version_id(&self) -> Option<&str> {
+        self.version_id.as_deref()
+    }
+
+    pub fn delete_marker(&self) -> bool {
+        self.delete_marker
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DeleteKeyError {
+    key: String,
+    code: String,
+    message: String,
+}
+
+impl DeleteKeyError {
+    pub fn new(key: String, code: String, message: String) -> Self {
+        DeleteKeyError { key, code, message }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DeleteObjectsResult {
+    // Empty in quiet mode.
+    deleted: Vec<DeletedObject>,
+    errors: Vec<DeleteKeyError>,
+}
+
+impl DeleteObjectsResult {
+    pub fn new(deleted: Vec<DeletedObject>, errors: Vec<DeleteKeyError>) -> Self {
+        DeleteObjectsResult { deleted, errors }
+    }
+
+    pub fn deleted(&self) -> &[DeletedObject] {
+        &self.deleted
+    }
+
+    pub fn errors(&self) -> &[DeleteKeyError] {
+        &self.errors
+    }
+
+    pub(crate) fn extend(&mut self, other: DeleteObjectsResult) {
+        self.deleted.extend(other.deleted);
+        self.errors.extend(other.errors);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ObjectType {
+    Normal,
+    Appendable,
+    Multipart,
+    Symlink,
+}
+
+impl FromStr for ObjectType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Normal" => Ok(ObjectType::Normal),
+            "Appendable" => Ok(ObjectType::Appendable),
+            "Multipart" => Ok(ObjectType::Multipart),
+            "Symlink" => Ok(ObjectType::Symlink),
+            _ => Err(Error::E(format!("unknown object type: {}", s))),
+        }
+    }
+}
+
+impl fmt::Display for ObjectType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            ObjectType::Normal => "Normal",
+            ObjectType::Appendable => "Appendable",
+            ObjectType::Multipart => "Multipart",
+            ObjectType::Symlink => "Symlink",
+        };
+        f.write_str(s)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StorageClass {
+    Standard,
+    IA,
+    Archive,
+    ColdArchive,
+    DeepColdArchive,
+}
+
+impl FromStr for StorageClass {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Standard" => Ok(StorageClass::Standard),
+            "IA" => Ok(StorageClass::IA),
+            "Archive" => Ok(StorageClass::Archive),
+            "ColdArchive" => Ok(StorageClass::ColdArchive),
+            "DeepColdArchive" => Ok(StorageClass::DeepColdArchive),
+            _ => Err(Error::E(format!("unknown storage class: {}", s))),
+        }
+    }
+}
+
+impl fmt::Display for StorageClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            StorageClass::Standard => "Standard",
+            StorageClass::IA => "IA",
+            StorageClass::Archive => "Archive",
+            StorageClass::ColdArchive => "ColdArchive",
+            StorageClass::DeepColdArchive => "DeepColdArchive",
+        };
+        f.write_str(s)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct HeadObjectResult {
+    object_type: Option<ObjectType>,
+    next_append_position: Option<u64>,
+    headers: HeaderMap,
+}
+
+impl HeadObjectResult {
+    pub fn from_headers(headers: HeaderMap) -> Self {
+        let object_type = headers
+            .get("x-oss-object-type")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok());
+        let next_append_position = headers
+            .get("x-oss-next-append-position")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok());
+        HeadObjectResult {
+            object_type,
+            next_append_position,
+            headers,
+        }
+    }
+
+    pub fn object_type(&self) -> Option<&ObjectType> {
+        self.object_type.as_ref()
+    }
+
+    pub fn request_id(&self) -> Option<&str> {
+        self.headers
+            .get("x-oss-request-id")
+            .and_then(|v| v.to_str().ok())
+    }
+
+    // Only present for Appendable objects.
+    pub fn next_append_position(&self) -> Option<u64> {
+        self.next_append_position
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delete_object_result_from_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-oss-version-id",
+            "CAEQNhiBgMDJgZCA0BYiIDc4MGZjZGI2".parse().unwrap(),
+        );
+        headers.insert("x-oss-delete-marker", "true".parse().unwrap());
+        headers.insert(
+            "x-oss-request-id",
+            "5C06A3B67B8B5A3DA422299D".parse().unwrap(),
+        );
+        let result = DeleteObjectResult::from_headers(&headers);
+        assert_eq!(result.request_id(), Some("5C06A3B67B8B5A3DA422299D"));
+        assert_eq!(
+            result.version_id(),
+            Some("CAEQNhiBgMDJgZCA0BYiIDc4MGZjZGI2")
+        );
+        assert!(result.delete_marker());
+
+        let result = DeleteObjectResult::from_headers(&HeaderMap::new());
+        assert_eq!(result.version_id(), None);
+        assert!(!result.delete_marker());
+    }
+
+    #[test]
+    fn test_head_object_result_from_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-oss-object-type", "Appendable".parse().unwrap());
+        headers.insert("x-oss-next-append-position", "1717".parse().unwrap());
+        let result = HeadObjectResult::from_headers(headers);
+        assert_eq!(result.object_type(), Some(&ObjectType::Appendable));
+        assert_eq!(result.next_append_position(), Some(1717));
+
+        let result = HeadObjectResult::from_headers(HeaderMap::new());
+        assert_eq!(result.request_id(), None);
+        assert_eq!(result.object_type(), None);
+        assert_eq!(result.next_append_position(), None);
+    }
+
+    #[test]
+    fn test_storage_class() {
+        for class in [
+            StorageClass::Standard,
+            StorageClass::IA,
+            StorageClass::Archive,
+            StorageClass::ColdArchive,
+            StorageClass::DeepColdArchive,
+        ]
+        .iter()
+        {
+            assert_eq!(class.to_string().parse::<StorageClass>().unwrap(), *class);
+        }
+        assert!("Glacier".parse::<StorageClass>().is_err());
+    }
+
+    #[test]
+    fn test_object_meta_from_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_LENGTH, "344606".parse().unwrap());
+        headers.insert(
+            ETAG,
+            "\"5B3C1A2E053D763E1B002CC607C5****\"".parse().unwrap(),
+        );
+        headers.insert(
+            LAST_MODIFIED,
+            "Fri, 24 Feb 2012 06:07:48 GMT".parse().unwrap(),
+        );
+        let meta = ObjectMeta::from_headers(&headers).unwrap();
+        assert_eq!(meta.content_length(), 344606);
+        assert_eq!(meta.etag(), "\"5B3C1A2E053D763E1B002CC607C5****\"");
+        assert_eq!(meta.last_modified().timestamp(), 1330063668);
+
+        headers.remove(LAST_MODIFIED);
+        assert!(ObjectMeta::from_headers(&headers).is_err());
+    }
+}
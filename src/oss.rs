@@ -1,16 +1,52 @@
 use super::errors::Error;
 use bytes::Bytes;
 use chrono::prelude::*;
+use futures::future;
+use futures::stream::{self, Stream, TryStreamExt};
 use quick_xml::{events::Event, Reader};
-use reqwest::header::{HeaderMap, CONTENT_LENGTH, DATE, ETAG};
-use reqwest::Client;
+use reqwest::header::{HeaderMap, CONTENT_LENGTH, CONTENT_TYPE, DATE, ETAG, RANGE};
+#[cfg(not(target_arch = "wasm32"))]
+use reqwest::Proxy;
+use reqwest::{Body, Client, Method, RequestBuilder, Response, StatusCode};
 use serde_derive::{Deserialize, Serialize};
 use serde_xml_rs::{from_str, to_string};
 use std::collections::HashMap;
+use std::future::Future;
+use std::ops::Range;
 use std::str;
-
-use crate::bucket::{Bucket, ListBuckets};
-use crate::errors::ObjectError;
+use std::sync::atomic::{AtomicI64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use crate::bucket::{
+    Bucket, ListBuckets, ListObjects, ListObjectsOptions, ListObjectsV2, ListObjectsV2Options,
+    Object, Owner,
+};
+use crate::cache::CachedObject;
+use crate::cdn::{etag, PurgeHook};
+use crate::clock::{Clock, SystemClock};
+use crate::config::{region, SignatureVersion};
+use crate::credentials::{Credentials, CredentialsProvider, StaticProvider};
+use crate::errors::{ChecksumMismatch, ErrorContext, ObjectError, OssApiError};
+use crate::interceptor::RequestInterceptor;
+use crate::metrics::{self, MetricsRecorder};
+use crate::object::{
+    CompleteMultipartUploadResult, DeleteKeyError, DeleteObjectResult, DeleteObjectsResult,
+    DeletedObject, HeadObjectResult, ObjectMeta, PutObjectResult, StorageClass,
+};
+use crate::progress::{Progress, TransferProgress};
+use crate::raw;
+use crate::retry::{self, Disposition, RetryPolicy};
+use crate::rt;
+use crate::signer::{RequestParts, Signer};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::sync::{needs_upload, RemoteState, SyncPolicy};
+use crate::throttle::RateLimiter;
+use crate::trace;
+use crate::transport::HttpTransport;
+use crate::tuning::{run_tuned, Concurrency};
+use crate::xml::XmlLimits;
 
 use super::auth::*;
 use super::utils::*;
@@ -19,12 +55,402 @@ use super::utils::*;
 pub struct OSS {
     key_id: String,
     key_secret: String,
+    security_token: Option<String>,
+    credentials_provider: Arc<dyn CredentialsProvider>,
+    // Whether the provider was set on the builder rather than made from the
+    // fixed key.
+    custom_provider: bool,
+    signer: Option<Arc<dyn Signer>>,
+    purge_hook: Option<Arc<dyn PurgeHook>>,
+    progress_hook: Option<Arc<dyn TransferProgress>>,
+    interceptors: Vec<Arc<dyn RequestInterceptor>>,
+    metrics_recorder: Option<Arc<dyn MetricsRecorder>>,
     endpoint: String,
     bucket: String,
+    signature_version: SignatureVersion,
+    region: Option<String>,
+    hedged_read_budget: Option<Duration>,
+    xml_limits: XmlLimits,
+    error_body_limit: usize,
+    verify_crc64: bool,
+    content_md5: bool,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    retry_policy: RetryPolicy,
+    // The `OSSBuilder::timeout` the client was built with, None with a
+    // client of the caller's, whose timeout can not be read back.
+    timeout: Option<Duration>,
+    clock: Arc<dyn Clock>,
+    // Milliseconds the server's clock is ahead of `clock`, learned from a
+    // RequestTimeTooSkewed response.
+    clock_skew: Arc<AtomicI64>,
+    handles: Arc<Mutex<HashMap<String, Arc<OSS>>>>,
+    object_cache: Arc<Mutex<HashMap<String, CachedObject>>>,
+    tasks: Arc<AtomicUsize>,
+    transport: Arc<dyn HttpTransport>,
     pub client: Client,
 }
 
-const RESOURCES: [&str; 50] = [
+// Configures an `OSS` beyond what `OSS::new` takes.
+#[derive(Debug)]
+pub struct OSSBuilder {
+    key_id: String,
+    key_secret: String,
+    security_token: Option<String>,
+    credentials_provider: Option<Arc<dyn CredentialsProvider>>,
+    signer: Option<Arc<dyn Signer>>,
+    purge_hook: Option<Arc<dyn PurgeHook>>,
+    progress_hook: Option<Arc<dyn TransferProgress>>,
+    interceptors: Vec<Arc<dyn RequestInterceptor>>,
+    metrics_recorder: Option<Arc<dyn MetricsRecorder>>,
+    endpoint: String,
+    bucket: String,
+    signature_version: SignatureVersion,
+    region: Option<String>,
+    hedged_read_budget: Option<Duration>,
+    xml_limits: XmlLimits,
+    error_body_limit: usize,
+    verify_crc64: bool,
+    content_md5: bool,
+    rate_limit: Option<u64>,
+    retry_policy: RetryPolicy,
+    client_config: ClientConfig,
+    clock: Arc<dyn Clock>,
+    client: Option<Client>,
+    transport: Option<Arc<dyn HttpTransport>>,
+}
+
+impl OSSBuilder {
+    // Signs every request with an STS token, for temporary credentials.
+    pub fn security_token(mut self, token: String) -> Self {
+        self.security_token = Some(token);
+        self
+    }
+
+    // Signs requests with whatever `provider` returns at the time instead of
+    // the fixed key. `sign_url` and `post_policy` still use the fixed key, and
+    // fail when it is empty; `sign_url_with_provider` and
+    // `post_policy_with_provider` sign with the provider's credentials.
+    pub fn credentials_provider<P: CredentialsProvider + 'static>(mut self, provider: P) -> Self {
+        self.credentials_provider = Some(Arc::new(provider));
+        self
+    }
+
+    // Has `signer` compute the Authorization header of every request, the
+    // keys and credentials provider are then left unused for them. Presigned
+    // URLs and post policies still use the fixed key.
+    pub fn signer<G: Signer + 'static>(mut self, signer: G) -> Self {
+        self.signer = Some(Arc::new(signer));
+        self
+    }
+
+    // Calls `hook` with the URL and new ETag of every object written by the
+    // put_object_* and upload methods, copies and completed multipart uploads.
+    pub fn invalidate_after_put<H: PurgeHook + 'static>(mut self, hook: H) -> Self {
+        self.purge_hook = Some(Arc::new(hook));
+        self
+    }
+
+    // Reports the progress of file uploads and downloads to `hook`.
+    pub fn progress<P: TransferProgress + 'static>(mut self, hook: P) -> Self {
+        self.progress_hook = Some(Arc::new(hook));
+        self
+    }
+
+    // Passes every request and response through `interceptor`, after those
+    // added before it.
+    pub fn interceptor<I: RequestInterceptor + 'static>(mut self, interceptor: I) -> Self {
+        self.interceptors.push(Arc::new(interceptor));
+        self
+    }
+
+    // Reports the operation, status, size and latency of every request to
+    // `recorder`.
+    pub fn metrics<M: MetricsRecorder + 'static>(mut self, recorder: M) -> Self {
+        self.metrics_recorder = Some(Arc::new(recorder));
+        self
+    }
+
+    // Defaults to V1.
+    pub fn signature_version(mut self, version: SignatureVersion) -> Self {
+        self.signature_version = version;
+        self
+    }
+
+    // The region V4 signatures are scoped to, e.g. cn-hangzhou. Derived from
+    // the endpoint when it is an oss-<region> one.
+    pub fn region(mut self, region: String) -> Self {
+        self.region = Some(region);
+        self
+    }
+
+    pub fn hedged_read_budget(mut self, budget: Duration) -> Self {
+        self.hedged_read_budget = Some(budget);
+        self
+    }
+
+    // Defaults to `XmlLimits::default()`.
+    pub fn xml_limits(mut self, limits: XmlLimits) -> Self {
+        self.xml_limits = limits;
+        self
+    }
+
+    // Bytes of an error response body kept for the error message, the rest is
+    // not read. Defaults to 64 KiB.
+    pub fn error_body_limit(mut self, limit: usize) -> Self {
+        self.error_body_limit = limit;
+        self
+    }
+
+    // Computes the CRC64 of the bytes uploaded by the put_object_* and
+    // multipart methods and downloaded by `get_object`, `get_object_into`,
+    // `get_object_stream`, `get_object_reader` and `download_file`, and fails
+    // with `Error::ChecksumMismatch` when it differs from
+    // x-oss-hash-crc64ecma. Completed multipart uploads and ranged downloads
+    // are checked against the combined CRC of their parts. Off by default.
+    pub fn verify_crc64(mut self, verify: bool) -> Self {
+        self.verify_crc64 = verify;
+        self
+    }
+
+    // Sends a Content-MD5 header with the bodies of the put_object_* methods
+    // and part uploads, so the server rejects a body corrupted on the way
+    // with InvalidDigest instead of storing it. Files are read an extra time
+    // to compute it; `put_object_from_reader` and `put_object_from_stream`
+    // can not know it up front and send none. Off by default.
+    pub fn content_md5(mut self, enabled: bool) -> Self {
+        self.content_md5 = enabled;
+        self
+    }
+
+    // Caps the bytes per second sent and received by the file, stream and
+    // part uploads and the downloads of this client and its clones, all
+    // transfers together.
+    pub fn rate_limit(mut self, bytes_per_second: u64) -> Self {
+        self.rate_limit = Some(bytes_per_second);
+        self
+    }
+
+    // Applies to every request with a body held in memory or none at all.
+    // POSTs, e.g. initiating and completing multipart uploads, are sent once
+    // as they are not idempotent, and so are bodies streamed from files and
+    // readers, which can not be replayed. Defaults to `RetryPolicy::default()`,
+    // `RetryPolicy::none()` turns retries off. Either way, a request rejected
+    // for clock skew or expired credentials is signed again once.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    // Bounds establishing a connection, TLS handshake included.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.client_config.connect_timeout = Some(timeout);
+        self
+    }
+
+    // Bounds every request from connecting to the end of the response body,
+    // so it also catches a stalled read. `OSS::with_timeout` overrides it for
+    // a single call. None by default.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.client_config.timeout = Some(timeout);
+        self
+    }
+
+    // Idle connections kept per host for reuse. Unlimited by default; raise
+    // the process's file limit rather than this for many small requests.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.client_config.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    // How long an idle connection is kept, 90 seconds by default.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.client_config.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    // Sends TCP keepalives at this interval, so idle pooled connections are
+    // not silently dropped by NAT gateways and load balancers.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn tcp_keepalive(mut self, interval: Duration) -> Self {
+        self.client_config.tcp_keepalive = Some(interval);
+        self
+    }
+
+    // Speaks HTTP/2 from the start instead of negotiating it, multiplexing
+    // requests over fewer connections. Only for endpoints known to support
+    // it.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn http2_prior_knowledge(mut self) -> Self {
+        self.client_config.http2_prior_knowledge = true;
+        self
+    }
+
+    // Defaults to `SystemClock`.
+    pub fn clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    // Sends all OSS traffic through `proxy`, e.g.
+    // `Proxy::all("http://proxy.internal:3128")?.basic_auth("user", "pass")`.
+    // The HTTP(S)_PROXY environment variables are used when no proxy is set.
+    // socks5:// proxies need reqwest's `socks` feature, enable it on the
+    // application's own reqwest dependency.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.client_config.proxies.push(proxy);
+        self
+    }
+
+    // Used as is, the timeout, pool and proxy settings only configure the
+    // default client.
+    pub fn client(mut self, client: Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    // Sends requests through `transport` instead of the client, which then
+    // only builds them.
+    pub fn transport<T: HttpTransport + 'static>(mut self, transport: T) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    // Panics if the HTTP client can not be built, e.g. when the TLS backend
+    // fails to initialize, as `reqwest::Client::new` does. `try_build`
+    // returns the error instead.
+    pub fn build(self) -> OSS {
+        self.try_build()
+            .expect("can not build the HTTP client, see OSSBuilder::try_build")
+    }
+
+    // Fails when the client configured by the timeout, pool, proxy and TLS
+    // settings can not be built, rather than sending requests around a
+    // required proxy with a default client.
+    pub fn try_build(self) -> Result<OSS, Error> {
+        let custom_provider = self.credentials_provider.is_some();
+        let credentials_provider = match self.credentials_provider {
+            Some(provider) => provider,
+            None => Arc::new(StaticProvider::new(Credentials::new(
+                self.key_id.clone(),
+                self.key_secret.clone(),
+                self.security_token.clone(),
+            ))),
+        };
+        let region = match self.region {
+            Some(r) => Some(r),
+            None => region(&self.endpoint),
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        let (client, timeout) = match self.client {
+            Some(client) => (client, None),
+            None => (self.client_config.build()?, self.client_config.timeout),
+        };
+        #[cfg(target_arch = "wasm32")]
+        let (client, timeout) = match self.client {
+            Some(client) => (client, None),
+            None => (self.client_config.build()?, None),
+        };
+        let transport = match self.transport {
+            Some(transport) => transport,
+            None => Arc::new(client.clone()),
+        };
+        credentials_provider.bind_transport(transport.clone());
+        Ok(OSS {
+            key_id: self.key_id,
+            key_secret: self.key_secret,
+            security_token: self.security_token,
+            credentials_provider,
+            custom_provider,
+            signer: self.signer,
+            purge_hook: self.purge_hook,
+            progress_hook: self.progress_hook,
+            interceptors: self.interceptors,
+            metrics_recorder: self.metrics_recorder,
+            endpoint: self.endpoint,
+            bucket: self.bucket,
+            signature_version: self.signature_version,
+            region,
+            hedged_read_budget: self.hedged_read_budget,
+            xml_limits: self.xml_limits,
+            error_body_limit: self.error_body_limit,
+            verify_crc64: self.verify_crc64,
+            content_md5: self.content_md5,
+            rate_limiter: self.rate_limit.map(|rate| Arc::new(RateLimiter::new(rate))),
+            retry_policy: self.retry_policy,
+            timeout,
+            clock: self.clock,
+            clock_skew: Arc::new(AtomicI64::new(0)),
+            handles: Arc::new(Mutex::new(HashMap::new())),
+            object_cache: Arc::new(Mutex::new(HashMap::new())),
+            tasks: Arc::new(AtomicUsize::new(0)),
+            transport,
+            client,
+        })
+    }
+}
+
+// Settings of the client built when none is given to `OSSBuilder::client`.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone, Debug, Default)]
+struct ClientConfig {
+    connect_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<Duration>,
+    tcp_keepalive: Option<Duration>,
+    http2_prior_knowledge: bool,
+    proxies: Vec<Proxy>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl ClientConfig {
+    fn build(&self) -> Result<Client, Error> {
+        let mut builder = Client::builder();
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+        if let Some(interval) = self.tcp_keepalive {
+            builder = builder.tcp_keepalive(interval);
+        }
+        if self.http2_prior_knowledge {
+            builder = builder.http2_prior_knowledge();
+        }
+        for proxy in &self.proxies {
+            builder = builder.proxy(proxy.clone());
+        }
+        Ok(builder.build()?)
+    }
+}
+
+// The browser's fetch owns connections, timeouts and proxies on wasm32.
+#[cfg(target_arch = "wasm32")]
+#[derive(Clone, Debug, Default)]
+struct ClientConfig {}
+
+#[cfg(target_arch = "wasm32")]
+impl ClientConfig {
+    fn build(&self) -> Result<Client, Error> {
+        Ok(Client::builder().build()?)
+    }
+}
+
+const DEFAULT_ERROR_BODY_LIMIT: usize = 64 * 1024;
+
+pub(crate) const RESOURCES: [&str; 55] = [
     "acl",
     "uploads",
     "location",
@@ -75,16 +501,63 @@ const RESOURCES: [&str; 50] = [
     "restore",
     "callback",
     "callback-var",
+    "continuation-token",
+    "rtc",
+    "stat",
+    "versions",
+    "versionId",
 ];
 
 impl OSS {
     pub fn new(key_id: String, key_secret: String, endpoint: String, bucket: String) -> Self {
-        OSS {
-            key_id: key_id,
-            key_secret: key_secret,
-            endpoint: endpoint,
-            bucket: bucket,
-            client: reqwest::Client::new(),
+        OSS::builder(key_id, key_secret, endpoint, bucket).build()
+    }
+
+    // Uses temporary credentials obtained from STS AssumeRole.
+    // https://help.aliyun.com/document_detail/100624.html
+    pub fn new_with_sts(
+        key_id: String,
+        key_secret: String,
+        token: String,
+        endpoint: String,
+        bucket: String,
+    ) -> Self {
+        OSS::builder(key_id, key_secret, endpoint, bucket)
+            .security_token(token)
+            .build()
+    }
+
+    pub fn builder(
+        key_id: String,
+        key_secret: String,
+        endpoint: String,
+        bucket: String,
+    ) -> OSSBuilder {
+        OSSBuilder {
+            key_id,
+            key_secret,
+            security_token: None,
+            credentials_provider: None,
+            signer: None,
+            purge_hook: None,
+            progress_hook: None,
+            interceptors: Vec::new(),
+            metrics_recorder: None,
+            endpoint,
+            bucket,
+            signature_version: SignatureVersion::V1,
+            region: None,
+            hedged_read_budget: None,
+            xml_limits: XmlLimits::default(),
+            error_body_limit: DEFAULT_ERROR_BODY_LIMIT,
+            verify_crc64: false,
+            content_md5: false,
+            rate_limit: None,
+            retry_policy: RetryPolicy::default(),
+            client_config: ClientConfig::default(),
+            clock: Arc::new(SystemClock),
+            client: None,
+            transport: None,
         }
     }
 
@@ -104,17 +577,152 @@ impl OSS {
         &self.key_secret
     }
 
+    pub fn security_token(&self) -> Option<&str> {
+        self.security_token.as_deref()
+    }
+
+    pub(crate) fn purge_hook(&self) -> Option<&dyn PurgeHook> {
+        self.purge_hook.as_deref()
+    }
+
+    pub(crate) fn progress_hook(&self) -> Option<Arc<dyn TransferProgress>> {
+        self.progress_hook.clone()
+    }
+
+    pub(crate) fn interceptors(&self) -> &[Arc<dyn RequestInterceptor>] {
+        &self.interceptors
+    }
+
+    pub(crate) fn metrics_recorder(&self) -> Option<&dyn MetricsRecorder> {
+        self.metrics_recorder.as_deref()
+    }
+
+    pub(crate) fn transport(&self) -> &Arc<dyn HttpTransport> {
+        &self.transport
+    }
+
+    pub(crate) fn rate_limiter(&self) -> Option<Arc<RateLimiter>> {
+        self.rate_limiter.clone()
+    }
+
+    // The credentials to sign the next request with.
+    pub(crate) async fn credentials(&self) -> Result<Credentials, Error> {
+        self.credentials_provider.credentials().await
+    }
+
+    // The fixed key, which presigned URLs and post policies are signed with
+    // without waiting on the credentials provider. A client built with only
+    // a provider has none.
+    #[cfg_attr(not(any(feature = "presign", feature = "crypto")), allow(dead_code))]
+    pub(crate) fn fixed_credentials(&self, operation: &str) -> Result<Credentials, Error> {
+        if self.custom_provider && (self.key_id.is_empty() || self.key_secret.is_empty()) {
+            return Err(Error::E(format!(
+                "no fixed key for {}, use {}_with_provider to sign with the credentials provider",
+                operation, operation
+            )));
+        }
+        Ok(Credentials::new(
+            self.key_id.clone(),
+            self.key_secret.clone(),
+            self.security_token.clone(),
+        ))
+    }
+
+    // The Authorization header for a request, from the custom signer if one
+    // is set and signed with the current credentials otherwise.
+    pub(crate) async fn sign(
+        &self,
+        verb: &str,
+        bucket: &str,
+        object: &str,
+        oss_resources: &str,
+        headers: &mut HeaderMap,
+    ) -> Result<String, Error> {
+        if let Some(signer) = &self.signer {
+            let parts = RequestParts::new(verb, bucket, object, oss_resources, headers);
+            return signer.sign(parts).await;
+        }
+        let credentials = self.credentials().await?;
+        Ok(self.oss_sign(verb, &credentials, bucket, object, oss_resources, headers))
+    }
+
     pub fn set_bucket(&mut self, bucket: &str) {
         self.bucket = bucket.to_string()
     }
 
+    // Returns a handle bound to `bucket` sharing this client's connection pool,
+    // credentials and settings. Handles are cached per bucket name on the
+    // client they were obtained from.
+    pub fn bucket_handle(&self, bucket: &str) -> Arc<OSS> {
+        let mut handles = self.handles.lock().unwrap();
+        handles
+            .entry(bucket.to_owned())
+            .or_insert_with(|| {
+                // A fresh cache keeps handles from referencing their parent's
+                // cache, which would form an Arc cycle.
+                Arc::new(OSS {
+                    bucket: bucket.to_owned(),
+                    handles: Arc::new(Mutex::new(HashMap::new())),
+                    ..self.clone()
+                })
+            })
+            .clone()
+    }
+
+    pub(crate) fn object_cache(&self) -> &Arc<Mutex<HashMap<String, CachedObject>>> {
+        &self.object_cache
+    }
+
+    pub(crate) fn tasks(&self) -> &Arc<AtomicUsize> {
+        &self.tasks
+    }
+
+    pub fn signature_version(&self) -> SignatureVersion {
+        self.signature_version
+    }
+
+    pub fn region(&self) -> Option<&str> {
+        self.region.as_deref()
+    }
+
+    pub fn hedged_read_budget(&self) -> Option<Duration> {
+        self.hedged_read_budget
+    }
+
+    pub(crate) fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    pub fn xml_limits(&self) -> XmlLimits {
+        self.xml_limits
+    }
+
+    pub fn verify_crc64_enabled(&self) -> bool {
+        self.verify_crc64
+    }
+
+    pub fn content_md5_enabled(&self) -> bool {
+        self.content_md5
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
+    // When set, a GET that has not returned headers within `budget` is raced
+    // against a second identical request; the slower one is dropped.
+    pub fn set_hedged_read_budget(&mut self, budget: Option<Duration>) {
+        self.hedged_read_budget = budget
+    }
+
+    // The request URL, `object` is the raw key and is percent-encoded here.
     pub fn host(&self, bucket: &str, object: &str, resources_str: &str) -> String {
         if self.endpoint.starts_with("https") {
             format!(
                 "https://{}.{}/{}?{}",
                 bucket,
                 self.endpoint.replacen("https://", "", 1),
-                object,
+                path_encode(object),
                 resources_str
             )
         } else {
@@ -122,15 +730,75 @@ impl OSS {
                 "http://{}.{}/{}?{}",
                 bucket,
                 self.endpoint.replacen("http://", "", 1),
-                object,
+                path_encode(object),
                 resources_str
             )
         }
     }
 
+    pub(crate) async fn with_context<T, F>(
+        &self,
+        operation: &str,
+        object: &str,
+        f: F,
+    ) -> Result<T, Error>
+    where
+        F: Future<Output = Result<T, Error>>,
+    {
+        // Boxed, as nested calls would otherwise grow the caller's future past
+        // the stack.
+        let f = Box::pin(trace::in_span(
+            operation,
+            self.bucket(),
+            object,
+            metrics::in_operation(operation, f),
+        ));
+        let ((result, request_id), attempt) = retry::with_attempts(raw::with_request_id(f)).await;
+        result.map_err(|e| {
+            e.with_context(
+                ErrorContext::new(operation, self.bucket(), object, self.endpoint(), attempt)
+                    .with_request_id(request_id),
+            )
+        })
+    }
+
+    async fn send_hedged(&self, req: RequestBuilder) -> Result<Response, Error> {
+        let (budget, backup) = match (self.hedged_read_budget, req.try_clone()) {
+            (Some(budget), Some(backup)) => (budget, backup),
+            _ => return self.send(req).await,
+        };
+
+        let first = self.send(req);
+        tokio::pin!(first);
+        tokio::select! {
+            res = &mut first => return res,
+            _ = rt::sleep(budget) => debug!("hedging GET after {:?}", budget),
+        }
+
+        // Whichever request answers first wins, falling back to the other one
+        // if the winner failed. Dropping the pending future cancels it.
+        let second = self.send(backup);
+        tokio::pin!(second);
+        tokio::select! {
+            res = &mut first => match res {
+                Ok(res) => Ok(res),
+                Err(_) => second.await,
+            },
+            res = &mut second => match res {
+                Ok(res) => Ok(res),
+                Err(_) => first.await,
+            },
+        }
+    }
+
+    // The time by the client's clock, corrected by the server's once OSS
+    // rejected a request as too skewed.
+    pub fn now(&self) -> DateTime<Utc> {
+        self.clock.now() + chrono::Duration::milliseconds(self.clock_skew.load(Ordering::Relaxed))
+    }
+
     pub fn date(&self) -> String {
-        let now: DateTime<Utc> = Utc::now();
-        now.format("%a, %d %b %Y %T GMT").to_string()
+        self.now().format("%a, %d %b %Y %T GMT").to_string()
     }
 
     pub fn get_resources_str<S>(&self, params: HashMap<S, Option<S>>) -> String
@@ -141,7 +809,7 @@ impl OSS {
             .iter()
             .filter(|(k, _)| RESOURCES.contains(&k.as_ref()))
             .collect();
-        resources.sort_by(|a, b| a.0.as_ref().to_string().cmp(&b.0.as_ref().to_string()));
+        resources.sort_by_key(|a| a.0.as_ref().to_string());
         let mut result = String::new();
         for (k, v) in resources {
             if !result.is_empty() {
@@ -156,264 +824,764 @@ impl OSS {
         result
     }
 
+    // Opens `n` pooled connections to the bucket endpoint ahead of a latency
+    // sensitive burst, so later requests skip the TCP/TLS handshake. The HEAD
+    // requests are unsigned, their status is irrelevant. Returns the number
+    // of connections that were established.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn warm_up(&self, n: usize) -> Result<usize, Error> {
+        self.with_context("warm_up", "", async {
+            let host = self.host(self.bucket(), "", "");
+            let handles: Vec<_> = (0..n)
+                .map(|_| {
+                    let transport = self.transport.clone();
+                    let req = self.client.head(&host).build();
+                    self.spawn_named("warm-up", async move { transport.execute(req?).await })
+                })
+                .collect();
+
+            let mut warmed = 0;
+            for handle in handles {
+                handle.await??;
+                warmed += 1;
+            }
+            Ok(warmed)
+        })
+        .await
+    }
+
     pub async fn list_bucket<S, R>(&self, resources: R) -> Result<ListBuckets, Error>
     where
         S: AsRef<str>,
         R: Into<Option<HashMap<S, Option<S>>>>,
     {
-        let resources_str = if let Some(r) = resources.into() {
-            self.get_resources_str(r)
-        } else {
-            String::new()
-        };
-        let host = self.endpoint();
-        let date = self.date();
-
-        let mut headers = HeaderMap::new();
-        headers.insert(DATE, date.parse()?);
-        let authorization = self.oss_sign(
-            "GET",
-            self.key_id(),
-            self.key_secret(),
-            "",
-            "",
-            &resources_str,
-            &headers,
-        );
-        headers.insert("Authorization", authorization.parse()?);
-
-        let resp = self.client.get(host).headers(headers).send().await?;
-
-        let xml_str = resp.text().await?;
-        let mut result = Vec::new();
-        let mut reader = Reader::from_str(xml_str.as_str());
-        reader.trim_text(true);
-        let mut buf = Vec::new();
+        self.with_context("list_bucket", "", async {
+            let resources_str = if let Some(r) = resources.into() {
+                self.get_resources_str(r)
+            } else {
+                String::new()
+            };
+            let host = self.endpoint();
+            let resources_str = &resources_str;
+            let resp = self
+                .retrying(&Method::GET, "", || async move {
+                    let mut headers = HeaderMap::new();
+                    headers.insert(DATE, self.date().parse()?);
+                    let authorization = self
+                        .sign("GET", "", "", resources_str, &mut headers)
+                        .await?;
+                    headers.insert("Authorization", authorization.parse()?);
+                    self.send(self.client.get(host).headers(headers)).await
+                })
+                .await?;
+            if !resp.status().is_success() {
+                return Err(self
+                    .api_error(resp, |reason| {
+                        Error::E(format!("can not list buckets, reason: {:?}", reason))
+                    })
+                    .await);
+            }
 
-        let mut prefix = String::new();
-        let mut marker = String::new();
-        let mut max_keys = String::new();
-        let mut is_truncated = false;
-        let mut next_marker = String::new();
-        let mut id = String::new();
-        let mut display_name = String::new();
+            parse_list_buckets(&self.xml_text(resp).await?)
+        })
+        .await
+    }
 
-        let mut name = String::new();
-        let mut location = String::new();
-        let mut create_date = String::new();
-        let mut extranet_endpoint = String::new();
-        let mut intranet_endpoint = String::new();
-        let mut storage_class = String::new();
+    // https://help.aliyun.com/document_detail/31965.html
+    pub async fn list_objects(&self, options: &ListObjectsOptions) -> Result<ListObjects, Error> {
+        self.with_context("list_objects", "", async {
+            let params = [
+                ("prefix", options.prefix.clone()),
+                ("marker", options.marker.clone()),
+                ("max-keys", options.max_keys.map(|m| m.to_string())),
+                ("delimiter", options.delimiter.clone()),
+                ("encoding-type", Some("url".to_owned())),
+            ];
+            let resp = self.signed_get("", &params, HeaderMap::new()).await?;
+            parse_list_objects(&self.xml_text(resp).await?)
+        })
+        .await
+    }
 
-        let list_buckets;
+    // https://help.aliyun.com/document_detail/187544.html
+    pub async fn list_objects_v2(
+        &self,
+        options: &ListObjectsV2Options,
+    ) -> Result<ListObjectsV2, Error> {
+        self.with_context("list_objects_v2", "", async {
+            let params = [
+                ("list-type", Some("2".to_owned())),
+                ("prefix", options.prefix.clone()),
+                ("continuation-token", options.continuation_token.clone()),
+                ("start-after", options.start_after.clone()),
+                ("max-keys", options.max_keys.map(|m| m.to_string())),
+                ("delimiter", options.delimiter.clone()),
+                ("fetch-owner", Some(options.fetch_owner.to_string())),
+                ("encoding-type", Some("url".to_owned())),
+            ];
+            let resp = self.signed_get("", &params, HeaderMap::new()).await?;
+            parse_list_objects_v2(&self.xml_text(resp).await?)
+        })
+        .await
+    }
 
-        loop {
-            match reader.read_event(&mut buf) {
-                Ok(Event::Start(ref e)) => match e.name() {
-                    b"Prefix" => prefix = reader.read_text(e.name(), &mut Vec::new())?,
-                    b"Marker" => marker = reader.read_text(e.name(), &mut Vec::new())?,
-                    b"MaxKeys" => max_keys = reader.read_text(e.name(), &mut Vec::new())?,
-                    b"IsTruncated" => {
-                        is_truncated = reader.read_text(e.name(), &mut Vec::new())? == "true"
-                    }
-                    b"NextMarker" => next_marker = reader.read_text(e.name(), &mut Vec::new())?,
-                    b"ID" => id = reader.read_text(e.name(), &mut Vec::new())?,
-                    b"DisplayName" => display_name = reader.read_text(e.name(), &mut Vec::new())?,
-
-                    b"Bucket" => {
-                        name = String::new();
-                        location = String::new();
-                        create_date = String::new();
-                        extranet_endpoint = String::new();
-                        intranet_endpoint = String::new();
-                        storage_class = String::new();
-                    }
+    // Lists every key under `prefix`, following markers until the listing
+    // is exhausted.
+    pub(crate) async fn list_keys(&self, prefix: &str) -> Result<Vec<String>, Error> {
+        let objects = self.list_all_objects(prefix).await?;
+        Ok(objects.into_iter().map(|o| o.key().to_owned()).collect())
+    }
 
-                    b"Name" => name = reader.read_text(e.name(), &mut Vec::new())?,
-                    b"CreationDate" => create_date = reader.read_text(e.name(), &mut Vec::new())?,
-                    b"ExtranetEndpoint" => {
-                        extranet_endpoint = reader.read_text(e.name(), &mut Vec::new())?
-                    }
-                    b"IntranetEndpoint" => {
-                        intranet_endpoint = reader.read_text(e.name(), &mut Vec::new())?
-                    }
-                    b"Location" => location = reader.read_text(e.name(), &mut Vec::new())?,
-                    b"StorageClass" => {
-                        storage_class = reader.read_text(e.name(), &mut Vec::new())?
-                    }
-                    _ => (),
-                },
-                Ok(Event::End(ref e)) if e.name() == b"Bucket" => {
-                    let bucket = Bucket::new(
-                        name.clone(),
-                        create_date.clone(),
-                        location.clone(),
-                        extranet_endpoint.clone(),
-                        intranet_endpoint.clone(),
-                        storage_class.clone(),
-                    );
-                    result.push(bucket);
-                }
-                Ok(Event::Eof) => {
-                    list_buckets = ListBuckets::new(
-                        prefix,
-                        marker,
-                        max_keys,
-                        is_truncated,
-                        next_marker,
-                        id,
-                        display_name,
-                        result,
-                    );
-                    break;
-                } // exits the loop when reaching end of file
-                Err(e) => panic!("Error at position {}: {:?}", reader.buffer_position(), e),
-                _ => (), // There are several other `Event`s we do not consider here
-            }
-            buf.clear();
-        }
-        Ok(list_buckets)
+    pub(crate) async fn list_all_objects(&self, prefix: &str) -> Result<Vec<Object>, Error> {
+        self.list_objects_stream(prefix).try_collect().await
     }
 
-    pub async fn get_object<S>(
+    // Streams every object under `prefix`, fetching the next page once the
+    // current one is consumed until the listing is no longer truncated. Each
+    // page is fetched in a task named `oss-sdk:list-page`.
+    pub fn list_objects_stream<S>(
         &self,
-        object: S,
-        headers: Option<HashMap<S, S>>,
-        resources: Option<HashMap<S, Option<S>>>,
-    ) -> Result<Bytes, reqwest::Error>
+        prefix: S,
+    ) -> impl Stream<Item = Result<Object, Error>> + '_
     where
         S: AsRef<str>,
     {
-        let object = object.as_ref();
-        let resources_str = if let Some(r) = resources {
-            self.get_resources_str(r)
-        } else {
-            String::new()
-        };
-        let host = self.host(self.bucket(), object, &resources_str);
-        let date = self.date();
-        let mut headers = if let Some(h) = headers {
-            to_headers(h).unwrap()
-        } else {
-            HeaderMap::new()
+        let options = ListObjectsOptions {
+            prefix: Some(prefix.as_ref().to_owned()),
+            max_keys: Some(1000),
+            ..Default::default()
         };
-        headers.insert(DATE, date.parse().unwrap());
-        let authorization = self.oss_sign(
-            "GET",
-            self.key_id(),
-            self.key_secret(),
-            self.bucket(),
-            object,
-            &resources_str,
-            &headers,
-        );
-        headers.insert("Authorization", authorization.parse().unwrap());
-
-        let res = reqwest::Client::new()
-            .get(&host)
-            .headers(headers)
-            .send()
-            .await?;
-        Ok(res.bytes().await?)
+        stream::try_unfold(Some(options), move |options| async move {
+            let mut options = match options {
+                Some(options) => options,
+                None => return Ok(None),
+            };
+            let oss = self.clone();
+            let page_options = options.clone();
+            let list = self
+                .spawn_child(
+                    "list-page",
+                    async move { oss.list_objects(&page_options).await },
+                )
+                .await?;
+            let next = if list.is_truncated() && !list.next_marker().is_empty() {
+                options.marker = Some(list.next_marker().to_owned());
+                Some(options)
+            } else {
+                None
+            };
+            let page = stream::iter(list.objects().clone().into_iter().map(Ok));
+            Ok::<_, Error>(Some((page, next)))
+        })
+        .try_flatten()
     }
 
-    pub async fn head_object<S>(
+    // Like `list_objects_stream`, yielding only objects in one of `classes`,
+    // e.g. to audit what sits in Archive and ColdArchive. ListObjects can not
+    // filter by storage class, pages are filtered as they arrive.
+    pub fn list_objects_by_storage_class<S>(
         &self,
-        object: S,
-        headers: Option<HashMap<S, S>>,
-        resources: Option<HashMap<S, Option<S>>>,
-    ) -> Result<HeaderMap, reqwest::Error>
+        prefix: S,
+        classes: &[StorageClass],
+    ) -> impl Stream<Item = Result<Object, Error>> + '_
     where
         S: AsRef<str>,
     {
-        let object = object.as_ref();
-        let resources_str = if let Some(r) = resources {
-            self.get_resources_str(r)
-        } else {
-            String::new()
-        };
-        let host = self.host(self.bucket(), object, &resources_str);
-        let date = self.date();
-        let mut headers = if let Some(h) = headers {
-            to_headers(h).unwrap()
-        } else {
-            HeaderMap::new()
-        };
-        headers.insert(DATE, date.parse().unwrap());
-        let authorization = self.oss_sign(
-            "HEAD",
-            self.key_id(),
-            self.key_secret(),
-            self.bucket(),
-            object,
-            &resources_str,
-            &headers,
-        );
-        headers.insert("Authorization", authorization.parse().unwrap());
+        let classes = classes.to_vec();
+        self.list_objects_stream(prefix).try_filter(move |object| {
+            let wanted = object
+                .storage_class_kind()
+                .is_some_and(|class| classes.contains(&class));
+            future::ready(wanted)
+        })
+    }
 
-        let res = reqwest::Client::new()
-            .head(&host)
-            .headers(headers)
-            .send()
-            .await?;
-        Ok(res.headers().clone())
+    // Builds the percent-encoded URL query for `params` along with the
+    // canonicalized sub-resources that have to be signed. Unset params are
+    // skipped.
+    pub(crate) fn query_and_resources(
+        &self,
+        params: &[(&str, Option<String>)],
+    ) -> (String, String) {
+        let mut query = Vec::new();
+        let mut resources = HashMap::new();
+        for (k, v) in params {
+            match v {
+                Some(v) if v.is_empty() => query.push(k.to_string()),
+                Some(v) => query.push(format!("{}={}", k, url_encode(v))),
+                None => continue,
+            }
+            resources.insert(*k, v.as_deref().filter(|v| !v.is_empty()));
+        }
+        (query.join("&"), self.get_resources_str(resources))
     }
 
-    pub async fn put_object_from_buffer<S1, S2, H, R>(
+    // Signs and sends a request for `object`, `params` become both the query
+    // and the signed sub-resources. The status is left to the caller, after
+    // transient failures were retried as the retry policy says.
+    pub(crate) async fn signed_request(
         &self,
-        buf: &[u8],
-        object: S1,
-        headers: H,
-        resources: R,
-    ) -> Result<Bytes, reqwest::Error>
+        method: Method,
+        object: &str,
+        params: &[(&str, Option<String>)],
+        headers: HeaderMap,
+        body: Vec<u8>,
+    ) -> Result<Response, Error> {
+        let body = Bytes::from(body);
+        let (method_ref, headers, body) = (&method, &headers, &body);
+        self.retrying(&method, object, || async move {
+            let body = if body.is_empty() {
+                None
+            } else {
+                Some(Body::from(body.clone()))
+            };
+            self.signed_request_body(method_ref.clone(), object, params, headers.clone(), body)
+                .await
+        })
+        .await
+    }
+
+    // Calls `send`, which signs and sends one request, again while it fails
+    // transiently, as the retry policy says. Every request with a body held
+    // in memory goes through here. The status is left to the caller. POSTs
+    // are sent once, they are not idempotent.
+    pub(crate) async fn retrying<F, Fut>(
+        &self,
+        method: &Method,
+        object: &str,
+        mut send: F,
+    ) -> Result<Response, Error>
     where
-        S1: AsRef<str>,
-        S2: AsRef<str>,
-        H: Into<Option<HashMap<S2, S2>>>,
-        R: Into<Option<HashMap<S2, Option<S2>>>>,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<Response, Error>>,
     {
-        let object = object.as_ref();
-        let resources_str = if let Some(r) = resources.into() {
-            self.get_resources_str(r)
-        } else {
-            String::new()
-        };
-        let host = self.host(self.bucket(), object, &resources_str);
-        let date = self.date();
-
-        let mut headers = if let Some(h) = headers.into() {
-            to_headers(h).unwrap()
+        let max_attempts = if *method == Method::POST {
+            1
         } else {
-            HeaderMap::new()
+            self.retry_policy.max_attempts.max(1)
         };
-        headers.insert(DATE, date.parse().unwrap());
-        let authorization = self.oss_sign(
-            "PUT",
-            self.key_id(),
-            self.key_secret(),
-            self.bucket(),
-            object,
-            &resources_str,
-            &headers,
-        );
-        headers.insert("Authorization", authorization.parse().unwrap());
-
-        let res = reqwest::Client::new()
-            .put(&host)
-            .headers(headers)
-            .body(buf.to_owned())
-            .send()
-            .await?;
-        Ok(res.bytes().await?)
-    }
+        let mut attempt = 1;
+        let (mut resigned, mut refreshed) = (false, false);
+        loop {
+            retry::record_attempt(attempt);
+            let (result, disposition) = match send().await {
+                Ok(resp) => match self.response_disposition(resp).await {
+                    Ok((resp, disposition)) => (Ok(resp), disposition),
+                    Err(e) => {
+                        let disposition = e.disposition();
+                        (Err(e), disposition)
+                    }
+                },
+                Err(e) => {
+                    let disposition = e.disposition();
+                    (Err(e), disposition)
+                }
+            };
+            // Every attempt is signed anew, with the date corrected for skew
+            // and the credentials fetched again, which helps only once. Those
+            // rejections come before the request did anything, so they are
+            // retried whatever the method and `max_attempts`.
+            let retry = match disposition {
+                Disposition::Retryable => attempt < max_attempts,
+                Disposition::ResignAndRetry => !std::mem::replace(&mut resigned, true),
+                Disposition::RefreshCredentials if !refreshed => {
+                    self.credentials_provider.invalidate();
+                    refreshed = true;
+                    true
+                }
+                _ => false,
+            };
+            if !retry {
+                return result;
+            }
+            if disposition == Disposition::Retryable {
+                let delay = self.retry_policy.delay(attempt);
+                debug!("retrying {} {} in {:?}", method, object, delay);
+                rt::sleep(delay).await;
+            } else {
+                debug!("retrying {} {}: {:?}", method, object, disposition);
+            }
+            attempt += 1;
+        }
+    }
+
+    // How the retry loop should handle `resp`. Statuses OSS uses for both
+    // fatal and recoverable errors, 400 and 403, are told apart by the code in
+    // the body, which is read for that and put back.
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn response_disposition(&self, resp: Response) -> Result<(Response, Disposition), Error> {
+        let status = resp.status();
+        let small = resp
+            .content_length()
+            .is_some_and(|n| n as usize <= self.error_body_limit);
+        if !small || !(status == StatusCode::BAD_REQUEST || status == StatusCode::FORBIDDEN) {
+            return Ok((resp, Disposition::from_status(status)));
+        }
+        let (version, headers) = (resp.version(), resp.headers().clone());
+        let body = resp.bytes().await?;
+        let api = OssApiError::parse(status, &String::from_utf8_lossy(&body));
+        let disposition = Disposition::classify(api.as_ref().map(|e| e.code()), status);
+        if disposition == Disposition::ResignAndRetry {
+            self.correct_clock_skew(&headers);
+        }
+        let mut rebuilt = http::Response::new(body);
+        *rebuilt.status_mut() = status;
+        *rebuilt.version_mut() = version;
+        *rebuilt.headers_mut() = headers;
+        Ok((Response::from(rebuilt), disposition))
+    }
+
+    // A fetch response can not be put back together once its body is read,
+    // so on wasm32 only the status counts: clock skew and expired
+    // credentials are not retried there.
+    #[cfg(target_arch = "wasm32")]
+    async fn response_disposition(&self, resp: Response) -> Result<(Response, Disposition), Error> {
+        let status = resp.status();
+        Ok((resp, Disposition::from_status(status)))
+    }
+
+    // Adopts the server's time from the Date of its response.
+    fn correct_clock_skew(&self, headers: &HeaderMap) {
+        let server = headers
+            .get(DATE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| DateTime::parse_from_rfc2822(v).ok());
+        if let Some(server) = server {
+            let skew = server.with_timezone(&Utc) - self.clock.now();
+            debug!("correcting clock skew of {}ms", skew.num_milliseconds());
+            self.clock_skew
+                .store(skew.num_milliseconds(), Ordering::Relaxed);
+        }
+    }
+
+    // Like `signed_request`, for bodies that are streamed rather than held in
+    // memory and so can not be retried. Set Content-Length in `headers` or the
+    // body is sent chunked.
+    pub(crate) async fn signed_request_body(
+        &self,
+        method: Method,
+        object: &str,
+        params: &[(&str, Option<String>)],
+        mut headers: HeaderMap,
+        body: Option<Body>,
+    ) -> Result<Response, Error> {
+        let (query, resources_str) = self.query_and_resources(params);
+        let host = self.host(self.bucket(), object, &query);
+        let date = self.date();
+        headers.insert(DATE, date.parse()?);
+        // V4 signs every query parameter, V1 only the sub-resources.
+        let signed = match self.signature_version() {
+            SignatureVersion::V1 => &resources_str,
+            SignatureVersion::V4 => &query,
+        };
+        let authorization = self
+            .sign(method.as_str(), self.bucket(), object, signed, &mut headers)
+            .await?;
+        headers.insert("Authorization", authorization.parse()?);
+
+        let mut req = self.client.request(method, &host).headers(headers);
+        if let Some(body) = body {
+            req = req.body(body);
+        }
+        self.send(req).await
+    }
+
+    // Signs and sends a GET for `object`, failing on non-success statuses so
+    // callers can consume the body directly.
+    pub(crate) async fn signed_get(
+        &self,
+        object: &str,
+        params: &[(&str, Option<String>)],
+        headers: HeaderMap,
+    ) -> Result<Response, Error> {
+        let resp = self
+            .signed_request(Method::GET, object, params, headers, Vec::new())
+            .await?;
+        if resp.status().is_success() {
+            Ok(resp)
+        } else {
+            Err(self
+                .api_error(resp, |reason| {
+                    Error::Object(ObjectError::GetError {
+                        msg: format!("can not get object, reason: {:?}", reason),
+                    })
+                })
+                .await)
+        }
+    }
+
+    // The error for a failed response: the OSS error in its body, the status
+    // and headers when it has no body, e.g. a HEAD response, or, when the body
+    // holds no OSS error, e.g. a proxy's page, `fallback` called with its
+    // text.
+    pub(crate) async fn api_error<F>(&self, resp: Response, fallback: F) -> Error
+    where
+        F: FnOnce(Result<String, Error>) -> Error,
+    {
+        let status = resp.status();
+        let headers = resp.headers().clone();
+        let text = self.error_text(resp).await;
+        let api = match &text {
+            Ok(body) if body.is_empty() && !status.is_success() => {
+                Some(OssApiError::from_status(status, &headers))
+            }
+            Ok(body) => OssApiError::parse(status, body).map(|api| api.with_headers(&headers)),
+            Err(_) => None,
+        };
+        match api {
+            Some(api) => Error::Api(Box::new(api)),
+            None => fallback(text),
+        }
+    }
+
+    // The start of an error response body, at most `error_body_limit` bytes,
+    // so a proxy answering with a huge page can not balloon memory. Invalid
+    // UTF-8, e.g. from a cut multi-byte character, is replaced.
+    pub(crate) async fn error_text(&self, resp: Response) -> Result<String, Error> {
+        let limit = self.error_body_limit;
+        let mut body = Vec::new();
+        let mut truncated = false;
+        let chunks = resp.bytes_stream();
+        futures::pin_mut!(chunks);
+        while let Some(chunk) = chunks.try_next().await? {
+            if body.len() + chunk.len() > limit {
+                body.extend_from_slice(&chunk[..limit - body.len()]);
+                truncated = true;
+                break;
+            }
+            body.extend_from_slice(&chunk);
+        }
+        let mut text = String::from_utf8_lossy(&body).into_owned();
+        if truncated {
+            text.push_str("...");
+        }
+        Ok(text)
+    }
+
+    // A CRC for a streamed body to feed, when `verify_crc64` is on.
+    pub(crate) fn body_crc(&self) -> Option<Arc<Mutex<Crc64>>> {
+        if self.verify_crc64 {
+            Some(Arc::new(Mutex::new(Crc64::new())))
+        } else {
+            None
+        }
+    }
+
+    // Adds Content-MD5 for `buf` to `headers` when `content_md5` is on.
+    pub(crate) fn insert_content_md5(
+        &self,
+        headers: &mut HeaderMap,
+        buf: &[u8],
+    ) -> Result<(), Error> {
+        if self.content_md5 {
+            headers.insert("Content-MD5", content_md5(buf).parse()?);
+        }
+        Ok(())
+    }
+
+    // The CRC64 of `buf`, when `verify_crc64` is on.
+    pub(crate) fn buffer_crc(&self, buf: &[u8]) -> Option<u64> {
+        if self.verify_crc64 {
+            let mut crc = Crc64::new();
+            crc.update(buf);
+            Some(crc.value())
+        } else {
+            None
+        }
+    }
+
+    // Compares `crc` with the x-oss-hash-crc64ecma of a response. Passes when
+    // `verify_crc64` is off or the server sent no CRC.
+    pub(crate) fn check_crc64(&self, crc: u64, headers: &HeaderMap) -> Result<(), Error> {
+        if !self.verify_crc64 {
+            return Ok(());
+        }
+        match server_crc64(headers) {
+            Some(server) if server != crc => {
+                Err(Error::ChecksumMismatch(ChecksumMismatch::new(crc, server)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Reads the whole object into `buf`, reusing its allocation. `buf` is
+    // cleared first; returns the number of bytes read.
+    pub async fn get_object_into<S>(&self, object: S, buf: &mut Vec<u8>) -> Result<usize, Error>
+    where
+        S: AsRef<str>,
+    {
+        let object = object.as_ref();
+        self.with_context("get_object_into", object, async {
+            let resp = self.signed_get(object, &[], HeaderMap::new()).await?;
+            let headers = resp.headers().clone();
+            buf.clear();
+            let chunks = resp.bytes_stream();
+            futures::pin_mut!(chunks);
+            while let Some(chunk) = chunks.try_next().await? {
+                buf.extend_from_slice(&chunk);
+            }
+            if let Some(crc) = self.buffer_crc(buf) {
+                self.check_crc64(crc, &headers)?;
+            }
+            Ok(buf.len())
+        })
+        .await
+    }
+
+    // Reads `range` of the object into the front of `buf`, which must be
+    // large enough to hold it. Returns the number of bytes read, which is
+    // shorter than the range when it extends past the end of the object.
+    pub async fn read_range_into<S>(
+        &self,
+        object: S,
+        range: Range<u64>,
+        buf: &mut [u8],
+    ) -> Result<usize, Error>
+    where
+        S: AsRef<str>,
+    {
+        let object = object.as_ref();
+        self.with_context("read_range_into", object, async {
+            if range.start >= range.end {
+                return Ok(0);
+            }
+            if (range.end - range.start) as usize > buf.len() {
+                return Err(Error::E(format!(
+                    "buffer of {} bytes can not hold range {:?}",
+                    buf.len(),
+                    range
+                )));
+            }
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                RANGE,
+                format!("bytes={}-{}", range.start, range.end - 1).parse()?,
+            );
+            let resp = self.signed_get(object, &[], headers).await?;
+            if resp.status() != StatusCode::PARTIAL_CONTENT {
+                return Err(Error::Object(ObjectError::GetError {
+                    msg: format!("range not satisfied, status: {}", resp.status()),
+                }));
+            }
+            let mut n = 0;
+            let chunks = resp.bytes_stream();
+            futures::pin_mut!(chunks);
+            while let Some(chunk) = chunks.try_next().await? {
+                if n + chunk.len() > buf.len() {
+                    return Err(Error::E("response exceeds the requested range".to_owned()));
+                }
+                buf[n..n + chunk.len()].copy_from_slice(&chunk);
+                n += chunk.len();
+            }
+            Ok(n)
+        })
+        .await
+    }
+
+    pub async fn get_object<S>(
+        &self,
+        object: S,
+        headers: Option<HashMap<S, S>>,
+        resources: Option<HashMap<S, Option<S>>>,
+    ) -> Result<Bytes, Error>
+    where
+        S: AsRef<str>,
+    {
+        let object = object.as_ref();
+        self.with_context("get_object", object, async {
+            let resources_str = if let Some(r) = resources {
+                self.get_resources_str(r)
+            } else {
+                String::new()
+            };
+            let host = &self.host(self.bucket(), object, &resources_str);
+            let headers = &if let Some(h) = headers {
+                to_headers(h)?
+            } else {
+                HeaderMap::new()
+            };
+            let resources_str = &resources_str;
+            let res = self
+                .retrying(&Method::GET, object, || async move {
+                    let mut headers = headers.clone();
+                    headers.insert(DATE, self.date().parse()?);
+                    let authorization = self
+                        .sign("GET", self.bucket(), object, resources_str, &mut headers)
+                        .await?;
+                    headers.insert("Authorization", authorization.parse()?);
+                    self.send_hedged(self.client.get(host).headers(headers))
+                        .await
+                })
+                .await?;
+            if !res.status().is_success() {
+                return Err(self
+                    .api_error(res, |reason| {
+                        Error::Object(ObjectError::GetError {
+                            msg: format!("can not get object, reason: {:?}", reason),
+                        })
+                    })
+                    .await);
+            }
+            // A ranged response still carries the CRC of the whole object.
+            let whole = res.status() == StatusCode::OK;
+            let resp_headers = res.headers().clone();
+            let body = res.bytes().await?;
+            if whole {
+                if let Some(crc) = self.buffer_crc(&body) {
+                    self.check_crc64(crc, &resp_headers)?;
+                }
+            }
+            Ok(body)
+        })
+        .await
+    }
+
+    pub async fn head_object<S>(
+        &self,
+        object: S,
+        headers: Option<HashMap<S, S>>,
+        resources: Option<HashMap<S, Option<S>>>,
+    ) -> Result<HeadObjectResult, Error>
+    where
+        S: AsRef<str>,
+    {
+        let object = object.as_ref();
+        self.with_context("head_object", object, async {
+            let resources_str = if let Some(r) = resources {
+                self.get_resources_str(r)
+            } else {
+                String::new()
+            };
+            let host = &self.host(self.bucket(), object, &resources_str);
+            let headers = &if let Some(h) = headers {
+                to_headers(h)?
+            } else {
+                HeaderMap::new()
+            };
+            let resources_str = &resources_str;
+            let res = self
+                .retrying(&Method::HEAD, object, || async move {
+                    let mut headers = headers.clone();
+                    headers.insert(DATE, self.date().parse()?);
+                    let authorization = self
+                        .sign("HEAD", self.bucket(), object, resources_str, &mut headers)
+                        .await?;
+                    headers.insert("Authorization", authorization.parse()?);
+                    self.send(self.client.head(host).headers(headers)).await
+                })
+                .await?;
+            if !res.status().is_success() {
+                let status = res.status();
+                return Err(self
+                    .api_error(res, |_| {
+                        Error::Object(ObjectError::GetError {
+                            msg: format!("can not head object, status: {}", status),
+                        })
+                    })
+                    .await);
+            }
+            Ok(HeadObjectResult::from_headers(res.headers().clone()))
+        })
+        .await
+    }
+
+    // https://help.aliyun.com/document_detail/31985.html
+    pub async fn get_object_meta<S>(&self, object: S) -> Result<ObjectMeta, Error>
+    where
+        S: AsRef<str>,
+    {
+        let object = object.as_ref();
+        self.with_context("get_object_meta", object, async {
+            let params = [("objectMeta", Some(String::new()))];
+            let resp = self
+                .signed_request(Method::HEAD, object, &params, HeaderMap::new(), Vec::new())
+                .await?;
+            if resp.status().is_success() {
+                ObjectMeta::from_headers(resp.headers())
+            } else {
+                let status = resp.status();
+                Err(self
+                    .api_error(resp, |_| {
+                        Error::Object(ObjectError::GetError {
+                            msg: format!("can not get object meta, status: {}", status),
+                        })
+                    })
+                    .await)
+            }
+        })
+        .await
+    }
+
+    pub async fn put_object_from_buffer<S1, S2, H, R>(
+        &self,
+        buf: &[u8],
+        object: S1,
+        headers: H,
+        resources: R,
+    ) -> Result<PutObjectResult, Error>
+    where
+        S1: AsRef<str>,
+        S2: AsRef<str>,
+        H: Into<Option<HashMap<S2, S2>>>,
+        R: Into<Option<HashMap<S2, Option<S2>>>>,
+    {
+        let object = object.as_ref();
+        self.with_context("put_object", object, async {
+            let resources_str = if let Some(r) = resources.into() {
+                self.get_resources_str(r)
+            } else {
+                String::new()
+            };
+            let host = &self.host(self.bucket(), object, &resources_str);
+            let mut headers = if let Some(h) = headers.into() {
+                to_headers(h)?
+            } else {
+                HeaderMap::new()
+            };
+            self.insert_content_md5(&mut headers, buf)?;
+            let (headers, resources_str) = (&headers, &resources_str);
+            let body = &Bytes::copy_from_slice(buf);
+            let res = self
+                .retrying(&Method::PUT, object, || async move {
+                    let mut headers = headers.clone();
+                    headers.insert(DATE, self.date().parse()?);
+                    let authorization = self
+                        .sign("PUT", self.bucket(), object, resources_str, &mut headers)
+                        .await?;
+                    headers.insert("Authorization", authorization.parse()?);
+                    self.throttle(body.len() as u64).await;
+                    self.send(self.client.put(host).headers(headers).body(body.clone()))
+                        .await
+                })
+                .await?;
+            if res.status().is_success() {
+                if let Some(crc) = self.buffer_crc(buf) {
+                    self.check_crc64(crc, res.headers())?;
+                }
+                self.invalidate(object, etag(res.headers())).await?;
+                let result = PutObjectResult::from_headers(res.headers());
+                Ok(result.with_body(res.bytes().await?))
+            } else {
+                Err(self
+                    .api_error(res, |reason| {
+                        Error::Object(ObjectError::PutError {
+                            msg: format!("can not put object, reason: {:?}", reason),
+                        })
+                    })
+                    .await)
+            }
+        })
+        .await
+    }
 
+    #[cfg(not(target_arch = "wasm32"))]
     pub async fn put_object_from_file<S1, S2, S3, H, R>(
         &self,
         file: S1,
         object_name: S2,
         headers: H,
         resources: R,
-    ) -> Result<(), Error>
+    ) -> Result<PutObjectResult, Error>
     where
         S1: AsRef<str>,
         S2: AsRef<str>,
@@ -421,53 +1589,81 @@ impl OSS {
         H: Into<Option<HashMap<S3, S3>>>,
         R: Into<Option<HashMap<S3, Option<S3>>>>,
     {
-        let mut file = tokio::fs::File::open(file.as_ref()).await?;
         let object_name = object_name.as_ref();
-        let resources_str = if let Some(r) = resources.into() {
-            self.get_resources_str(r)
-        } else {
-            String::new()
-        };
-        let host = self.host(self.bucket(), object_name, &resources_str);
-        let date = self.date();
-        let buf = load_file(&mut file).await?;
-        let mut headers = if let Some(h) = headers.into() {
-            to_headers(h)?
-        } else {
-            HeaderMap::new()
-        };
-        headers.insert(DATE, date.parse()?);
-        headers.insert(CONTENT_LENGTH, buf.len().to_string().parse()?);
-        let authorization = self.oss_sign(
-            "PUT",
-            self.key_id(),
-            self.key_secret(),
-            self.bucket(),
-            object_name,
-            &resources_str,
-            &headers,
-        );
-        headers.insert("Authorization", authorization.parse()?);
-
-        let resp = self
-            .client
-            .put(&host)
-            .headers(headers)
-            .body(buf)
-            .send()
-            .await?;
+        let path = file.as_ref();
+        self.with_context("put_object", object_name, async {
+            let file = rt::open(path).await?;
+            let resources_str = if let Some(r) = resources.into() {
+                self.get_resources_str(r)
+            } else {
+                String::new()
+            };
+            let host = self.host(self.bucket(), object_name, &resources_str);
+            let date = self.date();
+            let size = file.metadata().await?.len();
+            let mut headers = if let Some(h) = headers.into() {
+                to_headers(h)?
+            } else {
+                HeaderMap::new()
+            };
+            headers.insert(DATE, date.parse()?);
+            headers.insert(CONTENT_LENGTH, size.into());
+            if self.content_md5 {
+                let md5 = file_md5(rt::open(path).await?, 0, size).await?;
+                headers.insert("Content-MD5", md5.parse()?);
+            }
+            let authorization = self
+                .sign(
+                    "PUT",
+                    self.bucket(),
+                    object_name,
+                    &resources_str,
+                    &mut headers,
+                )
+                .await?;
+            headers.insert("Authorization", authorization.parse()?);
+
+            let crc = self.body_crc();
+            let resp = self
+                .send(
+                    self.client.put(&host).headers(headers).body(
+                        file_body(
+                            file,
+                            0,
+                            size,
+                            crc.clone(),
+                            self.progress(object_name, size),
+                            self.rate_limiter(),
+                        )
+                        .await?,
+                    ),
+                )
+                .await?;
 
-        if resp.status().is_success() {
-            Ok(())
-        } else {
-            Err(Error::Object(ObjectError::PutError {
-                msg: format!("can not put object, reason: {:?}", resp.text().await).into(),
-            }))
-        }
+            if resp.status().is_success() {
+                if let Some(crc) = crc {
+                    let crc = crc.lock().unwrap().value();
+                    self.check_crc64(crc, resp.headers())?;
+                }
+                self.invalidate(object_name, etag(resp.headers())).await?;
+                Ok(PutObjectResult::from_headers(resp.headers()))
+            } else {
+                Err(self
+                    .api_error(resp, |reason| {
+                        Error::Object(ObjectError::PutError {
+                            msg: format!("can not put object, reason: {:?}", reason),
+                        })
+                    })
+                    .await)
+            }
+        })
+        .await
     }
 
+    // Starts a multipart upload and returns its upload id. Most callers want
+    // `start_multipart_upload`, which wraps the id in a `MultipartUpload`.
     // https://help.aliyun.com/document_detail/31992.html
-    async fn initiate_multipart_upload<S2, S3, H>(
+    pub async fn initiate_multipart_upload<S2, S3, H>(
         &self,
         object_name: S2,
         headers: H,
@@ -478,161 +1674,243 @@ impl OSS {
         H: Into<Option<HashMap<S3, S3>>>,
     {
         let object_name = object_name.as_ref();
-        let resources_str = "uploads";
-
-        let host = self.host(self.bucket(), object_name, resources_str);
-        let date = self.date();
-        let mut headers = if let Some(h) = headers.into() {
-            to_headers(h)?
-        } else {
-            HeaderMap::new()
-        };
-        headers.insert(DATE, date.parse()?);
-        let authorization = self.oss_sign(
-            "POST",
-            self.key_id(),
-            self.key_secret(),
-            self.bucket(),
-            object_name,
-            resources_str,
-            &headers,
-        );
-        headers.insert("Authorization", authorization.parse()?);
+        self.with_context("initiate_multipart_upload", object_name, async {
+            let resources_str = "uploads";
 
-        let resp = self.client.post(&host).headers(headers).send().await?;
+            let host = &self.host(self.bucket(), object_name, resources_str);
+            let headers = &if let Some(h) = headers.into() {
+                to_headers(h)?
+            } else {
+                HeaderMap::new()
+            };
+            let resp = self
+                .retrying(&Method::POST, object_name, || async move {
+                    let mut headers = headers.clone();
+                    headers.insert(DATE, self.date().parse()?);
+                    let authorization = self
+                        .sign(
+                            "POST",
+                            self.bucket(),
+                            object_name,
+                            resources_str,
+                            &mut headers,
+                        )
+                        .await?;
+                    headers.insert("Authorization", authorization.parse()?);
+                    self.send(self.client.post(host).headers(headers)).await
+                })
+                .await?;
+
+            if resp.status().is_success() {
+                #[derive(Debug, Serialize, Deserialize, PartialEq)]
+                #[serde(rename_all = "PascalCase")]
+                struct InitiateMultipartUploadResult {
+                    bucket: String,
+                    key: String,
+                    upload_id: String,
+                }
 
-        if resp.status().is_success() {
-            #[derive(Debug, Serialize, Deserialize, PartialEq)]
-            struct InitiateMultipartUploadResult {
-                Bucket: String,
-                Key: String,
-                UploadId: String,
+                let init: InitiateMultipartUploadResult = from_str(&self.xml_text(resp).await?)?;
+                Ok(init.upload_id)
+            } else {
+                Err(self
+                    .api_error(resp, |reason| {
+                        Error::Object(ObjectError::PutError {
+                            msg: format!("can not put object, reason: {:?}", reason),
+                        })
+                    })
+                    .await)
             }
-
-            let init: InitiateMultipartUploadResult =
-                from_str(&resp.text().await.unwrap()).unwrap();
-            Ok(init.UploadId)
-        } else {
-            Err(Error::Object(ObjectError::PutError {
-                msg: format!("can not put object, reason: {:?}", resp.text().await).into(),
-            }))
-        }
+        })
+        .await
     }
 
+    // Uploads `chunk` of the file at `path` as a part.
     // https://help.aliyun.com/document_detail/31993.html
-    async fn upload_part<S1, S2, H>(
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) async fn upload_part_from_file<S1, S2, H>(
         &self,
-        file: &mut tokio::fs::File,
+        path: &str,
         object_name: S1,
         chunk: FileChunk,
         upload_id: String,
         headers: H,
-    ) -> Result<String, Error>
+        progress: Option<Arc<Progress>>,
+    ) -> Result<Part, Error>
     where
         S1: AsRef<str>,
         S2: AsRef<str>,
         H: Into<Option<HashMap<S2, S2>>>,
     {
         let object_name = object_name.as_ref();
-        let resources_str = &format!("partNumber={}&uploadId={}", chunk.number, upload_id);
-
-        let host = self.host(self.bucket(), object_name, resources_str);
-        let date = self.date();
-        let mut headers = if let Some(h) = headers.into() {
-            to_headers(h)?
-        } else {
-            HeaderMap::new()
-        };
-        headers.insert(DATE, date.parse()?);
+        self.with_context("upload_part", object_name, async {
+            let resources_str = &format!("partNumber={}&uploadId={}", chunk.number, upload_id);
 
-        let authorization = self.oss_sign(
-            "PUT",
-            self.key_id(),
-            self.key_secret(),
-            self.bucket(),
-            object_name,
-            resources_str,
-            &headers,
-        );
-        headers.insert("Authorization", authorization.parse()?);
-
-        let buf = load_chunk_file(file, chunk.offset, chunk.size).await?;
-        headers.insert(CONTENT_LENGTH, buf.len().to_string().parse()?);
+            let host = self.host(self.bucket(), object_name, resources_str);
+            let date = self.date();
+            let mut headers = if let Some(h) = headers.into() {
+                to_headers(h)?
+            } else {
+                HeaderMap::new()
+            };
+            headers.insert(DATE, date.parse()?);
+            if self.content_md5 {
+                let md5 = file_md5(rt::open(path).await?, chunk.offset, chunk.size).await?;
+                headers.insert("Content-MD5", md5.parse()?);
+            }
 
-        let resp = self
-            .client
-            .put(&host)
-            .headers(headers)
-            .body(buf)
-            .send()
+            let authorization = self
+                .sign(
+                    "PUT",
+                    self.bucket(),
+                    object_name,
+                    resources_str,
+                    &mut headers,
+                )
+                .await?;
+            headers.insert("Authorization", authorization.parse()?);
+
+            let file = rt::open(path).await?;
+            let crc = self.body_crc();
+            let body = file_body(
+                file,
+                chunk.offset,
+                chunk.size,
+                crc.clone(),
+                progress,
+                self.rate_limiter(),
+            )
             .await?;
-
-        if resp.status().is_success() {
-            let etag = resp.headers().get(ETAG).unwrap().to_str().unwrap();
-            Ok(etag.to_owned())
-        } else {
-            Err(Error::Object(ObjectError::PutError {
-                msg: format!("can not put object, reason: {:?}", resp.text().await).into(),
-            }))
-        }
+            headers.insert(CONTENT_LENGTH, chunk.size.into());
+
+            let resp = self
+                .send(self.client.put(&host).headers(headers).body(body))
+                .await?;
+
+            if resp.status().is_success() {
+                let etag = resp
+                    .headers()
+                    .get(ETAG)
+                    .and_then(|v| v.to_str().ok())
+                    .map(|v| v.to_owned())
+                    .ok_or_else(|| Error::E("upload part response has no ETag".to_owned()))?;
+                let part = Part::new(chunk.number, etag);
+                match crc {
+                    Some(crc) => {
+                        let crc = crc.lock().unwrap().value();
+                        self.check_crc64(crc, resp.headers())?;
+                        Ok(part.with_crc64(crc, chunk.size))
+                    }
+                    None => Ok(part),
+                }
+            } else {
+                Err(self
+                    .api_error(resp, |reason| {
+                        Error::Object(ObjectError::PutError {
+                            msg: format!("can not put object, reason: {:?}", reason),
+                        })
+                    })
+                    .await)
+            }
+        })
+        .await
     }
 
-    // https://help.aliyun.com/document_detail/31993.html
-    async fn complete_multipart_upload<S1, S3, H>(
+    // Assembles the object from the uploaded parts, listed in ascending part
+    // number order.
+    // https://help.aliyun.com/document_detail/31995.html
+    pub async fn complete_multipart_upload<S1, S3, H>(
         &self,
         object_name: S1,
         upload_id: String,
         complete: CompleteMultipartUpload,
         headers: H,
-    ) -> Result<(), Error>
+    ) -> Result<CompleteMultipartUploadResult, Error>
     where
         S1: AsRef<str>,
         S3: AsRef<str>,
         H: Into<Option<HashMap<S3, S3>>>,
     {
         let object_name = object_name.as_ref();
+        self.with_context("complete_multipart_upload", object_name, async {
+            let headers = match headers.into() {
+                Some(h) => to_headers(h)?,
+                None => HeaderMap::new(),
+            };
+            let (etag, headers) = self
+                .complete_upload(object_name, &upload_id, complete, headers)
+                .await?;
+            Ok(CompleteMultipartUploadResult::from_headers(etag, &headers))
+        })
+        .await
+    }
+
+    // Completes the upload and returns the ETag of the new object, if the
+    // body has one, together with the response headers.
+    pub(crate) async fn complete_upload(
+        &self,
+        object_name: &str,
+        upload_id: &str,
+        complete: CompleteMultipartUpload,
+        mut headers: HeaderMap,
+    ) -> Result<(Option<String>, HeaderMap), Error> {
         let resources_str = &format!("uploadId={}", upload_id);
 
-        let host = self.host(self.bucket(), object_name, resources_str);
-        let buf = get_complete_str(complete);
-        let date = self.date();
-        let mut headers = if let Some(h) = headers.into() {
-            to_headers(h)?
-        } else {
-            HeaderMap::new()
-        };
-        headers.insert(DATE, date.parse()?);
-        let authorization = self.oss_sign(
-            "POST",
-            self.key_id(),
-            self.key_secret(),
-            self.bucket(),
-            object_name,
-            resources_str,
-            &headers,
-        );
-        headers.insert("Authorization", authorization.parse()?);
+        let host = &self.host(self.bucket(), object_name, resources_str);
+        let crc = combined_crc64(&complete.part);
+        let buf = &get_complete_str(complete);
         headers.insert(CONTENT_LENGTH, buf.len().to_string().parse()?);
-
+        let headers = &headers;
         let resp = self
-            .client
-            .post(&host)
-            .headers(headers)
-            .body(buf)
-            .send()
+            .retrying(&Method::POST, object_name, || async move {
+                let mut headers = headers.clone();
+                headers.insert(DATE, self.date().parse()?);
+                let authorization = self
+                    .sign(
+                        "POST",
+                        self.bucket(),
+                        object_name,
+                        resources_str,
+                        &mut headers,
+                    )
+                    .await?;
+                headers.insert("Authorization", authorization.parse()?);
+                self.send(self.client.post(host).headers(headers).body(buf.clone()))
+                    .await
+            })
             .await?;
 
         if resp.status().is_success() {
-            Ok(())
+            if let Some(crc) = crc {
+                self.check_crc64(crc, resp.headers())?;
+            }
+            #[derive(Debug, Deserialize)]
+            struct CompleteMultipartUploadResult {
+                #[serde(rename = "ETag")]
+                etag: Option<String>,
+            }
+
+            // With a callback the body is the callback server's reply.
+            let resp_headers = resp.headers().clone();
+            let body = resp.text().await?;
+            let etag = from_str::<CompleteMultipartUploadResult>(&body)
+                .ok()
+                .and_then(|r| r.etag);
+            self.invalidate(object_name, etag.as_deref()).await?;
+            Ok((etag, resp_headers))
         } else {
-            Err(Error::Object(ObjectError::PutError {
-                msg: format!("can not put object, status code: {:?}", resp.text().await).into(),
-            }))
+            Err(self
+                .api_error(resp, |reason| {
+                    Error::Object(ObjectError::PutError {
+                        msg: format!("can not put object, status code: {:?}", reason),
+                    })
+                })
+                .await)
         }
     }
 
     // https://help.aliyun.com/document_detail/31996.html
-    async fn abort_multipart_upload<S1>(
+    pub async fn abort_multipart_upload<S1>(
         &self,
         object_name: S1,
         upload_id: String,
@@ -641,39 +1919,67 @@ impl OSS {
         S1: AsRef<str>,
     {
         let object_name = object_name.as_ref();
-        let resources_str = &format!("uploadId={}", upload_id);
-
-        let host = self.host(self.bucket(), object_name, resources_str);
-        let date = self.date();
-        let mut headers = HeaderMap::new();
-        headers.insert(DATE, date.parse()?);
-        let authorization = self.oss_sign(
-            "DELETE",
-            self.key_id(),
-            self.key_secret(),
-            self.bucket(),
-            object_name,
-            resources_str,
-            &headers,
-        );
-        headers.insert("Authorization", authorization.parse()?);
-
-        let resp = self.client.delete(&host).send().await?;
+        self.with_context("abort_multipart_upload", object_name, async {
+            let resources_str = &format!("uploadId={}", upload_id);
+
+            let host = &self.host(self.bucket(), object_name, resources_str);
+            let resp = self
+                .retrying(&Method::DELETE, object_name, || async move {
+                    let mut headers = HeaderMap::new();
+                    headers.insert(DATE, self.date().parse()?);
+                    let authorization = self
+                        .sign(
+                            "DELETE",
+                            self.bucket(),
+                            object_name,
+                            resources_str,
+                            &mut headers,
+                        )
+                        .await?;
+                    headers.insert("Authorization", authorization.parse()?);
+                    self.send(self.client.delete(host).headers(headers)).await
+                })
+                .await?;
+
+            if resp.status().is_success() {
+                Ok(())
+            } else {
+                Err(self
+                    .api_error(resp, |reason| {
+                        Error::Object(ObjectError::DeleteError {
+                            msg: format!("can not abort multipart upload, reason: {:?}", reason),
+                        })
+                    })
+                    .await)
+            }
+        })
+        .await
+    }
 
-        if resp.status().is_success() {
-            Ok(())
-        } else {
-            Err(Error::Object(ObjectError::DeleteError {
-                msg: format!(
-                    "can not abort multipart upload, reason: {:?}",
-                    resp.text().await
-                )
-                .into(),
-            }))
+    // Aborts the multipart upload `e` stopped and returns `e`. A failed abort
+    // leaves the parts on the server, where they are billed until
+    // `list_multipart_uploads` finds them, so it is logged with the upload id.
+    pub(crate) async fn abort_after(
+        &self,
+        object_name: &str,
+        upload_id: String,
+        e: Error,
+    ) -> Error {
+        if let Err(abort) = self
+            .abort_multipart_upload(object_name, upload_id.clone())
+            .await
+        {
+            warn!(
+                "can not abort multipart upload {} of {}: {}",
+                upload_id, object_name, abort
+            );
         }
+        e
     }
 
+    // Uploads one part at a time, `parallel_upload` takes a `Concurrency`.
     // <MinSizeAllowed>102400</MinSizeAllowed>
+    #[cfg(not(target_arch = "wasm32"))]
     pub async fn chunk_upload_by_size<S1, H>(
         &self,
         object_name: S1,
@@ -685,80 +1991,568 @@ impl OSS {
         S1: AsRef<str>,
         H: Into<Option<HashMap<S1, S1>>>,
     {
-        let mut file = tokio::fs::File::open(file.as_ref()).await?;
-        // chunk object
-        let chunks = split_file_by_part_size(&file, chunk_size).await?;
-        if chunks.is_empty() {
-            return Err(Error::E("chunks is empty".to_owned()));
-        }
-        // init multi upload
         let object_name = object_name.as_ref();
-        let upload_id = self.initiate_multipart_upload(object_name, headers).await?;
-        // part upload
-        let mut parts = vec![];
-        for chunk in chunks {
-            let etag = match self
-                .upload_part(
-                    &mut file,
+        let path = file.as_ref();
+        self.with_context("chunk_upload_by_size", object_name, async {
+            let file = rt::open(path).await?;
+            // chunk object
+            let chunks = split_file_by_part_size(&file, chunk_size).await?;
+            if chunks.is_empty() {
+                return Err(Error::E("chunks is empty".to_owned()));
+            }
+            let progress = self.progress(object_name, file.metadata().await?.len());
+            // init multi upload
+            let upload_id = self.initiate_multipart_upload(object_name, headers).await?;
+            // part upload
+            let mut parts = vec![];
+            for chunk in chunks {
+                let part = match self
+                    .upload_part_from_file(
+                        path,
+                        object_name,
+                        chunk,
+                        upload_id.clone(),
+                        None::<HashMap<&str, &str>>,
+                        progress.clone(),
+                    )
+                    .await
+                {
+                    Ok(part) => part,
+                    Err(e) => return Err(self.abort_after(object_name, upload_id, e).await),
+                };
+                parts.push(part);
+            }
+            // complete multi upload
+            self.complete_multipart_upload_idempotent(object_name, upload_id, parts, path)
+                .await
+        })
+        .await
+    }
+
+    // CompleteMultipartUpload can time out after the server has already
+    // assembled the object, the retry then fails with NoSuchUpload. That
+    // counts as success when the object matches `file` in size and CRC64.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) async fn complete_multipart_upload_idempotent(
+        &self,
+        object_name: &str,
+        upload_id: String,
+        parts: Vec<Part>,
+        file: &str,
+    ) -> Result<(), Error> {
+        let mut attempt = 1;
+        loop {
+            let complete = CompleteMultipartUpload::new(parts.clone());
+            let e = match self
+                .complete_multipart_upload(
                     object_name,
-                    chunk.clone(),
                     upload_id.clone(),
+                    complete,
                     None::<HashMap<&str, &str>>,
                 )
                 .await
             {
-                Ok(etag) => etag,
-                Err(e) => {
-                    let _ = self.abort_multipart_upload(object_name, upload_id).await;
-                    return Err(e);
-                }
+                Ok(_) => return Ok(()),
+                Err(e) => e,
             };
-            parts.push(Part {
-                PartNumber: chunk.number,
-                ETag: etag,
-            });
+            if attempt > 1 && e.code() == Some("NoSuchUpload") {
+                let head = self.head_object(object_name, None, None).await?;
+                let remote = RemoteState::from_head(&head)?;
+                if !needs_upload(file, &remote, SyncPolicy::Crc64).await? {
+                    debug!("{} was completed by an earlier attempt", object_name);
+                    return Ok(());
+                }
+                return Err(e);
+            }
+            if attempt >= 3 || !e.disposition().is_retryable() {
+                return Err(e);
+            }
+            rt::sleep(self.retry_policy.delay(attempt)).await;
+            attempt += 1;
         }
-        // complete multi upload
-        self.complete_multipart_upload(
+    }
+
+    // Like `chunk_upload_by_size`, with several parts uploaded at once.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn parallel_upload<S1, H>(
+        &self,
+        object_name: S1,
+        file: S1,
+        chunk_size: u64,
+        concurrency: Concurrency,
+        headers: H,
+    ) -> Result<(), Error>
+    where
+        S1: AsRef<str>,
+        H: Into<Option<HashMap<S1, S1>>>,
+    {
+        self.parallel_upload_cancellable(
             object_name,
-            upload_id,
-            CompleteMultipartUpload { Part: parts },
-            None::<HashMap<&str, &str>>,
+            file,
+            chunk_size,
+            concurrency,
+            headers,
+            &CancellationToken::new(),
         )
         .await
     }
 
-    pub async fn delete_object<S>(&self, object_name: S) -> Result<(), Error>
+    // Like `parallel_upload`, stopping when `cancel` is cancelled: the parts
+    // in flight are dropped and the multipart upload is aborted on the
+    // server, so no orphaned parts are left behind. Should the abort fail,
+    // it is logged as a warning with the upload id.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn parallel_upload_cancellable<S1, H>(
+        &self,
+        object_name: S1,
+        file: S1,
+        chunk_size: u64,
+        concurrency: Concurrency,
+        headers: H,
+        cancel: &CancellationToken,
+    ) -> Result<(), Error>
+    where
+        S1: AsRef<str>,
+        H: Into<Option<HashMap<S1, S1>>>,
+    {
+        let object_name = object_name.as_ref();
+        let path = file.as_ref();
+        self.with_context("parallel_upload", object_name, async {
+            let file = rt::open(path).await?;
+            let chunks = split_file_by_part_size(&file, chunk_size).await?;
+            if chunks.is_empty() {
+                return Err(Error::E("chunks is empty".to_owned()));
+            }
+            let progress = self.progress(object_name, file.metadata().await?.len());
+            let upload_id = self.initiate_multipart_upload(object_name, headers).await?;
+            let parts = run_tuned(
+                chunks,
+                concurrency,
+                |chunk| chunk.size,
+                |chunk| {
+                    let oss = self.clone();
+                    let (path, object_name) = (path.to_owned(), object_name.to_owned());
+                    let (upload_id, progress) = (upload_id.clone(), progress.clone());
+                    // Every part reads through its own handle.
+                    self.spawn_child("upload-part", async move {
+                        oss.upload_part_from_file(
+                            &path,
+                            object_name,
+                            chunk,
+                            upload_id,
+                            None::<HashMap<&str, &str>>,
+                            progress,
+                        )
+                        .await
+                    })
+                },
+            );
+            let parts = until_cancelled(cancel, parts).await;
+            let parts = match parts {
+                Ok(parts) => parts,
+                Err(e) => return Err(self.abort_after(object_name, upload_id, e).await),
+            };
+            self.complete_multipart_upload_idempotent(object_name, upload_id, parts, path)
+                .await
+        })
+        .await
+    }
+
+    pub async fn delete_object<S>(&self, object_name: S) -> Result<DeleteObjectResult, Error>
     where
         S: AsRef<str>,
     {
         let object_name = object_name.as_ref();
-        let host = self.host(self.bucket(), object_name, "");
-        let date = self.date();
+        self.with_context("delete_object", object_name, async {
+            let host = &self.host(self.bucket(), object_name, "");
+            let resp = self
+                .retrying(&Method::DELETE, object_name, || async move {
+                    let mut headers = HeaderMap::new();
+                    headers.insert(DATE, self.date().parse()?);
+                    let authorization = self
+                        .sign("DELETE", self.bucket(), object_name, "", &mut headers)
+                        .await?;
+                    headers.insert("Authorization", authorization.parse()?);
+                    self.send(self.client.delete(host).headers(headers)).await
+                })
+                .await?;
+
+            if resp.status().is_success() {
+                Ok(DeleteObjectResult::from_headers(resp.headers()))
+            } else {
+                Err(self
+                    .api_error(resp, |reason| {
+                        Error::Object(ObjectError::DeleteError {
+                            msg: format!("can not delete object, reason: {:?}", reason),
+                        })
+                    })
+                    .await)
+            }
+        })
+        .await
+    }
 
-        let mut headers = HeaderMap::new();
-        headers.insert(DATE, date.parse()?);
-        let authorization = self.oss_sign(
-            "DELETE",
-            self.key_id(),
-            self.key_secret(),
-            self.bucket(),
-            object_name,
+    // Deletes the keys in batches of 1000, the most a single request takes.
+    // In quiet mode only the keys that failed are reported.
+    // https://help.aliyun.com/document_detail/31983.html
+    pub async fn delete_objects<I, S>(
+        &self,
+        keys: I,
+        quiet: bool,
+    ) -> Result<DeleteObjectsResult, Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let objects: Vec<(String, Option<String>)> = keys
+            .into_iter()
+            .map(|k| (k.as_ref().to_owned(), None))
+            .collect();
+        self.with_context(
+            "delete_objects",
             "",
-            &headers,
-        );
-        headers.insert("Authorization", authorization.parse()?);
+            self.delete_object_versions(objects, quiet),
+        )
+        .await
+    }
+
+    // Like `delete_objects`, deleting the given version of a key where one
+    // is set, which removes it for good on a versioned bucket.
+    pub(crate) async fn delete_object_versions(
+        &self,
+        objects: Vec<(String, Option<String>)>,
+        quiet: bool,
+    ) -> Result<DeleteObjectsResult, Error> {
+        let mut result = DeleteObjectsResult::default();
+        for batch in objects.chunks(1000) {
+            let body = delete_objects_body(batch, quiet);
+            // Required by DeleteMultipleObjects whether `content_md5` is on or not.
+            let mut headers = HeaderMap::new();
+            headers.insert("Content-MD5", content_md5(body.as_bytes()).parse()?);
+            headers.insert(CONTENT_TYPE, "application/xml".parse()?);
+            let params = [("delete", Some(String::new()))];
+            let resp = self
+                .signed_request(Method::POST, "", &params, headers, body.into_bytes())
+                .await?;
+            if !resp.status().is_success() {
+                return Err(self
+                    .api_error(resp, |reason| {
+                        Error::Object(ObjectError::DeleteError {
+                            msg: format!("can not delete objects, reason: {:?}", reason),
+                        })
+                    })
+                    .await);
+            }
+            result.extend(parse_delete_result(&self.xml_text(resp).await?)?);
+        }
+        Ok(result)
+    }
+}
 
-        let resp = self.client.delete(&host).headers(headers).send().await?;
+fn delete_objects_body(objects: &[(String, Option<String>)], quiet: bool) -> String {
+    let mut body = format!("<Delete><Quiet>{}</Quiet>", quiet);
+    for (key, version_id) in objects {
+        body += &format!("<Object><Key>{}</Key>", xml_escape(key));
+        if let Some(version_id) = version_id {
+            body += &format!("<VersionId>{}</VersionId>", xml_escape(version_id));
+        }
+        body += "</Object>";
+    }
+    body += "</Delete>";
+    body
+}
 
-        if resp.status().is_success() {
-            Ok(())
-        } else {
-            Err(Error::Object(ObjectError::DeleteError {
-                msg: format!("can not delete object, reason: {:?}", resp.text().await).into(),
-            }))
+fn parse_delete_result(xml_str: &str) -> Result<DeleteObjectsResult, Error> {
+    let mut reader = Reader::from_str(xml_str);
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+
+    let mut deleted = Vec::new();
+    let mut errors = Vec::new();
+    let mut fields: HashMap<Vec<u8>, String> = HashMap::new();
+
+    loop {
+        match reader.read_event(&mut buf) {
+            Ok(Event::Start(ref e)) => match e.name() {
+                b"DeleteResult" | b"Deleted" | b"Error" => fields.clear(),
+                name => {
+                    let name = name.to_vec();
+                    let text = reader.read_text(e.name(), &mut Vec::new())?;
+                    fields.insert(name, text);
+                }
+            },
+            Ok(Event::End(ref e)) => {
+                let mut field = |name: &[u8]| fields.remove(name);
+                match e.name() {
+                    b"Deleted" => deleted.push(DeletedObject::new(
+                        field(b"Key").unwrap_or_default(),
+                        field(b"VersionId"),
+                        field(b"DeleteMarker").as_deref() == Some("true"),
+                    )),
+                    b"Error" => errors.push(DeleteKeyError::new(
+                        field(b"Key").unwrap_or_default(),
+                        field(b"Code").unwrap_or_default(),
+                        field(b"Message").unwrap_or_default(),
+                    )),
+                    _ => (),
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(e.into()),
+            _ => (),
+        }
+        buf.clear();
+    }
+    Ok(DeleteObjectsResult::new(deleted, errors))
+}
+
+// The top level elements of a GetBucket (V1 or V2) response by name,
+// along with its objects and common prefixes.
+struct Listing {
+    fields: HashMap<String, String>,
+    objects: Vec<Object>,
+    common_prefixes: Vec<String>,
+}
+
+impl Listing {
+    fn field(&self, name: &str) -> String {
+        self.fields.get(name).cloned().unwrap_or_default()
+    }
+
+    fn is_truncated(&self) -> bool {
+        self.field("IsTruncated") == "true"
+    }
+}
+
+fn parse_listing(xml_str: &str) -> Result<Listing, Error> {
+    let mut reader = Reader::from_str(xml_str);
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+
+    let mut fields = HashMap::new();
+    let mut objects = Vec::new();
+    let mut common_prefixes = Vec::new();
+
+    let mut key = String::new();
+    let mut last_modified = String::new();
+    let mut etag = String::new();
+    let mut size = 0;
+    let mut storage_class = String::new();
+    let mut id = String::new();
+    let mut display_name = String::new();
+    let mut in_root = false;
+    let mut in_contents = false;
+    let mut in_common_prefixes = false;
+
+    loop {
+        match reader.read_event(&mut buf) {
+            Ok(Event::Start(ref e)) if !in_root => in_root = e.name() == b"ListBucketResult",
+            Ok(Event::Start(ref e)) if in_common_prefixes && e.name() == b"Prefix" => {
+                common_prefixes.push(reader.read_text(e.name(), &mut Vec::new())?)
+            }
+            Ok(Event::Start(_)) if in_common_prefixes => (),
+            Ok(Event::Start(ref e)) if in_contents => match e.name() {
+                b"Key" => key = reader.read_text(e.name(), &mut Vec::new())?,
+                b"LastModified" => last_modified = reader.read_text(e.name(), &mut Vec::new())?,
+                b"ETag" => etag = reader.read_text(e.name(), &mut Vec::new())?,
+                b"Size" => {
+                    size = reader
+                        .read_text(e.name(), &mut Vec::new())?
+                        .parse()
+                        .map_err(|_| Error::E("invalid object size".to_owned()))?
+                }
+                b"StorageClass" => storage_class = reader.read_text(e.name(), &mut Vec::new())?,
+                b"ID" => id = reader.read_text(e.name(), &mut Vec::new())?,
+                b"DisplayName" => display_name = reader.read_text(e.name(), &mut Vec::new())?,
+                _ => (),
+            },
+            Ok(Event::Start(ref e)) => match e.name() {
+                b"CommonPrefixes" => in_common_prefixes = true,
+                b"Contents" => {
+                    in_contents = true;
+                    key = String::new();
+                    last_modified = String::new();
+                    etag = String::new();
+                    size = 0;
+                    storage_class = String::new();
+                    id = String::new();
+                    display_name = String::new();
+                }
+                name => {
+                    let name = String::from_utf8_lossy(name).into_owned();
+                    let text = reader.read_text(e.name(), &mut Vec::new())?;
+                    fields.insert(name, text);
+                }
+            },
+            Ok(Event::End(ref e)) if e.name() == b"CommonPrefixes" => in_common_prefixes = false,
+            Ok(Event::End(ref e)) if e.name() == b"Contents" => {
+                in_contents = false;
+                objects.push(Object::new(
+                    key.clone(),
+                    last_modified.clone(),
+                    etag.clone(),
+                    size,
+                    storage_class.clone(),
+                    Owner::new(id.clone(), display_name.clone()),
+                ));
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(e.into()),
+            _ => (),
+        }
+        buf.clear();
+    }
+
+    // Listings are requested with encoding-type=url so that keys with
+    // characters XML can't carry come through, decode them back.
+    if fields.get("EncodingType").map(|e| e.as_str()) == Some("url") {
+        for name in ["Prefix", "Marker", "NextMarker", "Delimiter", "StartAfter"] {
+            if let Some(value) = fields.get_mut(name) {
+                *value = url_decode(value);
+            }
         }
+        objects = objects
+            .into_iter()
+            .map(|o| {
+                Object::new(
+                    url_decode(o.key()),
+                    o.last_modified().to_owned(),
+                    o.etag().to_owned(),
+                    o.size(),
+                    o.storage_class().to_owned(),
+                    o.owner().clone(),
+                )
+            })
+            .collect();
+        common_prefixes = common_prefixes.iter().map(|p| url_decode(p)).collect();
     }
+
+    Ok(Listing {
+        fields,
+        objects,
+        common_prefixes,
+    })
+}
+
+fn parse_list_buckets(xml_str: &str) -> Result<ListBuckets, Error> {
+    let mut result = Vec::new();
+    let mut reader = Reader::from_str(xml_str);
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+
+    let mut prefix = String::new();
+    let mut marker = String::new();
+    let mut max_keys = String::new();
+    let mut is_truncated = false;
+    let mut next_marker = String::new();
+    let mut id = String::new();
+    let mut display_name = String::new();
+
+    let mut name = String::new();
+    let mut location = String::new();
+    let mut create_date = String::new();
+    let mut extranet_endpoint = String::new();
+    let mut intranet_endpoint = String::new();
+    let mut storage_class = String::new();
+
+    let list_buckets;
+
+    loop {
+        match reader.read_event(&mut buf) {
+            Ok(Event::Start(ref e)) => match e.name() {
+                b"Prefix" => prefix = reader.read_text(e.name(), &mut Vec::new())?,
+                b"Marker" => marker = reader.read_text(e.name(), &mut Vec::new())?,
+                b"MaxKeys" => max_keys = reader.read_text(e.name(), &mut Vec::new())?,
+                b"IsTruncated" => {
+                    is_truncated = reader.read_text(e.name(), &mut Vec::new())? == "true"
+                }
+                b"NextMarker" => next_marker = reader.read_text(e.name(), &mut Vec::new())?,
+                b"ID" => id = reader.read_text(e.name(), &mut Vec::new())?,
+                b"DisplayName" => display_name = reader.read_text(e.name(), &mut Vec::new())?,
+
+                b"Bucket" => {
+                    name = String::new();
+                    location = String::new();
+                    create_date = String::new();
+                    extranet_endpoint = String::new();
+                    intranet_endpoint = String::new();
+                    storage_class = String::new();
+                }
+
+                b"Name" => name = reader.read_text(e.name(), &mut Vec::new())?,
+                b"CreationDate" => create_date = reader.read_text(e.name(), &mut Vec::new())?,
+                b"ExtranetEndpoint" => {
+                    extranet_endpoint = reader.read_text(e.name(), &mut Vec::new())?
+                }
+                b"IntranetEndpoint" => {
+                    intranet_endpoint = reader.read_text(e.name(), &mut Vec::new())?
+                }
+                b"Location" => location = reader.read_text(e.name(), &mut Vec::new())?,
+                b"StorageClass" => storage_class = reader.read_text(e.name(), &mut Vec::new())?,
+                _ => (),
+            },
+            Ok(Event::End(ref e)) if e.name() == b"Bucket" => {
+                let bucket = Bucket::new(
+                    name.clone(),
+                    create_date.clone(),
+                    location.clone(),
+                    extranet_endpoint.clone(),
+                    intranet_endpoint.clone(),
+                    storage_class.clone(),
+                );
+                result.push(bucket);
+            }
+            Ok(Event::Eof) => {
+                list_buckets = ListBuckets::new(
+                    prefix,
+                    marker,
+                    max_keys,
+                    is_truncated,
+                    next_marker,
+                    id,
+                    display_name,
+                    result,
+                );
+                break;
+            } // exits the loop when reaching end of file
+            Err(e) => return Err(Error::Qxml(e)),
+            _ => (), // There are several other `Event`s we do not consider here
+        }
+        buf.clear();
+    }
+    Ok(list_buckets)
+}
+
+fn parse_list_objects(xml_str: &str) -> Result<ListObjects, Error> {
+    let listing = parse_listing(xml_str)?;
+    Ok(ListObjects::new(
+        listing.field("Name"),
+        listing.field("Prefix"),
+        listing.field("Marker"),
+        listing.field("MaxKeys"),
+        listing.field("Delimiter"),
+        listing.is_truncated(),
+        listing.field("NextMarker"),
+        listing.objects,
+        listing.common_prefixes,
+    ))
+}
+
+fn parse_list_objects_v2(xml_str: &str) -> Result<ListObjectsV2, Error> {
+    let listing = parse_listing(xml_str)?;
+    Ok(ListObjectsV2::new(
+        listing.field("Name"),
+        listing.field("Prefix"),
+        listing.field("StartAfter"),
+        listing.field("ContinuationToken"),
+        listing.field("NextContinuationToken"),
+        listing.field("MaxKeys"),
+        listing.field("Delimiter"),
+        listing.field("KeyCount").parse().unwrap_or_default(),
+        listing.is_truncated(),
+        listing.objects,
+        listing.common_prefixes,
+    ))
 }
 
 // <CompleteMultipartUpload>
@@ -769,19 +2563,73 @@ impl OSS {
 // ...
 // </CompleteMultipartUpload>
 #[derive(Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
 pub struct CompleteMultipartUpload {
-    Part: Vec<Part>,
+    part: Vec<Part>,
 }
 
-#[derive(Debug, Deserialize, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
 pub struct Part {
-    PartNumber: u64,
-    ETag: String,
+    part_number: u64,
+    #[serde(rename = "ETag")]
+    etag: String,
+    // CRC64 and size of the part data, when it was verified on upload.
+    #[serde(skip)]
+    crc64: Option<(u64, u64)>,
+}
+
+impl CompleteMultipartUpload {
+    pub fn new(part: Vec<Part>) -> Self {
+        CompleteMultipartUpload { part }
+    }
+
+    pub fn parts(&self) -> &[Part] {
+        &self.part
+    }
+}
+
+impl Part {
+    pub fn new(part_number: u64, etag: String) -> Self {
+        Part {
+            part_number,
+            etag,
+            crc64: None,
+        }
+    }
+
+    pub(crate) fn with_crc64(mut self, crc: u64, size: u64) -> Self {
+        self.crc64 = Some((crc, size));
+        self
+    }
+
+    pub fn part_number(&self) -> u64 {
+        self.part_number
+    }
+
+    pub fn etag(&self) -> &str {
+        &self.etag
+    }
+}
+
+pub(crate) fn server_crc64(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get("x-oss-hash-crc64ecma")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok())
+}
+
+// The CRC64 of the object assembled from `parts`, if all of them carry one.
+fn combined_crc64(parts: &[Part]) -> Option<u64> {
+    parts.iter().try_fold(0, |crc, part| {
+        let (part_crc, size) = part.crc64?;
+        Some(Crc64::combine(crc, part_crc, size))
+    })
 }
 
 fn get_complete_str(complete: CompleteMultipartUpload) -> String {
     let mut str = String::from("<CompleteMultipartUpload>");
-    for p in complete.Part {
+    for p in complete.part {
         str.push_str(&to_string(&p).unwrap());
     }
     str.push_str("</CompleteMultipartUpload>");
@@ -797,14 +2645,16 @@ mod tests {
     // waiting for the serde-xml-rs to fix the serde vector bug
     fn test_get_complete_str() {
         let complete = CompleteMultipartUpload {
-            Part: vec![
+            part: vec![
                 Part {
-                    PartNumber: 2,
-                    ETag: r#""test""#.to_string(),
+                    part_number: 2,
+                    etag: r#""test""#.to_string(),
+                    crc64: None,
                 },
                 Part {
-                    PartNumber: 2,
-                    ETag: r#""123""#.to_string(),
+                    part_number: 2,
+                    etag: r#""123""#.to_string(),
+                    crc64: None,
                 },
             ],
         };
@@ -813,14 +2663,327 @@ mod tests {
         assert_eq!(str, "<CompleteMultipartUpload><Part><PartNumber>2</PartNumber><ETag>\"test\"</ETag></Part><Part><PartNumber>2</PartNumber><ETag>\"123\"</ETag></Part></CompleteMultipartUpload>");
     }
 
+    #[tokio::test]
+    async fn test_error_text_is_capped() {
+        let oss_instance = OSS::builder(
+            "xxx".to_string(),
+            "xxx".to_string(),
+            "xxx.aliyuncs.com".to_string(),
+            "xxx".to_string(),
+        )
+        .error_body_limit(8)
+        .build();
+        let resp = Response::from(http::Response::new("<html>proxy error</html>"));
+        assert_eq!(oss_instance.error_text(resp).await.unwrap(), "<html>pr...");
+        let resp = Response::from(http::Response::new("denied"));
+        assert_eq!(oss_instance.error_text(resp).await.unwrap(), "denied");
+    }
+
+    #[derive(Debug)]
+    struct Denied;
+
+    impl HttpTransport for Denied {
+        fn execute(
+            &self,
+            _: reqwest::Request,
+        ) -> crate::transport::BoxFuture<'_, Result<Response, Error>> {
+            let body = "<Error><Code>AccessDenied</Code><Message>denied</Message></Error>";
+            let resp = http::Response::builder().status(403).body(body).unwrap();
+            Box::pin(async move { Ok(Response::from(resp)) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_bucket_error() {
+        let oss_instance = OSS::builder(
+            "xxx".to_string(),
+            "xxx".to_string(),
+            "https://oss-cn-hangzhou.aliyuncs.com".to_string(),
+            "xxx".to_string(),
+        )
+        .transport(Denied)
+        .build();
+        let err = oss_instance
+            .list_bucket(None::<HashMap<&str, Option<&str>>>)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Some("AccessDenied"));
+    }
+
+    #[test]
+    fn test_check_crc64() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-oss-hash-crc64ecma",
+            "11051210869376104954".parse().unwrap(),
+        );
+        let oss_instance = get_oss_instance();
+        assert!(oss_instance.check_crc64(1, &headers).is_ok());
+
+        let oss_instance = OSS::builder(
+            "xxx".to_string(),
+            "xxx".to_string(),
+            "xxx.aliyuncs.com".to_string(),
+            "xxx".to_string(),
+        )
+        .verify_crc64(true)
+        .build();
+        let crc = oss_instance.buffer_crc(b"123456789").unwrap();
+        assert!(oss_instance.check_crc64(crc, &headers).is_ok());
+        assert!(oss_instance.check_crc64(crc, &HeaderMap::new()).is_ok());
+        match oss_instance.check_crc64(1, &headers) {
+            Err(Error::ChecksumMismatch(e)) => assert_eq!(e.server(), crc),
+            r => panic!("unexpected {:?}", r),
+        }
+
+        let parts = vec![
+            Part::new(1, "\"a\"".to_owned())
+                .with_crc64(oss_instance.buffer_crc(b"1234").unwrap(), 4),
+            Part::new(2, "\"b\"".to_owned())
+                .with_crc64(oss_instance.buffer_crc(b"56789").unwrap(), 5),
+        ];
+        assert_eq!(combined_crc64(&parts), Some(crc));
+        assert_eq!(combined_crc64(&[Part::new(1, "\"a\"".to_owned())]), None);
+    }
+
     fn get_oss_instance() -> OSS {
-        let oss_instance = OSS::new(
+        OSS::new(
             "xxx".to_string(),
             "xxx".to_string(),
             "xxx.aliyuncs.com".to_string(),
             "xxx".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_parse_list_buckets() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ListAllMyBucketsResult>
+  <Owner>
+    <ID>512**</ID>
+    <DisplayName>51264</DisplayName>
+  </Owner>
+  <Buckets>
+    <Bucket>
+      <CreationDate>2014-02-17T18:12:43.000Z</CreationDate>
+      <ExtranetEndpoint>oss-cn-shanghai.aliyuncs.com</ExtranetEndpoint>
+      <IntranetEndpoint>oss-cn-shanghai-internal.aliyuncs.com</IntranetEndpoint>
+      <Location>oss-cn-shanghai</Location>
+      <Name>app-base-oss</Name>
+      <StorageClass>Standard</StorageClass>
+    </Bucket>
+  </Buckets>
+</ListAllMyBucketsResult>"#;
+        let list = parse_list_buckets(xml).unwrap();
+        assert_eq!(list.id(), "512**");
+        assert_eq!(list.buckets().len(), 1);
+        assert_eq!(list.buckets()[0].name(), "app-base-oss");
+        assert_eq!(list.buckets()[0].location(), "oss-cn-shanghai");
+
+        let err = parse_list_buckets("<Buckets><Bucket></Buckets>").unwrap_err();
+        assert!(matches!(err, Error::Qxml(_)));
+    }
+
+    #[test]
+    fn test_parse_list_objects() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ListBucketResult>
+  <Name>examplebucket</Name>
+  <Prefix>fun/</Prefix>
+  <Marker></Marker>
+  <MaxKeys>100</MaxKeys>
+  <Delimiter>/</Delimiter>
+  <IsTruncated>true</IsTruncated>
+  <NextMarker>fun/test.jpg</NextMarker>
+  <Contents>
+    <Key>fun/test.jpg</Key>
+    <LastModified>2012-02-24T08:42:32.000Z</LastModified>
+    <ETag>"5B3C1A2E053D763E1B002CC607C5A0FE"</ETag>
+    <Type>Normal</Type>
+    <Size>344606</Size>
+    <StorageClass>Standard</StorageClass>
+    <Owner>
+      <ID>0022012****</ID>
+      <DisplayName>user-example</DisplayName>
+    </Owner>
+  </Contents>
+  <CommonPrefixes>
+    <Prefix>fun/movie/</Prefix>
+  </CommonPrefixes>
+</ListBucketResult>"#;
+        let list = parse_list_objects(xml).unwrap();
+        assert_eq!(list.name(), "examplebucket");
+        assert_eq!(list.prefix(), "fun/");
+        assert!(list.is_truncated());
+        assert_eq!(list.next_marker(), "fun/test.jpg");
+        assert_eq!(list.common_prefixes(), &vec!["fun/movie/".to_owned()]);
+        assert_eq!(list.objects().len(), 1);
+        let object = &list.objects()[0];
+        assert_eq!(object.key(), "fun/test.jpg");
+        assert_eq!(object.size(), 344606);
+        assert_eq!(object.etag(), "\"5B3C1A2E053D763E1B002CC607C5A0FE\"");
+        assert_eq!(object.storage_class(), "Standard");
+        assert_eq!(object.owner().display_name(), "user-example");
+    }
+
+    #[test]
+    fn test_parse_list_objects_v2() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ListBucketResult>
+  <Name>examplebucket</Name>
+  <Prefix>a/</Prefix>
+  <ContinuationToken>CgJiYw--</ContinuationToken>
+  <MaxKeys>2</MaxKeys>
+  <Delimiter></Delimiter>
+  <EncodingType>url</EncodingType>
+  <IsTruncated>true</IsTruncated>
+  <NextContinuationToken>CgJiZA--</NextContinuationToken>
+  <KeyCount>2</KeyCount>
+  <Contents>
+    <Key>a/b</Key>
+    <LastModified>2020-05-18T05:45:43.000Z</LastModified>
+    <ETag>"35A27C2B9EAEEB6F48FD7FB5861D****"</ETag>
+    <Size>25</Size>
+    <StorageClass>STANDARD</StorageClass>
+  </Contents>
+  <Contents>
+    <Key>a/c</Key>
+    <LastModified>2020-05-18T05:45:47.000Z</LastModified>
+    <ETag>"35A27C2B9EAEEB6F48FD7FB5861D****"</ETag>
+    <Size>30</Size>
+    <StorageClass>IA</StorageClass>
+  </Contents>
+</ListBucketResult>"#;
+        let list = parse_list_objects_v2(xml).unwrap();
+        assert_eq!(list.name(), "examplebucket");
+        assert_eq!(list.continuation_token(), "CgJiYw--");
+        assert_eq!(list.next_continuation_token(), "CgJiZA--");
+        assert_eq!(list.key_count(), 2);
+        assert!(list.is_truncated());
+        let keys: Vec<_> = list.objects().iter().map(|o| o.key()).collect();
+        assert_eq!(keys, ["a/b", "a/c"]);
+        assert_eq!(list.objects()[1].size(), 30);
+        assert_eq!(list.objects()[1].owner().id(), "");
+    }
+
+    #[test]
+    fn test_query_and_resources() {
+        let oss_instance = get_oss_instance();
+        let (query, resources) = oss_instance.query_and_resources(&[
+            ("list-type", Some("2".to_owned())),
+            ("prefix", Some("a b/".to_owned())),
+            ("continuation-token", Some("CgJiYw==".to_owned())),
+            ("marker", None),
+        ]);
+        assert_eq!(
+            query,
+            "list-type=2&prefix=a%20b%2F&continuation-token=CgJiYw%3D%3D"
+        );
+        assert_eq!(resources, "continuation-token=CgJiYw==");
+    }
+
+    #[test]
+    fn test_delete_objects_body() {
+        let objects = vec![("a.txt".to_owned(), None), ("b&c.txt".to_owned(), None)];
+        assert_eq!(
+            delete_objects_body(&objects, true),
+            "<Delete><Quiet>true</Quiet><Object><Key>a.txt</Key></Object>\
+             <Object><Key>b&amp;c.txt</Key></Object></Delete>"
+        );
+        let objects = vec![("a.txt".to_owned(), Some("CAEQNRiBgI".to_owned()))];
+        assert_eq!(
+            delete_objects_body(&objects, false),
+            "<Delete><Quiet>false</Quiet><Object><Key>a.txt</Key>\
+             <VersionId>CAEQNRiBgI</VersionId></Object></Delete>"
+        );
+    }
+
+    #[test]
+    fn test_parse_delete_result() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<DeleteResult>
+  <Deleted>
+    <Key>a.txt</Key>
+  </Deleted>
+  <Deleted>
+    <Key>b.txt</Key>
+    <DeleteMarker>true</DeleteMarker>
+    <DeleteMarkerVersionId>CAEQMhiBgIDXiaaB0BYiIGQzYmRkZGUxMTM1ZDRjOTZhNjk4YjRjMTAyZjhl****</DeleteMarkerVersionId>
+  </Deleted>
+  <Error>
+    <Key>c.txt</Key>
+    <Code>AccessDenied</Code>
+    <Message>Access Denied</Message>
+  </Error>
+</DeleteResult>"#;
+        let result = parse_delete_result(xml).unwrap();
+        let keys: Vec<_> = result.deleted().iter().map(|d| d.key()).collect();
+        assert_eq!(keys, ["a.txt", "b.txt"]);
+        assert!(!result.deleted()[0].delete_marker());
+        assert!(result.deleted()[1].delete_marker());
+        assert_eq!(result.errors().len(), 1);
+        assert_eq!(result.errors()[0].key(), "c.txt");
+        assert_eq!(result.errors()[0].code(), "AccessDenied");
+        assert!(parse_delete_result("<DeleteResult></DeleteResult>")
+            .unwrap()
+            .deleted()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_parse_list_objects_url_encoded() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ListBucketResult>
+  <Name>examplebucket</Name>
+  <Prefix>a%2Fb%20c%2F</Prefix>
+  <Marker></Marker>
+  <MaxKeys>100</MaxKeys>
+  <Delimiter>%2F</Delimiter>
+  <EncodingType>url</EncodingType>
+  <IsTruncated>true</IsTruncated>
+  <NextMarker>a%2Fb%20c%2F%01ctl</NextMarker>
+  <Contents>
+    <Key>a%2Fb%20c%2F%01ctl</Key>
+    <LastModified>2012-02-24T08:42:32.000Z</LastModified>
+    <ETag>"5B3C1A2E053D763E1B002CC607C5A0FE"</ETag>
+    <Size>1</Size>
+    <StorageClass>Standard</StorageClass>
+  </Contents>
+  <CommonPrefixes>
+    <Prefix>a%2Fb%20c%2F%E4%B8%AD%2F</Prefix>
+  </CommonPrefixes>
+</ListBucketResult>"#;
+        let list = parse_list_objects(xml).unwrap();
+        assert_eq!(list.prefix(), "a/b c/");
+        assert_eq!(list.delimiter(), "/");
+        assert_eq!(list.next_marker(), "a/b c/\u{1}ctl");
+        assert_eq!(list.objects()[0].key(), "a/b c/\u{1}ctl");
+        assert_eq!(list.common_prefixes(), &vec!["a/b c/中/".to_owned()]);
+    }
+
+    #[test]
+    fn test_host_encodes_key() {
+        let oss_instance = get_oss_instance();
+        assert_eq!(
+            oss_instance.host("b", "中文/a b+c#d?.txt", "acl"),
+            "http://b.xxx.aliyuncs.com/%E4%B8%AD%E6%96%87/a%20b%2Bc%23d%3F.txt?acl"
         );
-        oss_instance
+        // The canonicalized resource carries the key as is.
+        let headers = HeaderMap::new();
+        assert!(
+            string_to_sign("GET", "b", "中文/a b+c#d?.txt", "", &headers)
+                .ends_with("\n/b/中文/a b+c#d?.txt")
+        );
+    }
+
+    #[test]
+    fn test_bucket_handle() {
+        let oss_instance = get_oss_instance();
+        let a = oss_instance.bucket_handle("a");
+        assert_eq!(a.bucket(), "a");
+        assert_eq!(oss_instance.bucket(), "xxx");
+        assert!(Arc::ptr_eq(&a, &oss_instance.bucket_handle("a")));
+        assert!(!Arc::ptr_eq(&a, &oss_instance.bucket_handle("b")));
     }
 
     #[tokio::test]
@@ -854,19 +3017,19 @@ mod tests {
                 None,
             )
             .await;
-        assert_eq!(result.is_ok(), true);
+        assert!(result.is_ok());
     }
 
     async fn get_object(oss_instance: &OSS) {
         let result = oss_instance
             .get_object("objectName", None::<HashMap<&str, &str>>, None)
             .await;
-        assert_eq!(result.is_ok(), true);
+        assert!(result.is_ok());
         println!("text = {:?}", String::from_utf8(result.unwrap().to_vec()));
     }
 
     async fn delete_object(oss_instance: &OSS) {
         let result = oss_instance.delete_object("objectName").await;
-        assert_eq!(result.is_ok(), true);
+        assert!(result.is_ok());
     }
 }
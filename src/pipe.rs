@@ -0,0 +1,48 @@
+use std::collections::HashMap;
+
+use crate::errors::Error;
+use crate::oss::OSS;
+
+// Part size of the upload side, and the most either side holds in memory.
+const PIPE_PART_SIZE: u64 = 8 * 1024 * 1024;
+
+// Streams `src_key` from `src` into `dst_key` on `dst`, for transfers across
+// accounts or regions where server-side copy is unavailable. Nothing is
+// staged to disk: one part is buffered at a time and the download is only
+// read as fast as parts are uploaded. Objects over one part become a
+// multipart upload, aborted if either side fails.
+pub async fn pipe_object<S>(src: &OSS, src_key: S, dst: &OSS, dst_key: S) -> Result<(), Error>
+where
+    S: AsRef<str>,
+{
+    let reader = src.get_object_reader(src_key.as_ref());
+    dst.upload_from_reader(
+        dst_key.as_ref(),
+        reader,
+        PIPE_PART_SIZE,
+        None::<HashMap<&str, &str>>,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_pipe_object_fails_on_source_error() {
+        let src = OSS::new(
+            "xxx".to_string(),
+            "xxx".to_string(),
+            "invalid host".to_string(),
+            "src".to_string(),
+        );
+        let dst = OSS::new(
+            "xxx".to_string(),
+            "xxx".to_string(),
+            "invalid host".to_string(),
+            "dst".to_string(),
+        );
+        assert!(pipe_object(&src, "a.bin", &dst, "a.bin").await.is_err());
+    }
+}
@@ -0,0 +1,194 @@
+use base64::encode;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{json, Value};
+use std::time::Duration;
+
+use crate::auth::{hmac_sha1_base64, SECURITY_TOKEN};
+use crate::credentials::Credentials;
+use crate::errors::Error;
+use crate::oss::OSS;
+
+// Restrictions a browser upload must satisfy, unset ones are not checked.
+#[derive(Clone, Debug, Default)]
+pub struct PostPolicyOptions {
+    // The form's `key` field has to start with this.
+    pub key_prefix: Option<String>,
+    // Inclusive bounds on the uploaded size in bytes.
+    pub content_length_range: Option<(u64, u64)>,
+    pub content_type: Option<String>,
+}
+
+// Everything an HTML form needs to upload straight to the bucket: POST to
+// `url` as multipart/form-data with `fields`, a `key` field and the `file`
+// field last.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PostPolicy {
+    url: String,
+    fields: Vec<(String, String)>,
+}
+
+impl PostPolicy {
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn fields(&self) -> &[(String, String)] {
+        &self.fields
+    }
+}
+
+impl OSS {
+    // https://help.aliyun.com/document_detail/31988.html
+    pub fn post_policy(
+        &self,
+        expires: Duration,
+        options: &PostPolicyOptions,
+    ) -> Result<PostPolicy, Error> {
+        let expires = chrono::Duration::from_std(expires)
+            .map_err(|e| Error::E(format!("invalid expires: {}", e)))?;
+        let credentials = self.fixed_credentials("post_policy")?;
+        Ok(self.post_policy_at(&credentials, self.now() + expires, options))
+    }
+
+    // Like `post_policy`, signed with the current credentials of the client's
+    // credentials provider, for clients built without a fixed key. The form
+    // stops working when those credentials expire.
+    pub async fn post_policy_with_provider(
+        &self,
+        expires: Duration,
+        options: &PostPolicyOptions,
+    ) -> Result<PostPolicy, Error> {
+        let expires = chrono::Duration::from_std(expires)
+            .map_err(|e| Error::E(format!("invalid expires: {}", e)))?;
+        let credentials = self.credentials().await?;
+        Ok(self.post_policy_at(&credentials, self.now() + expires, options))
+    }
+
+    // Signs the policy with `credentials` rather than the client's own keys.
+    pub(crate) fn post_policy_at(
+        &self,
+        credentials: &Credentials,
+        expiration: DateTime<Utc>,
+        options: &PostPolicyOptions,
+    ) -> PostPolicy {
+        let mut conditions = vec![json!({ "bucket": self.bucket() })];
+        if let Some(prefix) = &options.key_prefix {
+            conditions.push(json!(["starts-with", "$key", prefix]));
+        }
+        if let Some((min, max)) = options.content_length_range {
+            conditions.push(json!(["content-length-range", min, max]));
+        }
+        if let Some(content_type) = &options.content_type {
+            conditions.push(json!(["eq", "$Content-Type", content_type]));
+        }
+        let policy: Value = json!({
+            "expiration": expiration.to_rfc3339_opts(SecondsFormat::Millis, true),
+            "conditions": conditions,
+        });
+        let policy = encode(policy.to_string());
+        let signature = hmac_sha1_base64(credentials.key_secret(), &policy);
+
+        let mut fields = vec![
+            ("OSSAccessKeyId".to_owned(), credentials.key_id().to_owned()),
+            ("policy".to_owned(), policy),
+            ("Signature".to_owned(), signature),
+        ];
+        if let Some(content_type) = &options.content_type {
+            fields.push(("Content-Type".to_owned(), content_type.clone()));
+        }
+        if let Some(token) = credentials.security_token() {
+            fields.push((SECURITY_TOKEN.to_owned(), token.to_owned()));
+        }
+        PostPolicy {
+            url: self
+                .host(self.bucket(), "", "")
+                .trim_end_matches('?')
+                .to_owned(),
+            fields,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::credentials::StaticProvider;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_post_policy() {
+        let oss_instance = OSS::new(
+            "access-key-id".to_string(),
+            "access-key-secret".to_string(),
+            "https://oss-cn-hangzhou.aliyuncs.com".to_string(),
+            "examplebucket".to_string(),
+        );
+        let options = PostPolicyOptions {
+            key_prefix: Some("user/1/".to_owned()),
+            content_length_range: Some((1, 10485760)),
+            content_type: Some("image/png".to_owned()),
+        };
+        let expiration = Utc.with_ymd_and_hms(2023, 11, 14, 22, 13, 20).unwrap();
+        let credentials = Credentials::new(
+            "access-key-id".to_owned(),
+            "access-key-secret".to_owned(),
+            None,
+        );
+        let policy = oss_instance.post_policy_at(&credentials, expiration, &options);
+        assert_eq!(
+            policy.url(),
+            "https://examplebucket.oss-cn-hangzhou.aliyuncs.com/"
+        );
+
+        let fields = policy.fields();
+        assert_eq!(
+            fields[0],
+            ("OSSAccessKeyId".to_owned(), "access-key-id".to_owned())
+        );
+        assert_eq!(
+            fields[3],
+            ("Content-Type".to_owned(), "image/png".to_owned())
+        );
+        let document = String::from_utf8(base64::decode(&fields[1].1).unwrap()).unwrap();
+        assert_eq!(
+            document,
+            r#"{"conditions":[{"bucket":"examplebucket"},["starts-with","$key","user/1/"],["content-length-range",1,10485760],["eq","$Content-Type","image/png"]],"expiration":"2023-11-14T22:13:20.000Z"}"#
+        );
+        assert_eq!(
+            fields[2].1,
+            hmac_sha1_base64("access-key-secret", &fields[1].1)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_post_policy_with_provider() {
+        let oss_instance = OSS::builder(
+            String::new(),
+            String::new(),
+            "https://oss-cn-hangzhou.aliyuncs.com".to_string(),
+            "examplebucket".to_string(),
+        )
+        .credentials_provider(StaticProvider::new(Credentials::new(
+            "STS.id".to_owned(),
+            "secret".to_owned(),
+            Some("token".to_owned()),
+        )))
+        .build();
+        let options = PostPolicyOptions::default();
+
+        assert!(oss_instance
+            .post_policy(Duration::from_secs(600), &options)
+            .is_err());
+        let policy = oss_instance
+            .post_policy_with_provider(Duration::from_secs(600), &options)
+            .await
+            .unwrap();
+        let fields = policy.fields();
+        assert_eq!(
+            fields[0],
+            ("OSSAccessKeyId".to_owned(), "STS.id".to_owned())
+        );
+        assert_eq!(fields[2].1, hmac_sha1_base64("secret", &fields[1].1));
+        assert_eq!(fields[3], (SECURITY_TOKEN.to_owned(), "token".to_owned()));
+    }
+}
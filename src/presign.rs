@@ -0,0 +1,300 @@
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Method;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::auth::{canonicalized_oss_headers, hmac_sha1_base64};
+use crate::credentials::Credentials;
+use crate::errors::Error;
+use crate::oss::OSS;
+use crate::utils::{path_encode, to_headers, url_encode};
+
+#[derive(Clone, Debug)]
+pub struct SignUrlOptions {
+    pub method: Method,
+    // Headers the client will send with the request, e.g. Content-Type or x-oss-*.
+    pub headers: HashMap<String, String>,
+    // Query parameters such as `response-content-type` or `x-oss-process`.
+    pub params: HashMap<String, Option<String>>,
+}
+
+impl Default for SignUrlOptions {
+    fn default() -> Self {
+        SignUrlOptions {
+            method: Method::GET,
+            headers: HashMap::new(),
+            params: HashMap::new(),
+        }
+    }
+}
+
+impl OSS {
+    // https://help.aliyun.com/document_detail/31952.html
+    pub fn sign_url<S>(
+        &self,
+        object: S,
+        expires: Duration,
+        options: &SignUrlOptions,
+    ) -> Result<String, Error>
+    where
+        S: AsRef<str>,
+    {
+        let mut urls = self.sign_urls(Some(object), expires, options)?;
+        Ok(urls.remove(0))
+    }
+
+    // Presigns many objects with the same options, the URLs are returned in
+    // the order of `objects`.
+    pub fn sign_urls<I, S>(
+        &self,
+        objects: I,
+        expires: Duration,
+        options: &SignUrlOptions,
+    ) -> Result<Vec<String>, Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let credentials = self.fixed_credentials("sign_urls")?;
+        let expires = self.expires_at(expires)?;
+        self.sign_urls_at(&credentials, objects, expires, options)
+    }
+
+    // Like `sign_url`, signed with the current credentials of the client's
+    // credentials provider, for clients built without a fixed key.
+    pub async fn sign_url_with_provider<S>(
+        &self,
+        object: S,
+        expires: Duration,
+        options: &SignUrlOptions,
+    ) -> Result<String, Error>
+    where
+        S: AsRef<str>,
+    {
+        let mut urls = self
+            .sign_urls_with_provider(Some(object), expires, options)
+            .await?;
+        Ok(urls.remove(0))
+    }
+
+    pub async fn sign_urls_with_provider<I, S>(
+        &self,
+        objects: I,
+        expires: Duration,
+        options: &SignUrlOptions,
+    ) -> Result<Vec<String>, Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let credentials = self.credentials().await?;
+        let expires = self.expires_at(expires)?;
+        self.sign_urls_at(&credentials, objects, expires, options)
+    }
+
+    fn expires_at(&self, expires: Duration) -> Result<i64, Error> {
+        let expires = chrono::Duration::from_std(expires)
+            .map_err(|e| Error::E(format!("invalid expires: {}", e)))?;
+        Ok((self.now() + expires).timestamp())
+    }
+
+    fn sign_urls_at<I, S>(
+        &self,
+        credentials: &Credentials,
+        objects: I,
+        expires: i64,
+        options: &SignUrlOptions,
+    ) -> Result<Vec<String>, Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        // Everything but the object key is shared by all URLs, so the string
+        // to sign and the query are canonicalized once up front.
+        let headers = to_headers(options.headers.clone())?;
+        let header = |name| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or_default()
+        };
+        let sign_prefix = format!(
+            "{}\n{}\n{}\n{}\n{}/{}/",
+            options.method,
+            header("Content-MD5"),
+            header(CONTENT_TYPE.as_str()),
+            expires,
+            canonicalized_oss_headers(&headers),
+            self.bucket()
+        );
+
+        // With STS credentials the token travels as a signed sub-resource.
+        let mut params = options.params.clone();
+        if let Some(token) = credentials.security_token() {
+            params.insert("security-token".to_owned(), Some(token.to_owned()));
+        }
+        let resources_str = self.get_resources_str(params.clone());
+        let sign_suffix = if resources_str.is_empty() {
+            String::new()
+        } else {
+            format!("?{}", resources_str)
+        };
+
+        let mut params: Vec<(&String, &Option<String>)> = params.iter().collect();
+        params.sort();
+        let mut query = format!(
+            "OSSAccessKeyId={}&Expires={}",
+            url_encode(credentials.key_id()),
+            expires
+        );
+        for (k, v) in params {
+            query += &format!("&{}", url_encode(k));
+            if let Some(v) = v {
+                query += &format!("={}", url_encode(v));
+            }
+        }
+
+        let base = self.host(self.bucket(), "", "");
+        let base = base.trim_end_matches('?');
+
+        Ok(objects
+            .into_iter()
+            .map(|object| {
+                let object = object.as_ref();
+                let sign_str = format!("{}{}{}", sign_prefix, object, sign_suffix);
+                let signature = hmac_sha1_base64(credentials.key_secret(), &sign_str);
+                format!(
+                    "{}{}?{}&Signature={}",
+                    base,
+                    path_encode(object),
+                    query,
+                    url_encode(&signature)
+                )
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+    use crate::credentials::StaticProvider;
+    use chrono::{TimeZone, Utc};
+
+    fn get_oss_instance() -> OSS {
+        OSS::new(
+            "access-key-id".to_string(),
+            "access-key-secret".to_string(),
+            "https://oss-cn-hangzhou.aliyuncs.com".to_string(),
+            "examplebucket".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_sign_urls() {
+        let oss_instance = get_oss_instance();
+        let mut options = SignUrlOptions::default();
+        options.params.insert(
+            "response-content-type".to_owned(),
+            Some("image/png".to_owned()),
+        );
+        let credentials = oss_instance.fixed_credentials("sign_urls").unwrap();
+        let urls = oss_instance
+            .sign_urls_at(&credentials, vec!["a.png", "b.png"], 1700000000, &options)
+            .unwrap();
+        assert_eq!(urls.len(), 2);
+        assert_eq!(
+            urls[0],
+            "https://examplebucket.oss-cn-hangzhou.aliyuncs.com/a.png?OSSAccessKeyId=access-key-id\
+             &Expires=1700000000&response-content-type=image%2Fpng&Signature=jsuh2dxyyyPLJyrTNPcpxD6YXew%3D"
+        );
+        assert!(urls[1].starts_with("https://examplebucket.oss-cn-hangzhou.aliyuncs.com/b.png?"));
+    }
+
+    #[test]
+    fn test_sign_url_with_fixed_clock() {
+        let now = Utc.timestamp_opt(1699999000, 0).unwrap();
+        let oss_instance = OSS::builder(
+            "access-key-id".to_string(),
+            "access-key-secret".to_string(),
+            "https://oss-cn-hangzhou.aliyuncs.com".to_string(),
+            "examplebucket".to_string(),
+        )
+        .clock(FixedClock(now))
+        .build();
+        let mut options = SignUrlOptions::default();
+        options.params.insert(
+            "response-content-type".to_owned(),
+            Some("image/png".to_owned()),
+        );
+        let url = oss_instance
+            .sign_url("a.png", Duration::from_secs(1000), &options)
+            .unwrap();
+        assert!(url.ends_with("&Expires=1700000000&response-content-type=image%2Fpng&Signature=jsuh2dxyyyPLJyrTNPcpxD6YXew%3D"));
+        assert_eq!(oss_instance.date(), "Tue, 14 Nov 2023 21:56:40 GMT");
+    }
+
+    #[test]
+    fn test_sign_url_with_security_token() {
+        let oss_instance = OSS::new_with_sts(
+            "access-key-id".to_string(),
+            "access-key-secret".to_string(),
+            "sts-token".to_string(),
+            "https://oss-cn-hangzhou.aliyuncs.com".to_string(),
+            "examplebucket".to_string(),
+        );
+        let credentials = oss_instance.fixed_credentials("sign_urls").unwrap();
+        let urls = oss_instance
+            .sign_urls_at(
+                &credentials,
+                vec!["a.png"],
+                1700000000,
+                &SignUrlOptions::default(),
+            )
+            .unwrap();
+        let sign_str = "GET\n\n\n1700000000\n/examplebucket/a.png?security-token=sts-token";
+        assert_eq!(
+            urls[0],
+            format!(
+                "https://examplebucket.oss-cn-hangzhou.aliyuncs.com/a.png?OSSAccessKeyId=access-key-id\
+                 &Expires=1700000000&security-token=sts-token&Signature={}",
+                url_encode(&hmac_sha1_base64("access-key-secret", sign_str))
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sign_url_with_provider() {
+        let now = Utc.timestamp_opt(1699999000, 0).unwrap();
+        let oss_instance = OSS::builder(
+            String::new(),
+            String::new(),
+            "https://oss-cn-hangzhou.aliyuncs.com".to_string(),
+            "examplebucket".to_string(),
+        )
+        .credentials_provider(StaticProvider::new(Credentials::new(
+            "access-key-id".to_owned(),
+            "access-key-secret".to_owned(),
+            None,
+        )))
+        .clock(FixedClock(now))
+        .build();
+        let mut options = SignUrlOptions::default();
+        options.params.insert(
+            "response-content-type".to_owned(),
+            Some("image/png".to_owned()),
+        );
+
+        let err = oss_instance
+            .sign_url("a.png", Duration::from_secs(1000), &options)
+            .unwrap_err();
+        assert!(err.to_string().contains("sign_urls_with_provider"));
+        let url = oss_instance
+            .sign_url_with_provider("a.png", Duration::from_secs(1000), &options)
+            .await
+            .unwrap();
+        assert!(url.contains("?OSSAccessKeyId=access-key-id&"));
+        assert!(url.ends_with("&Expires=1700000000&response-content-type=image%2Fpng&Signature=jsuh2dxyyyPLJyrTNPcpxD6YXew%3D"));
+    }
+}
@@ -0,0 +1,249 @@
+use serde_json::Value;
+use std::path::PathBuf;
+
+use crate::errors::Error;
+use crate::oss::OSS;
+
+// Credentials and endpoint read from an ossutil or aliyun CLI config file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Profile {
+    key_id: String,
+    key_secret: String,
+    security_token: Option<String>,
+    endpoint: Option<String>,
+}
+
+impl Profile {
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    pub fn key_secret(&self) -> &str {
+        &self.key_secret
+    }
+
+    pub fn security_token(&self) -> Option<&str> {
+        self.security_token.as_deref()
+    }
+
+    pub fn endpoint(&self) -> Option<&str> {
+        self.endpoint.as_deref()
+    }
+
+    // Parses the `[Credentials]` section of an ossutil config, ~/.ossutilconfig
+    // by default.
+    pub fn from_ossutil(config: &str) -> Result<Self, Error> {
+        let mut profile = Profile::default();
+        let mut in_credentials = false;
+        for line in config.lines().map(str::trim) {
+            if line.starts_with('[') {
+                in_credentials = line == "[Credentials]";
+                continue;
+            }
+            let (k, v) = match line.split_once('=') {
+                Some((k, v)) if in_credentials => (k.trim(), v.trim().to_owned()),
+                _ => continue,
+            };
+            match k {
+                "accessKeyID" => profile.key_id = v,
+                "accessKeySecret" => profile.key_secret = v,
+                "stsToken" if !v.is_empty() => profile.security_token = Some(v),
+                "endpoint" if !v.is_empty() => profile.endpoint = Some(v),
+                _ => (),
+            }
+        }
+        profile.check("ossutil config")
+    }
+
+    // Parses an aliyun CLI config, ~/.aliyun/config.json by default, using
+    // profile `name` or the current one. The endpoint is derived from the
+    // profile's region.
+    pub fn from_aliyun_cli(config: &str, name: Option<&str>) -> Result<Self, Error> {
+        let config: Value = serde_json::from_str(config)
+            .map_err(|e| Error::E(format!("invalid aliyun CLI config: {}", e)))?;
+        let name = name
+            .or_else(|| config["current"].as_str())
+            .unwrap_or("default");
+        let p = config["profiles"]
+            .as_array()
+            .and_then(|profiles| profiles.iter().find(|p| p["name"] == name))
+            .ok_or_else(|| Error::E(format!("aliyun CLI profile {} not found", name)))?;
+        let field = |k: &str| {
+            p[k].as_str()
+                .filter(|v| !v.is_empty())
+                .map(|v| v.to_owned())
+        };
+        Profile {
+            key_id: field("access_key_id").unwrap_or_default(),
+            key_secret: field("access_key_secret").unwrap_or_default(),
+            security_token: field("sts_token"),
+            endpoint: field("region_id").map(|r| format!("oss-{}.aliyuncs.com", r)),
+        }
+        .check("aliyun CLI config")
+    }
+
+    // Tries ~/.ossutilconfig, then the current profile of
+    // ~/.aliyun/config.json.
+    pub fn load() -> Result<Self, Error> {
+        let home = home_dir().ok_or_else(|| Error::E("home directory not found".to_owned()))?;
+        if let Ok(config) = std::fs::read_to_string(home.join(".ossutilconfig")) {
+            return Profile::from_ossutil(&config);
+        }
+        match std::fs::read_to_string(home.join(".aliyun").join("config.json")) {
+            Ok(config) => Profile::from_aliyun_cli(&config, None),
+            Err(_) => Err(Error::E(
+                "no ~/.ossutilconfig or ~/.aliyun/config.json found".to_owned(),
+            )),
+        }
+    }
+
+    fn check(self, source: &str) -> Result<Self, Error> {
+        if self.key_id.is_empty() || self.key_secret.is_empty() {
+            return Err(Error::E(format!("{} has no access key", source)));
+        }
+        Ok(self)
+    }
+}
+
+impl OSS {
+    // Builds a client from OSS_ACCESS_KEY_ID, OSS_ACCESS_KEY_SECRET,
+    // OSS_SESSION_TOKEN, OSS_ENDPOINT and OSS_BUCKET. Without the key
+    // variables the credentials, and the endpoint unless set, come from
+    // `Profile::load`.
+    pub fn from_env() -> Result<Self, Error> {
+        from_vars(
+            |k| std::env::var(k).ok().filter(|v| !v.is_empty()),
+            Profile::load,
+        )
+    }
+
+    pub fn from_profile(profile: &Profile, bucket: String) -> Result<Self, Error> {
+        let endpoint = profile
+            .endpoint()
+            .ok_or_else(|| Error::E("profile has no endpoint".to_owned()))?;
+        build(
+            profile.key_id.clone(),
+            profile.key_secret.clone(),
+            profile.security_token.clone(),
+            endpoint.to_owned(),
+            bucket,
+        )
+    }
+}
+
+fn from_vars<V, P>(var: V, load: P) -> Result<OSS, Error>
+where
+    V: Fn(&str) -> Option<String>,
+    P: FnOnce() -> Result<Profile, Error>,
+{
+    let bucket = var("OSS_BUCKET").ok_or_else(|| Error::E("OSS_BUCKET is not set".to_owned()))?;
+    let profile = match (var("OSS_ACCESS_KEY_ID"), var("OSS_ACCESS_KEY_SECRET")) {
+        (Some(key_id), Some(key_secret)) => Profile {
+            key_id,
+            key_secret,
+            security_token: var("OSS_SESSION_TOKEN"),
+            endpoint: None,
+        },
+        _ => load()?,
+    };
+    let endpoint = var("OSS_ENDPOINT")
+        .or_else(|| profile.endpoint.clone())
+        .ok_or_else(|| Error::E("OSS_ENDPOINT is not set".to_owned()))?;
+    build(
+        profile.key_id,
+        profile.key_secret,
+        profile.security_token,
+        endpoint,
+        bucket,
+    )
+}
+
+fn build(
+    key_id: String,
+    key_secret: String,
+    security_token: Option<String>,
+    endpoint: String,
+    bucket: String,
+) -> Result<OSS, Error> {
+    let builder = OSS::builder(key_id, key_secret, endpoint, bucket);
+    match security_token {
+        Some(token) => builder.security_token(token).try_build(),
+        None => builder.try_build(),
+    }
+}
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_from_ossutil() {
+        let config = "[Credentials]\nlanguage=EN\nendpoint=oss-cn-hangzhou.aliyuncs.com\n\
+                      accessKeyID=LTAI4GB6fwDbFvCBM5LkmQ1D\naccessKeySecret=secret\n\n\
+                      [Bucket-Endpoint]\nendpoint=ignored\n";
+        let profile = Profile::from_ossutil(config).unwrap();
+        assert_eq!(profile.key_id(), "LTAI4GB6fwDbFvCBM5LkmQ1D");
+        assert_eq!(profile.key_secret(), "secret");
+        assert_eq!(profile.security_token(), None);
+        assert_eq!(profile.endpoint(), Some("oss-cn-hangzhou.aliyuncs.com"));
+        assert!(Profile::from_ossutil("[Credentials]\nlanguage=EN\n").is_err());
+    }
+
+    #[test]
+    fn test_from_aliyun_cli() {
+        let config = r#"{
+  "current": "prod",
+  "profiles": [
+    {"name": "default", "mode": "AK", "access_key_id": "id1", "access_key_secret": "s1", "region_id": "cn-hangzhou"},
+    {"name": "prod", "mode": "StsToken", "access_key_id": "id2", "access_key_secret": "s2", "sts_token": "t2", "region_id": "cn-beijing"}
+  ]
+}"#;
+        let profile = Profile::from_aliyun_cli(config, None).unwrap();
+        assert_eq!(profile.key_id(), "id2");
+        assert_eq!(profile.security_token(), Some("t2"));
+        assert_eq!(profile.endpoint(), Some("oss-cn-beijing.aliyuncs.com"));
+        let profile = Profile::from_aliyun_cli(config, Some("default")).unwrap();
+        assert_eq!(profile.key_id(), "id1");
+        assert!(Profile::from_aliyun_cli(config, Some("missing")).is_err());
+    }
+
+    #[test]
+    fn test_from_vars() {
+        let vars: HashMap<&str, &str> = [
+            ("OSS_ACCESS_KEY_ID", "id"),
+            ("OSS_ACCESS_KEY_SECRET", "secret"),
+            ("OSS_ENDPOINT", "oss-cn-hangzhou.aliyuncs.com"),
+            ("OSS_BUCKET", "examplebucket"),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+        let var = |k: &str| vars.get(k).map(|v| v.to_string());
+        let oss_instance = from_vars(var, || panic!("profile must not be loaded")).unwrap();
+        assert_eq!(oss_instance.key_id(), "id");
+        assert_eq!(oss_instance.bucket(), "examplebucket");
+        assert_eq!(oss_instance.security_token(), None);
+
+        // Credentials and endpoint fall back to the profile.
+        let var = |k: &str| (k == "OSS_BUCKET").then(|| "examplebucket".to_owned());
+        let profile = Profile {
+            key_id: "profile-id".to_owned(),
+            key_secret: "profile-secret".to_owned(),
+            security_token: Some("token".to_owned()),
+            endpoint: Some("oss-cn-beijing.aliyuncs.com".to_owned()),
+        };
+        let oss_instance = from_vars(var, || Ok(profile)).unwrap();
+        assert_eq!(oss_instance.key_id(), "profile-id");
+        assert_eq!(oss_instance.security_token(), Some("token"));
+        assert_eq!(oss_instance.endpoint(), "oss-cn-beijing.aliyuncs.com");
+
+        assert!(from_vars(|_| None, Profile::load).is_err());
+    }
+}
@@ -0,0 +1,92 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use crate::oss::OSS;
+
+// Told how far file uploads and downloads got, e.g. to draw progress bars.
+// Set with `OSSBuilder::progress`. Called from the tasks moving the bytes,
+// from several at once for parallel transfers, so keep it cheap.
+pub trait TransferProgress: fmt::Debug + Send + Sync {
+    // `transferred` of the `total` bytes of `object` were sent or received.
+    fn on_progress(&self, object: &str, transferred: u64, total: u64);
+}
+
+// Byte count of one transfer, shared by the parts moving it.
+#[derive(Debug)]
+pub(crate) struct Progress {
+    hook: Arc<dyn TransferProgress>,
+    object: String,
+    transferred: AtomicU64,
+    total: u64,
+}
+
+impl Progress {
+    pub(crate) fn advance(&self, n: u64) {
+        let transferred = self.transferred.fetch_add(n, Ordering::Relaxed) + n;
+        self.hook.on_progress(&self.object, transferred, self.total);
+    }
+}
+
+impl OSS {
+    // A counter for a transfer of `total` bytes of `object`, when a progress
+    // hook is set.
+    pub(crate) fn progress(&self, object: &str, total: u64) -> Option<Arc<Progress>> {
+        let hook = self.progress_hook()?;
+        Some(Arc::new(Progress {
+            hook,
+            object: object.to_owned(),
+            transferred: AtomicU64::new(0),
+            total,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Debug, Default)]
+    struct Recorder(Arc<Mutex<Vec<(String, u64, u64)>>>);
+
+    impl TransferProgress for Recorder {
+        fn on_progress(&self, object: &str, transferred: u64, total: u64) {
+            self.0
+                .lock()
+                .unwrap()
+                .push((object.to_owned(), transferred, total));
+        }
+    }
+
+    #[test]
+    fn test_progress() {
+        let oss_instance = OSS::new(
+            "xxx".to_string(),
+            "xxx".to_string(),
+            "xxx.aliyuncs.com".to_string(),
+            "xxx".to_string(),
+        );
+        assert!(oss_instance.progress("big.bin", 10).is_none());
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let oss_instance = OSS::builder(
+            "xxx".to_string(),
+            "xxx".to_string(),
+            "xxx.aliyuncs.com".to_string(),
+            "xxx".to_string(),
+        )
+        .progress(Recorder(events.clone()))
+        .build();
+        let progress = oss_instance.progress("big.bin", 10).unwrap();
+        progress.advance(4);
+        progress.advance(6);
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                ("big.bin".to_owned(), 4, 10),
+                ("big.bin".to_owned(), 10, 10)
+            ]
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use reqwest::header::HeaderMap;
+use reqwest::{Response, StatusCode};
+use std::cell::RefCell;
+use std::future::Future;
+
+use crate::oss::OSS;
+
+tokio::task_local! {
+    static RAW_RESPONSE: RefCell<Option<RawResponse>>;
+    static REQUEST_ID: RefCell<Option<String>>;
+}
+
+// Status, headers and extensions of a response as received, for anything the
+// typed results don't model yet.
+#[derive(Debug)]
+pub struct RawResponse {
+    status: StatusCode,
+    headers: HeaderMap,
+    extensions: http::Extensions,
+}
+
+impl RawResponse {
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    pub fn extensions(&self) -> &http::Extensions {
+        &self.extensions
+    }
+}
+
+impl OSS {
+    // Runs `f`, typically a single call on this client, and also returns the
+    // raw form of the last response it received. Requests made from spawned
+    // tasks are not captured.
+    pub async fn with_raw_response<T, F>(&self, f: F) -> (T, Option<RawResponse>)
+    where
+        F: Future<Output = T>,
+    {
+        RAW_RESPONSE
+            .scope(RefCell::new(None), async {
+                let value = f.await;
+                (value, RAW_RESPONSE.with(|raw| raw.borrow_mut().take()))
+            })
+            .await
+    }
+}
+
+// Runs `f` and also returns the x-oss-request-id of the last response it
+// received, for the context of its errors.
+pub(crate) async fn with_request_id<T, F>(f: F) -> (T, Option<String>)
+where
+    F: Future<Output = T>,
+{
+    REQUEST_ID
+        .scope(RefCell::new(None), async {
+            let value = f.await;
+            (value, REQUEST_ID.with(|id| id.borrow_mut().take()))
+        })
+        .await
+}
+
+// The x-oss-request-id header in `headers`.
+pub(crate) fn request_id(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get("x-oss-request-id")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_owned())
+}
+
+// Keeps the raw parts of `resp` when inside `with_raw_response`, and its
+// request id inside `with_request_id`. The extensions are moved out, as they
+// can't be cloned.
+#[cfg_attr(target_arch = "wasm32", allow(unused_mut))]
+pub(crate) fn record(mut resp: Response) -> Response {
+    let _ = REQUEST_ID.try_with(|id| {
+        if let Some(request_id) = request_id(resp.headers()) {
+            *id.borrow_mut() = Some(request_id);
+        }
+    });
+    let _ = RAW_RESPONSE.try_with(|raw| {
+        *raw.borrow_mut() = Some(RawResponse {
+            status: resp.status(),
+            headers: resp.headers().clone(),
+            #[cfg(not(target_arch = "wasm32"))]
+            extensions: std::mem::take(resp.extensions_mut()),
+            // Responses from fetch carry none.
+            #[cfg(target_arch = "wasm32")]
+            extensions: http::Extensions::new(),
+        })
+    });
+    resp
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(status: u16) -> Response {
+        let mut resp = http::Response::builder()
+            .status(status)
+            .header("x-oss-request-id", "5C06A3B67B8B5A3DA422299D")
+            .body("")
+            .unwrap();
+        resp.extensions_mut().insert(42u32);
+        Response::from(resp)
+    }
+
+    #[tokio::test]
+    async fn test_with_raw_response() {
+        let oss = OSS::new(String::new(), String::new(), String::new(), String::new());
+        let (value, raw) = oss
+            .with_raw_response(async {
+                record(response(200));
+                record(response(404)).status().as_u16()
+            })
+            .await;
+        assert_eq!(value, 404);
+        let raw = raw.unwrap();
+        assert_eq!(raw.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            raw.headers()["x-oss-request-id"],
+            "5C06A3B67B8B5A3DA422299D"
+        );
+        assert_eq!(raw.extensions().get::<u32>(), Some(&42));
+
+        let (_, id) = with_request_id(async { record(response(503)) }).await;
+        assert_eq!(id.as_deref(), Some("5C06A3B67B8B5A3DA422299D"));
+
+        // Outside of `with_raw_response` nothing is kept.
+        record(response(200));
+        let ((), raw) = oss.with_raw_response(async {}).await;
+        assert!(raw.is_none());
+    }
+}
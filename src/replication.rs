@@ -0,0 +1,332 @@
+use chrono::{DateTime, Utc};
+use quick_xml::{events::Event, Reader};
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+use reqwest::Method;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::errors::Error;
+use crate::oss::OSS;
+use crate::utils::xml_escape;
+
+// Replication time control, replicates most objects within seconds and all
+// within 10 minutes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RtcStatus {
+    Enabled,
+    Disabled,
+    // Transitional state right after enabling.
+    Enabling,
+}
+
+impl FromStr for RtcStatus {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "enabled" => Ok(RtcStatus::Enabled),
+            "disabled" => Ok(RtcStatus::Disabled),
+            "enabling" => Ok(RtcStatus::Enabling),
+            _ => Err(Error::E(format!("unknown RTC status: {}", s))),
+        }
+    }
+}
+
+impl fmt::Display for RtcStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            RtcStatus::Enabled => "enabled",
+            RtcStatus::Disabled => "disabled",
+            RtcStatus::Enabling => "enabling",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReplicationRule {
+    id: String,
+    target_bucket: String,
+    target_location: String,
+    status: String,
+    rtc: Option<RtcStatus>,
+}
+
+impl ReplicationRule {
+    pub fn new(
+        id: String,
+        target_bucket: String,
+        target_location: String,
+        status: String,
+        rtc: Option<RtcStatus>,
+    ) -> Self {
+        ReplicationRule {
+            id,
+            target_bucket,
+            target_location,
+            status,
+            rtc,
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn target_bucket(&self) -> &str {
+        &self.target_bucket
+    }
+
+    pub fn target_location(&self) -> &str {
+        &self.target_location
+    }
+
+    // starting, doing or closing.
+    pub fn status(&self) -> &str {
+        &self.status
+    }
+
+    // None when the rule was created without RTC.
+    pub fn rtc(&self) -> Option<RtcStatus> {
+        self.rtc
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReplicationProgress {
+    rule: ReplicationRule,
+    historical_object: Option<f64>,
+    new_object: Option<DateTime<Utc>>,
+}
+
+impl ReplicationProgress {
+    pub fn new(
+        rule: ReplicationRule,
+        historical_object: Option<f64>,
+        new_object: Option<DateTime<Utc>>,
+    ) -> Self {
+        ReplicationProgress {
+            rule,
+            historical_object,
+            new_object,
+        }
+    }
+
+    pub fn rule(&self) -> &ReplicationRule {
+        &self.rule
+    }
+
+    // Fraction of historical objects replicated, between 0 and 1. None when
+    // historical replication is disabled.
+    pub fn historical_object(&self) -> Option<f64> {
+        self.historical_object
+    }
+
+    // Objects written before this time have been replicated.
+    pub fn new_object(&self) -> Option<DateTime<Utc>> {
+        self.new_object
+    }
+
+    // How far replication of new objects trails `now`, for lag monitoring.
+    pub fn lag(&self, now: DateTime<Utc>) -> Option<chrono::Duration> {
+        self.new_object.map(|t| now - t)
+    }
+}
+
+impl OSS {
+    // https://help.aliyun.com/document_detail/181408.html
+    pub async fn get_bucket_replication(&self) -> Result<Vec<ReplicationRule>, Error> {
+        self.with_context("get_bucket_replication", "", async {
+            let params = [("replication", Some(String::new()))];
+            let resp = self.signed_get("", &params, HeaderMap::new()).await?;
+            let rules = parse_rules(&self.xml_text(resp).await?)?;
+            Ok(rules.into_iter().map(|p| p.rule).collect())
+        })
+        .await
+    }
+
+    // https://help.aliyun.com/document_detail/181410.html
+    pub async fn get_bucket_replication_progress<S>(
+        &self,
+        rule_id: S,
+    ) -> Result<ReplicationProgress, Error>
+    where
+        S: AsRef<str>,
+    {
+        self.with_context("get_bucket_replication_progress", "", async {
+            let params = [
+                ("replicationProgress", Some(String::new())),
+                ("rule-id", Some(rule_id.as_ref().to_owned())),
+            ];
+            let resp = self.signed_get("", &params, HeaderMap::new()).await?;
+            parse_rules(&self.xml_text(resp).await?)?
+                .into_iter()
+                .next()
+                .ok_or_else(|| Error::E("replication progress has no rule".to_owned()))
+        })
+        .await
+    }
+
+    // Enables or disables RTC on an existing replication rule.
+    // https://help.aliyun.com/document_detail/383743.html
+    pub async fn put_bucket_rtc<S>(&self, rule_id: S, enabled: bool) -> Result<(), Error>
+    where
+        S: AsRef<str>,
+    {
+        self.with_context("put_bucket_rtc", "", async {
+            let status = if enabled {
+                RtcStatus::Enabled
+            } else {
+                RtcStatus::Disabled
+            };
+            let body = format!(
+                "<ReplicationRule><RTC><Status>{}</Status></RTC><ID>{}</ID></ReplicationRule>",
+                status,
+                xml_escape(rule_id.as_ref())
+            );
+            let mut headers = HeaderMap::new();
+            headers.insert(CONTENT_TYPE, "application/xml".parse()?);
+            let params = [("rtc", Some(String::new()))];
+            let resp = self
+                .signed_request(Method::PUT, "", &params, headers, body.into_bytes())
+                .await?;
+            if resp.status().is_success() {
+                Ok(())
+            } else {
+                Err(self
+                    .api_error(resp, |reason| {
+                        Error::E(format!("can not put bucket rtc, reason: {:?}", reason))
+                    })
+                    .await)
+            }
+        })
+        .await
+    }
+}
+
+// Parses the rules of a ReplicationConfiguration or ReplicationProgress
+// document, the progress is only set for the latter.
+fn parse_rules(xml_str: &str) -> Result<Vec<ReplicationProgress>, Error> {
+    let mut reader = Reader::from_str(xml_str);
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+
+    let mut rules = Vec::new();
+    let mut rule = ReplicationRule::default();
+    let mut historical_object = None;
+    let mut new_object = None;
+    let mut in_rtc = false;
+
+    loop {
+        match reader.read_event(&mut buf) {
+            Ok(Event::Start(ref e)) => match e.name() {
+                b"Rule" => {
+                    rule = ReplicationRule::default();
+                    historical_object = None;
+                    new_object = None;
+                }
+                b"RTC" => in_rtc = true,
+                b"Status" if in_rtc => {
+                    rule.rtc = Some(reader.read_text(e.name(), &mut Vec::new())?.parse()?)
+                }
+                b"Status" => rule.status = reader.read_text(e.name(), &mut Vec::new())?,
+                b"ID" => rule.id = reader.read_text(e.name(), &mut Vec::new())?,
+                b"Bucket" => rule.target_bucket = reader.read_text(e.name(), &mut Vec::new())?,
+                b"Location" => {
+                    rule.target_location = reader.read_text(e.name(), &mut Vec::new())?
+                }
+                b"HistoricalObject" => {
+                    historical_object = reader.read_text(e.name(), &mut Vec::new())?.parse().ok()
+                }
+                b"NewObject" => {
+                    let t = reader.read_text(e.name(), &mut Vec::new())?;
+                    let t = DateTime::parse_from_rfc3339(&t)
+                        .map_err(|e| Error::E(format!("invalid NewObject {:?}: {}", t, e)))?;
+                    new_object = Some(t.with_timezone(&Utc))
+                }
+                _ => (),
+            },
+            Ok(Event::End(ref e)) => match e.name() {
+                b"RTC" => in_rtc = false,
+                b"Rule" => rules.push(ReplicationProgress::new(
+                    rule.clone(),
+                    historical_object,
+                    new_object,
+                )),
+                _ => (),
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(e.into()),
+            _ => (),
+        }
+        buf.clear();
+    }
+    Ok(rules)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_parse_replication_configuration() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ReplicationConfiguration>
+  <Rule>
+    <ID>test_replication_1</ID>
+    <PrefixSet>
+      <Prefix>source_image</Prefix>
+    </PrefixSet>
+    <Action>PUT</Action>
+    <Destination>
+      <Bucket>destbucket</Bucket>
+      <Location>oss-cn-beijing</Location>
+      <TransferType>oss_acc</TransferType>
+    </Destination>
+    <Status>doing</Status>
+    <HistoricalObjectReplication>enabled</HistoricalObjectReplication>
+    <RTC>
+      <Status>enabling</Status>
+    </RTC>
+  </Rule>
+</ReplicationConfiguration>"#;
+        let rules = parse_rules(xml).unwrap();
+        assert_eq!(rules.len(), 1);
+        let rule = rules[0].rule();
+        assert_eq!(rule.id(), "test_replication_1");
+        assert_eq!(rule.target_bucket(), "destbucket");
+        assert_eq!(rule.target_location(), "oss-cn-beijing");
+        assert_eq!(rule.status(), "doing");
+        assert_eq!(rule.rtc(), Some(RtcStatus::Enabling));
+        assert_eq!(rules[0].historical_object(), None);
+        assert_eq!(rules[0].new_object(), None);
+    }
+
+    #[test]
+    fn test_parse_replication_progress() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ReplicationProgress>
+  <Rule>
+    <ID>test_replication_1</ID>
+    <Destination>
+      <Bucket>destbucket</Bucket>
+      <Location>oss-cn-beijing</Location>
+    </Destination>
+    <Status>doing</Status>
+    <Progress>
+      <HistoricalObject>0.85</HistoricalObject>
+      <NewObject>2015-09-24T15:28:14.000Z</NewObject>
+    </Progress>
+  </Rule>
+</ReplicationProgress>"#;
+        let rules = parse_rules(xml).unwrap();
+        let progress = &rules[0];
+        assert_eq!(progress.rule().rtc(), None);
+        assert_eq!(progress.historical_object(), Some(0.85));
+        let new_object = Utc.with_ymd_and_hms(2015, 9, 24, 15, 28, 14).unwrap();
+        assert_eq!(progress.new_object(), Some(new_object));
+        let now = new_object + chrono::Duration::seconds(30);
+        assert_eq!(progress.lag(now), Some(chrono::Duration::seconds(30)));
+    }
+}
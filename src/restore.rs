@@ -0,0 +1,160 @@
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+use reqwest::{Method, StatusCode};
+
+use crate::errors::{Error, ObjectError};
+use crate::oss::OSS;
+
+// How fast a Cold Archive object is restored, Archive objects take no tier.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tier {
+    // Within an hour.
+    Expedited,
+    // Within 2 to 5 hours.
+    Standard,
+    // Within 5 to 12 hours.
+    Bulk,
+}
+
+impl Tier {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Tier::Expedited => "Expedited",
+            Tier::Standard => "Standard",
+            Tier::Bulk => "Bulk",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RestoreRequest {
+    // How long the restored copy stays readable.
+    pub days: u32,
+    pub job_parameters: Option<Tier>,
+}
+
+impl RestoreRequest {
+    pub fn to_xml(&self) -> String {
+        let mut xml = format!("<RestoreRequest><Days>{}</Days>", self.days);
+        if let Some(tier) = self.job_parameters {
+            xml += &format!(
+                "<JobParameters><Tier>{}</Tier></JobParameters>",
+                tier.as_str()
+            );
+        }
+        xml += "</RestoreRequest>";
+        xml
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RestoreStatus {
+    // 202, this call started the restore.
+    Initiated,
+    // 200, the object has already been restored.
+    Restored,
+    // A restore started earlier has not finished yet.
+    InProgress,
+}
+
+impl OSS {
+    // https://help.aliyun.com/document_detail/52930.html
+    pub async fn restore_object<S>(
+        &self,
+        object: S,
+        days: u32,
+        tier: Option<Tier>,
+    ) -> Result<RestoreStatus, Error>
+    where
+        S: AsRef<str>,
+    {
+        let object = object.as_ref();
+        self.with_context("restore_object", object, async {
+            let request = RestoreRequest {
+                days,
+                job_parameters: tier,
+            };
+            let mut headers = HeaderMap::new();
+            headers.insert(CONTENT_TYPE, "application/xml".parse()?);
+            let params = [("restore", Some(String::new()))];
+            let resp = self
+                .signed_request(
+                    Method::POST,
+                    object,
+                    &params,
+                    headers,
+                    request.to_xml().into_bytes(),
+                )
+                .await?;
+            let status = resp.status();
+            let error = self
+                .api_error(resp, |reason| {
+                    Error::Object(ObjectError::PutError {
+                        msg: format!("can not restore object, reason: {:?}", reason),
+                    })
+                })
+                .await;
+            restore_status(status, error)
+        })
+        .await
+    }
+}
+
+fn restore_status(status: StatusCode, error: Error) -> Result<RestoreStatus, Error> {
+    match status {
+        StatusCode::ACCEPTED => Ok(RestoreStatus::Initiated),
+        StatusCode::OK => Ok(RestoreStatus::Restored),
+        StatusCode::CONFLICT if error.code() == Some("RestoreAlreadyInProgress") => {
+            Ok(RestoreStatus::InProgress)
+        }
+        _ => Err(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::OssApiError;
+
+    #[test]
+    fn test_restore_request_xml() {
+        let request = RestoreRequest {
+            days: 2,
+            job_parameters: Some(Tier::Bulk),
+        };
+        assert_eq!(
+            request.to_xml(),
+            "<RestoreRequest><Days>2</Days><JobParameters><Tier>Bulk</Tier></JobParameters></RestoreRequest>"
+        );
+        let request = RestoreRequest {
+            days: 1,
+            job_parameters: None,
+        };
+        assert_eq!(
+            request.to_xml(),
+            "<RestoreRequest><Days>1</Days></RestoreRequest>"
+        );
+    }
+
+    #[test]
+    fn test_restore_status() {
+        let error = |code: &str| {
+            let body = format!("<Error><Code>{}</Code></Error>", code);
+            Error::Api(Box::new(
+                OssApiError::parse(StatusCode::CONFLICT, &body).unwrap(),
+            ))
+        };
+        assert_eq!(
+            restore_status(StatusCode::ACCEPTED, error("")).unwrap(),
+            RestoreStatus::Initiated
+        );
+        assert_eq!(
+            restore_status(StatusCode::OK, error("")).unwrap(),
+            RestoreStatus::Restored
+        );
+        assert_eq!(
+            restore_status(StatusCode::CONFLICT, error("RestoreAlreadyInProgress")).unwrap(),
+            RestoreStatus::InProgress
+        );
+        assert!(restore_status(StatusCode::BAD_REQUEST, error("OperationNotSupported")).is_err());
+    }
+}
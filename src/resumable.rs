@@ -0,0 +1,187 @@
+use serde_derive::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use crate::errors::Error;
+use crate::oss::{Part, OSS};
+use crate::rt::{self, AsyncReadExt, AsyncWriteExt};
+use crate::utils::split_file_by_part_size;
+
+// Progress of a resumable upload, saved after every part. Only valid for the
+// same object, file contents (by size and mtime) and part size.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+struct Checkpoint {
+    bucket: String,
+    object: String,
+    upload_id: String,
+    file_size: u64,
+    file_modified: u64,
+    part_size: u64,
+    parts: Vec<Part>,
+}
+
+impl Checkpoint {
+    fn matches(&self, other: &Checkpoint) -> bool {
+        self.bucket == other.bucket
+            && self.object == other.object
+            && self.file_size == other.file_size
+            && self.file_modified == other.file_modified
+            && self.part_size == other.part_size
+    }
+}
+
+impl OSS {
+    // Like `chunk_upload_by_size`, but survives failures: the upload id and
+    // every uploaded part are saved to `checkpoint` as they complete, and
+    // calling this again with the same arguments uploads only the missing
+    // parts. The checkpoint is ignored if the file changed, and removed once
+    // the object is complete or the server no longer knows the upload.
+    pub async fn resumable_upload<S, P>(
+        &self,
+        object_name: S,
+        file: S,
+        part_size: u64,
+        checkpoint: P,
+    ) -> Result<(), Error>
+    where
+        S: AsRef<str>,
+        P: AsRef<Path>,
+    {
+        let object_name = object_name.as_ref();
+        let path = file.as_ref();
+        let checkpoint = checkpoint.as_ref();
+        self.with_context("resumable_upload", object_name, async {
+            let file = rt::open(path).await?;
+            let metadata = file.metadata().await?;
+            let modified = metadata
+                .modified()?
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default();
+            let chunks = split_file_by_part_size(&file, part_size).await?;
+            if chunks.is_empty() {
+                return Err(Error::E("chunks is empty".to_owned()));
+            }
+
+            let fresh = Checkpoint {
+                bucket: self.bucket().to_owned(),
+                object: object_name.to_owned(),
+                upload_id: String::new(),
+                file_size: metadata.len(),
+                file_modified: modified,
+                part_size,
+                parts: Vec::new(),
+            };
+            let mut state = match load_checkpoint(checkpoint).await {
+                Some(saved) if saved.matches(&fresh) => saved,
+                _ => {
+                    let upload_id = self
+                        .initiate_multipart_upload(object_name, None::<HashMap<&str, &str>>)
+                        .await?;
+                    let state = Checkpoint { upload_id, ..fresh };
+                    save_checkpoint(checkpoint, &state).await?;
+                    state
+                }
+            };
+
+            let progress = self.progress(object_name, metadata.len());
+            for chunk in chunks {
+                if state.parts.iter().any(|p| p.part_number() == chunk.number) {
+                    if let Some(progress) = &progress {
+                        progress.advance(chunk.size);
+                    }
+                    continue;
+                }
+                let part = self
+                    .upload_part_from_file(
+                        path,
+                        object_name,
+                        chunk,
+                        state.upload_id.clone(),
+                        None::<HashMap<&str, &str>>,
+                        progress.clone(),
+                    )
+                    .await;
+                let part = match part {
+                    Ok(part) => part,
+                    Err(e) => {
+                        // The upload expired or was aborted, start over next time.
+                        if e.code() == Some("NoSuchUpload") {
+                            let _ = rt::remove_file(checkpoint).await;
+                        }
+                        return Err(e);
+                    }
+                };
+                state.parts.push(part);
+                save_checkpoint(checkpoint, &state).await?;
+            }
+
+            let mut parts = state.parts.clone();
+            parts.sort_by_key(|p| p.part_number());
+            self.complete_multipart_upload_idempotent(object_name, state.upload_id, parts, path)
+                .await?;
+            let _ = rt::remove_file(checkpoint).await;
+            Ok(())
+        })
+        .await
+    }
+}
+
+// A missing or unreadable checkpoint just means starting over.
+async fn load_checkpoint(path: &Path) -> Option<Checkpoint> {
+    let mut file = rt::open(path).await.ok()?;
+    let mut json = String::new();
+    file.read_to_string(&mut json).await.ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+// Written next to `path` and renamed over it, so a crash mid-write can not
+// leave a torn checkpoint behind.
+async fn save_checkpoint(path: &Path, checkpoint: &Checkpoint) -> Result<(), Error> {
+    let json = serde_json::to_vec(checkpoint).map_err(|e| Error::E(e.to_string()))?;
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let mut file = rt::create(&tmp).await?;
+    file.write_all(&json).await?;
+    file.sync_all().await?;
+    rt::rename(&tmp, path).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_checkpoint_round_trip() {
+        let path = std::env::temp_dir().join("oss-sdk-checkpoint.json");
+        let checkpoint = Checkpoint {
+            bucket: "examplebucket".to_owned(),
+            object: "big.bin".to_owned(),
+            upload_id: "0004B9894A22E5B1888A1E29F823****".to_owned(),
+            file_size: 300 * 1024,
+            file_modified: 1700000000,
+            part_size: 100 * 1024,
+            parts: vec![Part::new(
+                1,
+                "\"3349DC700140D7F86A0784842780****\"".to_owned(),
+            )],
+        };
+        save_checkpoint(&path, &checkpoint).await.unwrap();
+        let loaded = load_checkpoint(&path).await.unwrap();
+        assert_eq!(loaded, checkpoint);
+        assert!(loaded.matches(&Checkpoint {
+            upload_id: String::new(),
+            parts: Vec::new(),
+            ..checkpoint.clone()
+        }));
+        assert!(!loaded.matches(&Checkpoint {
+            file_modified: 1700000001,
+            ..checkpoint
+        }));
+        std::fs::remove_file(&path).unwrap();
+        assert!(load_checkpoint(&path).await.is_none());
+    }
+}
@@ -0,0 +1,327 @@
+use reqwest::StatusCode;
+use std::cell::Cell;
+use std::collections::hash_map::RandomState;
+use std::future::Future;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+use crate::errors::Error;
+
+tokio::task_local! {
+    static ATTEMPT: Cell<u32>;
+}
+
+// Runs `f` and also returns the attempt its last request was, 1 when it was
+// not retried, for the context of its errors.
+pub(crate) async fn with_attempts<F: Future>(f: F) -> (F::Output, u32) {
+    ATTEMPT
+        .scope(Cell::new(1), async {
+            let value = f.await;
+            (value, ATTEMPT.with(|attempt| attempt.get()))
+        })
+        .await
+}
+
+// Notes that attempt `attempt` of a request is being sent, inside
+// `with_attempts`.
+pub(crate) fn record_attempt(attempt: u32) {
+    let _ = ATTEMPT.try_with(|a| a.set(attempt));
+}
+
+// How a failed request should be handled by the retry layer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Disposition {
+    // Transient server or network condition, send the same request again.
+    Retryable,
+    // The signature went stale (e.g. clock skew), sign again with a fresh date and retry.
+    ResignAndRetry,
+    // Temporary credentials expired or were revoked, refresh them before retrying.
+    RefreshCredentials,
+    // Retrying cannot succeed.
+    Fatal,
+}
+
+// https://help.aliyun.com/document_detail/32005.html
+const DISPOSITIONS: [(&str, Disposition); 20] = [
+    ("InternalError", Disposition::Retryable),
+    ("ServiceUnavailable", Disposition::Retryable),
+    ("RequestTimeout", Disposition::Retryable),
+    ("InvalidDigest", Disposition::Retryable),
+    ("IncompleteBody", Disposition::Retryable),
+    ("DownloadTrafficRateLimitExceeded", Disposition::Retryable),
+    ("UploadTrafficRateLimitExceeded", Disposition::Retryable),
+    ("RequestTimeTooSkewed", Disposition::ResignAndRetry),
+    ("RequestExpired", Disposition::ResignAndRetry),
+    ("InvalidAccessKeyId", Disposition::RefreshCredentials),
+    ("SecurityTokenExpired", Disposition::RefreshCredentials),
+    ("InvalidSecurityToken", Disposition::RefreshCredentials),
+    ("AccessDenied", Disposition::Fatal),
+    ("SignatureDoesNotMatch", Disposition::Fatal),
+    ("NoSuchBucket", Disposition::Fatal),
+    ("NoSuchKey", Disposition::Fatal),
+    ("NoSuchUpload", Disposition::Fatal),
+    ("InvalidArgument", Disposition::Fatal),
+    ("EntityTooLarge", Disposition::Fatal),
+    ("PositionNotEqualToLength", Disposition::Fatal),
+];
+
+impl Disposition {
+    // Looks up the disposition of an OSS error code, `None` when the code is
+    // not in the table and the HTTP status should decide.
+    pub fn from_code(code: &str) -> Option<Disposition> {
+        DISPOSITIONS
+            .iter()
+            .find(|(c, _)| *c == code)
+            .map(|(_, disposition)| *disposition)
+    }
+
+    pub fn from_status(status: StatusCode) -> Disposition {
+        if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS {
+            Disposition::Retryable
+        } else {
+            Disposition::Fatal
+        }
+    }
+
+    // The code table takes precedence over the HTTP status.
+    pub fn classify(code: Option<&str>, status: StatusCode) -> Disposition {
+        code.and_then(Disposition::from_code)
+            .unwrap_or_else(|| Disposition::from_status(status))
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        *self != Disposition::Fatal
+    }
+}
+
+impl Error {
+    pub fn disposition(&self) -> Disposition {
+        match self.kind() {
+            Error::Api(e) => Disposition::classify(Some(e.code()), e.status()),
+            #[cfg(not(target_arch = "wasm32"))]
+            Error::Reqwest(e) if e.is_timeout() || e.is_connect() => Disposition::Retryable,
+            Error::Reqwest(e) => match e.status() {
+                Some(status) => Disposition::from_status(status),
+                None if e.is_body() || e.is_request() => Disposition::Retryable,
+                None => Disposition::Fatal,
+            },
+            _ => Disposition::Fatal,
+        }
+    }
+}
+
+// How often and how patiently requests are retried, set with
+// `OSSBuilder::retry_policy`. A request is retried when it failed to connect,
+// timed out or was cut off, or got a 5xx or 429 response or an error code
+// the table deems recoverable, see `Disposition`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    // Attempts per request including the first one, 1 disables retries.
+    pub max_attempts: u32,
+    // Backoff before the second attempt, doubled for every further one.
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    // Waits a random time up to the backoff instead of all of it, so clients
+    // failing together do not retry together.
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    // The wait after failed attempt `attempt`, counting from 1.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let backoff = self
+            .base_delay
+            .checked_mul(1 << attempt.saturating_sub(1).min(16))
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay);
+        if self.jitter {
+            // RandomState is seeded randomly, no need for a rand dependency.
+            let random = RandomState::new().build_hasher().finish();
+            backoff.mul_f64((random >> 11) as f64 / (1u64 << 53) as f64)
+        } else {
+            backoff
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::FixedClock;
+    use crate::credentials::{Credentials, CredentialsProvider};
+    use crate::interceptor::RequestInterceptor;
+    use crate::oss::OSS;
+    use chrono::{TimeZone, Utc};
+    use futures::future::BoxFuture;
+    use reqwest::header::DATE;
+    use reqwest::{Request, Response};
+    use std::collections::VecDeque;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    // Rejects requests with the given error codes in order, an hour ahead of
+    // the client, then accepts them. Keeps the dates they were signed with.
+    #[derive(Debug, Default)]
+    struct Rejecting {
+        codes: Mutex<VecDeque<&'static str>>,
+        dates: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl RequestInterceptor for Rejecting {
+        fn before_send(&self, request: &mut Request) -> Option<Response> {
+            let date = request.headers()[DATE].to_str().unwrap().to_owned();
+            self.dates.lock().unwrap().push(date);
+            let resp = match self.codes.lock().unwrap().pop_front() {
+                Some(code) => http::Response::builder()
+                    .status(403)
+                    .header(DATE, "Mon, 01 Jan 2024 01:00:00 GMT")
+                    .body(format!("<Error><Code>{}</Code></Error>", code)),
+                None => http::Response::builder().status(204).body(String::new()),
+            };
+            Some(Response::from(resp.unwrap()))
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct Invalidated(Arc<AtomicUsize>);
+
+    impl CredentialsProvider for Invalidated {
+        fn credentials(&self) -> BoxFuture<'_, Result<Credentials, Error>> {
+            Box::pin(async { Ok(Credentials::new("xxx".into(), "xxx".into(), None)) })
+        }
+
+        fn invalidate(&self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn rejecting_oss(codes: &[&'static str]) -> (OSS, Arc<Mutex<Vec<String>>>, Arc<AtomicUsize>) {
+        let rejecting = Rejecting {
+            codes: Mutex::new(codes.iter().copied().collect()),
+            ..Default::default()
+        };
+        let (dates, invalidated) = (rejecting.dates.clone(), Arc::new(AtomicUsize::new(0)));
+        let oss_instance = OSS::builder(
+            "xxx".to_string(),
+            "xxx".to_string(),
+            "xxx.aliyuncs.com".to_string(),
+            "xxx".to_string(),
+        )
+        .clock(FixedClock(
+            Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+        ))
+        .credentials_provider(Invalidated(invalidated.clone()))
+        .interceptor(rejecting)
+        .build();
+        (oss_instance, dates, invalidated)
+    }
+
+    #[tokio::test]
+    async fn test_resign_and_refresh() {
+        let (oss_instance, dates, _) = rejecting_oss(&["RequestTimeTooSkewed"]);
+        oss_instance.delete_object("a.txt").await.unwrap();
+        assert_eq!(
+            *dates.lock().unwrap(),
+            [
+                "Mon, 01 Jan 2024 00:00:00 GMT",
+                "Mon, 01 Jan 2024 01:00:00 GMT"
+            ]
+        );
+
+        // Signing anew helps once, so does refreshing the credentials.
+        let (oss_instance, _, _) = rejecting_oss(&["RequestTimeTooSkewed", "RequestTimeTooSkewed"]);
+        let err = oss_instance.delete_object("a.txt").await.unwrap_err();
+        assert_eq!(err.code(), Some("RequestTimeTooSkewed"));
+        assert_eq!(err.context().unwrap().attempt(), 2);
+
+        let (oss_instance, dates, invalidated) = rejecting_oss(&["SecurityTokenExpired"]);
+        oss_instance.delete_object("a.txt").await.unwrap();
+        assert_eq!(dates.lock().unwrap().len(), 2);
+        assert_eq!(invalidated.load(Ordering::SeqCst), 1);
+
+        let (oss_instance, _, _) = rejecting_oss(&["AccessDenied"]);
+        let err = oss_instance.delete_object("a.txt").await.unwrap_err();
+        assert_eq!(err.disposition(), Disposition::Fatal);
+        assert_eq!(err.context().unwrap().attempt(), 1);
+    }
+
+    #[test]
+    fn test_disposition_table() {
+        assert_eq!(
+            Disposition::from_code("InternalError"),
+            Some(Disposition::Retryable)
+        );
+        assert_eq!(
+            Disposition::from_code("RequestTimeTooSkewed"),
+            Some(Disposition::ResignAndRetry)
+        );
+        assert_eq!(
+            Disposition::from_code("SecurityTokenExpired"),
+            Some(Disposition::RefreshCredentials)
+        );
+        assert_eq!(
+            Disposition::from_code("NoSuchKey"),
+            Some(Disposition::Fatal)
+        );
+        assert_eq!(Disposition::from_code("SomethingNew"), None);
+    }
+
+    #[test]
+    fn test_classify_falls_back_to_status() {
+        assert_eq!(
+            Disposition::classify(Some("SomethingNew"), StatusCode::SERVICE_UNAVAILABLE),
+            Disposition::Retryable
+        );
+        assert_eq!(
+            Disposition::classify(Some("NoSuchKey"), StatusCode::INTERNAL_SERVER_ERROR),
+            Disposition::Fatal
+        );
+        assert_eq!(
+            Disposition::classify(None, StatusCode::FORBIDDEN),
+            Disposition::Fatal
+        );
+        assert!(!Error::E("boom".to_owned()).disposition().is_retryable());
+        let api = crate::errors::OssApiError::parse(
+            StatusCode::FORBIDDEN,
+            "<Error><Code>SecurityTokenExpired</Code></Error>",
+        )
+        .unwrap();
+        assert_eq!(
+            Error::Api(Box::new(api)).disposition(),
+            Disposition::RefreshCredentials
+        );
+    }
+
+    #[test]
+    fn test_retry_policy_delay() {
+        let policy = RetryPolicy {
+            jitter: false,
+            ..Default::default()
+        };
+        assert_eq!(policy.delay(1), Duration::from_millis(200));
+        assert_eq!(policy.delay(2), Duration::from_millis(400));
+        assert_eq!(policy.delay(10), Duration::from_secs(5));
+        assert_eq!(policy.delay(100), Duration::from_secs(5));
+        for attempt in 1..5 {
+            assert!(RetryPolicy::default().delay(attempt) <= policy.delay(attempt));
+        }
+    }
+}
@@ -0,0 +1,322 @@
+// File, timer and task primitives of the async runtime, used by the
+// transfer helpers instead of naming tokio directly. Tokio by default; with
+// the `async-std` feature async-std's own, for applications standardized on
+// async-std or smol. HTTP still goes through reqwest, which the feature runs
+// on async-std's tokio 1 compatibility runtime. On wasm32 there are no files,
+// and timers and tasks are the browser's.
+use futures::channel::oneshot;
+use futures::future::{self, Either, FutureExt};
+use std::future::Future;
+#[cfg(not(target_arch = "wasm32"))]
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::thread;
+use std::time::Duration;
+
+use crate::errors::Error;
+use crate::transport::MaybeSend;
+
+#[cfg(all(not(feature = "async-std"), not(target_arch = "wasm32")))]
+pub(crate) use tokio::fs::File;
+#[cfg(all(not(feature = "async-std"), not(target_arch = "wasm32")))]
+pub(crate) use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+#[cfg(all(feature = "async-std", not(target_arch = "wasm32")))]
+pub(crate) use async_std::fs::File;
+#[cfg(all(feature = "async-std", not(target_arch = "wasm32")))]
+pub(crate) use async_std::io::{
+    prelude::SeekExt as AsyncSeekExt, ReadExt as AsyncReadExt, WriteExt as AsyncWriteExt,
+};
+
+#[cfg(all(not(feature = "async-std"), not(target_arch = "wasm32")))]
+pub(crate) async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await
+}
+
+#[cfg(all(feature = "async-std", not(target_arch = "wasm32")))]
+pub(crate) async fn sleep(duration: Duration) {
+    async_std::task::sleep(duration).await
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) async fn sleep(duration: Duration) {
+    gloo_timers::future::sleep(duration).await
+}
+
+// A task started with `spawn`. Awaiting it returns the task's output, or
+// resumes its panic; dropping it cancels the task unless it was detached.
+#[derive(Debug)]
+#[cfg_attr(not(feature = "transfer-manager"), allow(dead_code))]
+pub(crate) struct JoinHandle<T> {
+    output: oneshot::Receiver<thread::Result<T>>,
+    detached: Arc<AtomicBool>,
+    finished: Arc<AtomicBool>,
+    // Closed once the task's future is dropped, finished or not.
+    dropped: oneshot::Receiver<()>,
+}
+
+impl<T> JoinHandle<T> {
+    #[cfg_attr(not(feature = "transfer-manager"), allow(dead_code))]
+    pub(crate) fn is_finished(&self) -> bool {
+        self.finished.load(Ordering::SeqCst)
+    }
+
+    // Lets the task run to completion on its own.
+    pub(crate) fn detach(self) {
+        self.detached.store(true, Ordering::SeqCst);
+    }
+
+    // Cancels the task and waits until its future has been dropped.
+    #[cfg_attr(not(feature = "transfer-manager"), allow(dead_code))]
+    pub(crate) async fn cancel(self) {
+        let JoinHandle {
+            output, dropped, ..
+        } = self;
+        drop(output);
+        let _ = dropped.await;
+    }
+}
+
+impl<T> Future for JoinHandle<T> {
+    type Output = Result<T, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.output)
+            .poll(cx)
+            .map(|output| match output {
+                Ok(Ok(output)) => Ok(output),
+                Ok(Err(panic)) => panic::resume_unwind(panic),
+                // Dropped by the runtime, e.g. as it shut down.
+                Err(_) => Err(Error::E("task was cancelled".to_owned())),
+            })
+    }
+}
+
+// Marks the task finished, and closes `dropped`, when its future goes.
+struct Finished(Arc<AtomicBool>, Option<oneshot::Sender<()>>);
+
+impl Drop for Finished {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::SeqCst);
+        self.1.take();
+    }
+}
+
+// Runs `fut` in a task of its own. `name` shows up in tokio-console when
+// built with the `tokio-console` feature and `RUSTFLAGS="--cfg tokio_unstable"`.
+pub(crate) fn spawn<F>(name: &str, fut: F) -> JoinHandle<F::Output>
+where
+    F: Future + MaybeSend + 'static,
+    F::Output: MaybeSend + 'static,
+{
+    let (tx, output) = oneshot::channel();
+    let (dropped_tx, dropped) = oneshot::channel();
+    let detached = Arc::new(AtomicBool::new(false));
+    let finished = Arc::new(AtomicBool::new(false));
+    let task = {
+        let detached = detached.clone();
+        let finished = Finished(finished.clone(), Some(dropped_tx));
+        async move {
+            let fut = AssertUnwindSafe(fut).catch_unwind();
+            futures::pin_mut!(fut);
+            let mut tx = tx;
+            let output = match future::select(fut, tx.cancellation()).await {
+                Either::Left((output, _)) => output,
+                Either::Right((_, fut)) if detached.load(Ordering::SeqCst) => fut.await,
+                Either::Right(_) => return,
+            };
+            drop(finished);
+            let _ = tx.send(output);
+        }
+    };
+    spawn_task(name, task);
+    JoinHandle {
+        output,
+        detached,
+        finished,
+        dropped,
+    }
+}
+
+#[cfg(all(
+    tokio_unstable,
+    feature = "tokio-console",
+    not(feature = "async-std"),
+    not(target_arch = "wasm32")
+))]
+fn spawn_task<F: Future<Output = ()> + Send + 'static>(name: &str, task: F) {
+    tokio::task::Builder::new()
+        .name(name)
+        .spawn(task)
+        .expect("failed to spawn task");
+}
+
+#[cfg(all(
+    not(all(tokio_unstable, feature = "tokio-console")),
+    not(feature = "async-std"),
+    not(target_arch = "wasm32")
+))]
+fn spawn_task<F: Future<Output = ()> + Send + 'static>(_name: &str, task: F) {
+    tokio::spawn(task);
+}
+
+#[cfg(all(feature = "async-std", not(target_arch = "wasm32")))]
+fn spawn_task<F: Future<Output = ()> + Send + 'static>(_name: &str, task: F) {
+    start();
+    async_std::task::spawn(task);
+}
+
+// async-std's executor adopts whatever tokio runtime is current on the
+// thread that first spawns onto it, for reqwest, and fails once that runtime
+// shuts down; start it from a thread of its own so it builds its own.
+#[cfg(all(feature = "async-std", not(target_arch = "wasm32")))]
+fn start() {
+    static START: std::sync::Once = std::sync::Once::new();
+    START.call_once(|| {
+        let _ = std::thread::spawn(|| async_std::task::spawn(async {})).join();
+    });
+}
+
+#[cfg(target_arch = "wasm32")]
+fn spawn_task<F: Future<Output = ()> + 'static>(_name: &str, task: F) {
+    wasm_bindgen_futures::spawn_local(task);
+}
+
+// async-std has its own `Path`, these take the std one under either runtime.
+#[cfg(all(not(feature = "async-std"), not(target_arch = "wasm32")))]
+pub(crate) async fn open<P: AsRef<Path>>(path: P) -> io::Result<File> {
+    File::open(path).await
+}
+
+#[cfg(all(feature = "async-std", not(target_arch = "wasm32")))]
+pub(crate) async fn open<P: AsRef<Path>>(path: P) -> io::Result<File> {
+    start();
+    File::open(path.as_ref().as_os_str()).await
+}
+
+#[cfg(all(not(feature = "async-std"), not(target_arch = "wasm32")))]
+pub(crate) async fn create<P: AsRef<Path>>(path: P) -> io::Result<File> {
+    File::create(path).await
+}
+
+#[cfg(all(feature = "async-std", not(target_arch = "wasm32")))]
+pub(crate) async fn create<P: AsRef<Path>>(path: P) -> io::Result<File> {
+    start();
+    File::create(path.as_ref().as_os_str()).await
+}
+
+// Opens an existing file for writing without truncating it.
+#[cfg(all(not(feature = "async-std"), not(target_arch = "wasm32")))]
+pub(crate) async fn open_write<P: AsRef<Path>>(path: P) -> io::Result<File> {
+    tokio::fs::OpenOptions::new().write(true).open(path).await
+}
+
+#[cfg(all(feature = "async-std", not(target_arch = "wasm32")))]
+pub(crate) async fn open_write<P: AsRef<Path>>(path: P) -> io::Result<File> {
+    start();
+    async_std::fs::OpenOptions::new()
+        .write(true)
+        .open(path.as_ref().as_os_str())
+        .await
+}
+
+#[cfg(all(not(feature = "async-std"), not(target_arch = "wasm32")))]
+pub(crate) async fn metadata<P: AsRef<Path>>(path: P) -> io::Result<std::fs::Metadata> {
+    tokio::fs::metadata(path).await
+}
+
+#[cfg(all(feature = "async-std", not(target_arch = "wasm32")))]
+pub(crate) async fn metadata<P: AsRef<Path>>(path: P) -> io::Result<std::fs::Metadata> {
+    start();
+    async_std::fs::metadata(path.as_ref().as_os_str()).await
+}
+
+#[cfg(all(not(feature = "async-std"), not(target_arch = "wasm32")))]
+pub(crate) async fn rename<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> io::Result<()> {
+    tokio::fs::rename(from, to).await
+}
+
+#[cfg(all(feature = "async-std", not(target_arch = "wasm32")))]
+pub(crate) async fn rename<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> io::Result<()> {
+    start();
+    async_std::fs::rename(from.as_ref().as_os_str(), to.as_ref().as_os_str()).await
+}
+
+#[cfg(all(not(feature = "async-std"), not(target_arch = "wasm32")))]
+pub(crate) async fn remove_file<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    tokio::fs::remove_file(path).await
+}
+
+#[cfg(all(feature = "async-std", not(target_arch = "wasm32")))]
+pub(crate) async fn remove_file<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    start();
+    async_std::fs::remove_file(path.as_ref().as_os_str()).await
+}
+
+// The entries of the directory at `path`, with their file types.
+#[cfg(all(not(feature = "async-std"), not(target_arch = "wasm32")))]
+pub(crate) async fn read_dir<P: AsRef<Path>>(
+    path: P,
+) -> io::Result<Vec<(PathBuf, std::fs::FileType)>> {
+    let mut dir = tokio::fs::read_dir(path).await?;
+    let mut entries = Vec::new();
+    while let Some(entry) = dir.next_entry().await? {
+        entries.push((entry.path(), entry.file_type().await?));
+    }
+    Ok(entries)
+}
+
+#[cfg(all(feature = "async-std", not(target_arch = "wasm32")))]
+pub(crate) async fn read_dir<P: AsRef<Path>>(
+    path: P,
+) -> io::Result<Vec<(PathBuf, std::fs::FileType)>> {
+    start();
+    use futures::TryStreamExt;
+
+    let mut dir = async_std::fs::read_dir(path.as_ref().as_os_str()).await?;
+    let mut entries = Vec::new();
+    while let Some(entry) = dir.try_next().await? {
+        entries.push((entry.path().into(), entry.file_type().await?));
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_spawn() {
+        assert_eq!(spawn("test", async { 1 }).await.unwrap(), 1);
+
+        let panicked = spawn("test", async { panic!("boom") });
+        let caught = AssertUnwindSafe(panicked).catch_unwind().await;
+        assert!(caught.is_err());
+
+        // Dropped by `cancel`, the sender closes the channel.
+        let (tx, rx) = oneshot::channel::<()>();
+        let handle = spawn("test", async move {
+            let _tx = tx;
+            future::pending::<()>().await
+        });
+        assert!(!handle.is_finished());
+        handle.cancel().await;
+        assert!(rx.await.is_err());
+
+        let (tx, rx) = oneshot::channel();
+        let (done_tx, done_rx) = oneshot::channel();
+        spawn("test", async move {
+            let _ = rx.await;
+            let _ = done_tx.send(());
+        })
+        .detach();
+        tx.send(()).unwrap();
+        done_rx.await.unwrap();
+    }
+}
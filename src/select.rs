@@ -0,0 +1,337 @@
+use base64::encode;
+use reqwest::header::{HeaderMap, CONTENT_LENGTH, DATE};
+use reqwest::Method;
+use std::convert::TryInto;
+
+use crate::errors::{Error, ObjectError};
+use crate::oss::OSS;
+
+const FRAME_TYPE_CONTINUOUS: u32 = 0x80_0004;
+const FRAME_TYPE_CSV_META_END: u32 = 0x80_0006;
+const FRAME_TYPE_JSON_META_END: u32 = 0x80_0007;
+
+// version(1) | frame type(3) | payload length(4) | header checksum(4)
+const FRAME_HEADER_LEN: usize = 12;
+const FRAME_CHECKSUM_LEN: usize = 4;
+
+#[derive(Clone, Debug)]
+pub enum SelectMetaInput {
+    Csv {
+        record_delimiter: String,
+        field_delimiter: String,
+        quote_character: String,
+    },
+    // Only JSON LINES objects can be indexed, JSON DOCUMENT is not splittable.
+    Json,
+}
+
+impl Default for SelectMetaInput {
+    fn default() -> Self {
+        SelectMetaInput::Csv {
+            record_delimiter: "\n".to_owned(),
+            field_delimiter: ",".to_owned(),
+            quote_character: "\"".to_owned(),
+        }
+    }
+}
+
+impl SelectMetaInput {
+    fn process(&self) -> &'static str {
+        match self {
+            SelectMetaInput::Csv { .. } => "csv/meta",
+            SelectMetaInput::Json => "json/meta",
+        }
+    }
+
+    fn to_xml(&self, overwrite_if_exists: bool) -> String {
+        match self {
+            SelectMetaInput::Csv {
+                record_delimiter,
+                field_delimiter,
+                quote_character,
+            } => format!(
+                "<CsvMetaRequest><InputSerialization><CSV>\
+                 <RecordDelimiter>{}</RecordDelimiter>\
+                 <FieldDelimiter>{}</FieldDelimiter>\
+                 <QuoteCharacter>{}</QuoteCharacter>\
+                 </CSV></InputSerialization>\
+                 <OverwriteIfExists>{}</OverwriteIfExists></CsvMetaRequest>",
+                encode(record_delimiter),
+                encode(field_delimiter),
+                encode(quote_character),
+                overwrite_if_exists
+            ),
+            SelectMetaInput::Json => format!(
+                "<JsonMetaRequest><InputSerialization><JSON><Type>LINES</Type></JSON>\
+                 </InputSerialization><OverwriteIfExists>{}</OverwriteIfExists></JsonMetaRequest>",
+                overwrite_if_exists
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SelectObjectMeta {
+    offset: u64,
+    total_scanned_bytes: u64,
+    status: u32,
+    splits_count: u32,
+    rows_count: u64,
+    cols_count: Option<u32>,
+    error_message: String,
+}
+
+impl SelectObjectMeta {
+    pub fn new(
+        offset: u64,
+        total_scanned_bytes: u64,
+        status: u32,
+        splits_count: u32,
+        rows_count: u64,
+        cols_count: Option<u32>,
+        error_message: String,
+    ) -> Self {
+        SelectObjectMeta {
+            offset,
+            total_scanned_bytes,
+            status,
+            splits_count,
+            rows_count,
+            cols_count,
+            error_message,
+        }
+    }
+
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub fn total_scanned_bytes(&self) -> u64 {
+        self.total_scanned_bytes
+    }
+
+    pub fn status(&self) -> u32 {
+        self.status
+    }
+
+    pub fn splits_count(&self) -> u32 {
+        self.splits_count
+    }
+
+    pub fn rows_count(&self) -> u64 {
+        self.rows_count
+    }
+
+    // Only reported for CSV objects.
+    pub fn cols_count(&self) -> Option<u32> {
+        self.cols_count
+    }
+
+    pub fn error_message(&self) -> &str {
+        &self.error_message
+    }
+}
+
+impl OSS {
+    // https://help.aliyun.com/document_detail/106082.html
+    pub async fn create_select_object_meta<S>(
+        &self,
+        object: S,
+        input: SelectMetaInput,
+        overwrite_if_exists: bool,
+    ) -> Result<SelectObjectMeta, Error>
+    where
+        S: AsRef<str>,
+    {
+        let object = object.as_ref();
+        self.with_context("create_select_object_meta", object, async {
+            let resources_str = &format!("x-oss-process={}", input.process());
+            let host = &self.host(self.bucket(), object, resources_str);
+            let body = &input.to_xml(overwrite_if_exists);
+            let resp = self
+                .retrying(&Method::POST, object, || async move {
+                    let mut headers = HeaderMap::new();
+                    headers.insert(DATE, self.date().parse()?);
+                    headers.insert(CONTENT_LENGTH, body.len().to_string().parse()?);
+                    let authorization = self
+                        .sign("POST", self.bucket(), object, resources_str, &mut headers)
+                        .await?;
+                    headers.insert("Authorization", authorization.parse()?);
+                    self.send(self.client.post(host).headers(headers).body(body.clone()))
+                        .await
+                })
+                .await?;
+
+            if !resp.status().is_success() {
+                return Err(self
+                    .api_error(resp, |reason| {
+                        Error::Object(ObjectError::SelectError {
+                            msg: format!("can not create select object meta, reason: {:?}", reason),
+                        })
+                    })
+                    .await);
+            }
+
+            let meta = parse_meta_frames(&resp.bytes().await?)?;
+            if meta.status() >= 400 {
+                return Err(Error::Object(ObjectError::SelectError {
+                    msg: format!(
+                        "can not create select object meta, status: {}, reason: {}",
+                        meta.status(),
+                        meta.error_message()
+                    ),
+                }));
+            }
+            Ok(meta)
+        })
+        .await
+    }
+}
+
+// The meta response body is a sequence of select frames, the last of which
+// carries the statistics of the scanned object.
+fn parse_meta_frames(buf: &[u8]) -> Result<SelectObjectMeta, Error> {
+    let mut pos = 0;
+    while pos + FRAME_HEADER_LEN <= buf.len() {
+        let frame_type = u32::from_be_bytes([0, buf[pos + 1], buf[pos + 2], buf[pos + 3]]);
+        let payload_len = match read_u32(buf, pos + 4) {
+            Some(len) => len as usize,
+            None => break,
+        };
+        let payload_start = pos + FRAME_HEADER_LEN;
+        let payload_end = payload_start + payload_len;
+        if payload_end + FRAME_CHECKSUM_LEN > buf.len() {
+            break;
+        }
+        let payload = &buf[payload_start..payload_end];
+
+        // Frames too short for their type are skipped.
+        match frame_type {
+            FRAME_TYPE_CSV_META_END | FRAME_TYPE_JSON_META_END => {
+                if let Some(meta) = parse_meta_end(payload, frame_type == FRAME_TYPE_CSV_META_END) {
+                    return Ok(meta);
+                }
+            }
+            FRAME_TYPE_CONTINUOUS if payload.len() >= 16 => {
+                if let Some(scanned) = read_u64(payload, 8) {
+                    debug!("select meta scanned: {}", scanned);
+                }
+            }
+            _ => (),
+        }
+        pos = payload_end + FRAME_CHECKSUM_LEN;
+    }
+
+    Err(Error::Object(ObjectError::SelectError {
+        msg: "can not create select object meta, reason: missing meta end frame".to_owned(),
+    }))
+}
+
+// The payload of a meta end frame, CSV ones have the column count before
+// the error message.
+fn parse_meta_end(payload: &[u8], csv: bool) -> Option<SelectObjectMeta> {
+    let (cols_count, message_start) = if csv {
+        (Some(read_u32(payload, 32)?), 36)
+    } else {
+        (None, 32)
+    };
+    Some(SelectObjectMeta::new(
+        read_u64(payload, 0)?,
+        read_u64(payload, 8)?,
+        read_u32(payload, 16)?,
+        read_u32(payload, 20)?,
+        read_u64(payload, 24)?,
+        cols_count,
+        String::from_utf8_lossy(payload.get(message_start..)?).into_owned(),
+    ))
+}
+
+#[inline]
+fn read_u32(buf: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        buf.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+#[inline]
+fn read_u64(buf: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(
+        buf.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(frame_type: u32, payload: &[u8]) -> Vec<u8> {
+        let mut buf = vec![1];
+        buf.extend_from_slice(&frame_type.to_be_bytes()[1..]);
+        buf.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        buf.extend_from_slice(&[0; 4]);
+        buf.extend_from_slice(payload);
+        buf.extend_from_slice(&[0; 4]);
+        buf
+    }
+
+    #[test]
+    fn test_parse_csv_meta_frames() {
+        let mut continuous = Vec::new();
+        continuous.extend_from_slice(&10u64.to_be_bytes());
+        continuous.extend_from_slice(&10u64.to_be_bytes());
+
+        let mut end = Vec::new();
+        end.extend_from_slice(&100u64.to_be_bytes());
+        end.extend_from_slice(&100u64.to_be_bytes());
+        end.extend_from_slice(&200u32.to_be_bytes());
+        end.extend_from_slice(&3u32.to_be_bytes());
+        end.extend_from_slice(&42u64.to_be_bytes());
+        end.extend_from_slice(&5u32.to_be_bytes());
+
+        let mut buf = frame(FRAME_TYPE_CONTINUOUS, &continuous);
+        buf.extend(frame(FRAME_TYPE_CSV_META_END, &end));
+
+        let meta = parse_meta_frames(&buf).unwrap();
+        assert_eq!(meta.total_scanned_bytes(), 100);
+        assert_eq!(meta.status(), 200);
+        assert_eq!(meta.splits_count(), 3);
+        assert_eq!(meta.rows_count(), 42);
+        assert_eq!(meta.cols_count(), Some(5));
+        assert_eq!(meta.error_message(), "");
+    }
+
+    #[test]
+    fn test_parse_json_meta_frames() {
+        let mut end = Vec::new();
+        end.extend_from_slice(&0u64.to_be_bytes());
+        end.extend_from_slice(&0u64.to_be_bytes());
+        end.extend_from_slice(&400u32.to_be_bytes());
+        end.extend_from_slice(&0u32.to_be_bytes());
+        end.extend_from_slice(&0u64.to_be_bytes());
+        end.extend_from_slice(b"InvalidJsonData");
+
+        let meta = parse_meta_frames(&frame(FRAME_TYPE_JSON_META_END, &end)).unwrap();
+        assert_eq!(meta.status(), 400);
+        assert_eq!(meta.cols_count(), None);
+        assert_eq!(meta.error_message(), "InvalidJsonData");
+    }
+
+    #[test]
+    fn test_parse_meta_frames_missing_end() {
+        assert!(parse_meta_frames(&[]).is_err());
+    }
+
+    #[test]
+    fn test_parse_meta_frames_short_frames() {
+        let mut end = vec![0; 32];
+        end[16..20].copy_from_slice(&200u32.to_be_bytes());
+        let mut buf = frame(FRAME_TYPE_CONTINUOUS, &10u64.to_be_bytes());
+        buf.extend(frame(FRAME_TYPE_CSV_META_END, &end[..20]));
+        buf.extend(frame(FRAME_TYPE_JSON_META_END, &end));
+
+        let meta = parse_meta_frames(&buf).unwrap();
+        assert_eq!(meta.status(), 200);
+        assert_eq!(meta.cols_count(), None);
+        assert!(parse_meta_frames(&frame(FRAME_TYPE_CONTINUOUS, &[1])).is_err());
+    }
+}
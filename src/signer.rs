@@ -0,0 +1,131 @@
+use reqwest::header::HeaderMap;
+use std::fmt;
+
+use crate::auth::string_to_sign;
+use crate::errors::Error;
+use crate::transport::BoxFuture;
+
+// The parts of an outgoing request its signature covers.
+#[derive(Debug)]
+pub struct RequestParts<'a> {
+    verb: &'a str,
+    bucket: &'a str,
+    object: &'a str,
+    resources: &'a str,
+    headers: &'a mut HeaderMap,
+}
+
+impl<'a> RequestParts<'a> {
+    pub fn new(
+        verb: &'a str,
+        bucket: &'a str,
+        object: &'a str,
+        resources: &'a str,
+        headers: &'a mut HeaderMap,
+    ) -> Self {
+        RequestParts {
+            verb,
+            bucket,
+            object,
+            resources,
+            headers,
+        }
+    }
+
+    pub fn verb(&self) -> &str {
+        self.verb
+    }
+
+    // Empty for service level requests such as ListBuckets.
+    pub fn bucket(&self) -> &str {
+        self.bucket
+    }
+
+    pub fn object(&self) -> &str {
+        self.object
+    }
+
+    // The signed sub-resources for V1, the whole query for V4.
+    pub fn resources(&self) -> &str {
+        self.resources
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        self.headers
+    }
+
+    // For headers that have to be signed and sent, e.g. the STS token.
+    pub fn headers_mut(&mut self) -> &mut HeaderMap {
+        self.headers
+    }
+
+    // The V1 string to sign, the Authorization header is then
+    // `OSS <key id>:<base64 HMAC-SHA1 of it>`.
+    pub fn string_to_sign(&self) -> String {
+        string_to_sign(
+            self.verb,
+            self.bucket,
+            self.object,
+            self.resources,
+            self.headers,
+        )
+    }
+}
+
+// Computes the Authorization header of each request in place of the
+// client's own keys, e.g. by having a KMS or HSM compute the HMAC so the
+// secret never enters the process. Implementations return a boxed future,
+// i.e. an `async fn sign(&self, parts: RequestParts<'_>) -> Result<String, Error>`.
+pub trait Signer: fmt::Debug + Send + Sync {
+    fn sign<'a>(&'a self, parts: RequestParts<'a>) -> BoxFuture<'a, Result<String, Error>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::hmac_sha1_base64;
+    use crate::oss::OSS;
+    use crate::verify_signature;
+    use reqwest::header::{AUTHORIZATION, DATE};
+
+    // Stands in for an HSM holding the secret.
+    #[derive(Debug)]
+    struct HsmSigner;
+
+    impl Signer for HsmSigner {
+        fn sign<'a>(&'a self, parts: RequestParts<'a>) -> BoxFuture<'a, Result<String, Error>> {
+            Box::pin(async move {
+                let signature = hmac_sha1_base64("hsm-secret", &parts.string_to_sign());
+                Ok(format!("OSS hsm-id:{}", signature))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_signer() {
+        let oss_instance = OSS::builder(
+            String::new(),
+            String::new(),
+            "oss-cn-hangzhou.aliyuncs.com".to_string(),
+            "examplebucket".to_string(),
+        )
+        .signer(HsmSigner)
+        .build();
+        let mut headers = HeaderMap::new();
+        headers.insert(DATE, "Wed, 28 Dec 2022 10:27:41 GMT".parse().unwrap());
+        let authorization = oss_instance
+            .sign("GET", "examplebucket", "example.txt", "acl", &mut headers)
+            .await
+            .unwrap();
+        headers.insert(AUTHORIZATION, authorization.parse().unwrap());
+        assert!(verify_signature(
+            "hsm-id",
+            "hsm-secret",
+            "GET",
+            "examplebucket",
+            "example.txt",
+            "acl",
+            &headers
+        ));
+    }
+}
@@ -0,0 +1,188 @@
+use futures::stream::{self, Stream};
+use reqwest::header::HeaderMap;
+use serde_derive::Deserialize;
+use serde_xml_rs::from_str;
+use std::time::Duration;
+
+use crate::errors::Error;
+use crate::oss::OSS;
+use crate::rt;
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct BucketStat {
+    // Bytes stored across all storage classes.
+    storage: u64,
+    object_count: u64,
+    multipart_upload_count: u64,
+    #[serde(default)]
+    live_channel_count: u64,
+    // Unix seconds when the statistics were last updated, they lag behind
+    // writes by up to an hour.
+    #[serde(default)]
+    last_modified_time: i64,
+}
+
+impl BucketStat {
+    pub fn new(
+        storage: u64,
+        object_count: u64,
+        multipart_upload_count: u64,
+        live_channel_count: u64,
+        last_modified_time: i64,
+    ) -> Self {
+        BucketStat {
+            storage,
+            object_count,
+            multipart_upload_count,
+            live_channel_count,
+            last_modified_time,
+        }
+    }
+
+    pub fn storage(&self) -> u64 {
+        self.storage
+    }
+
+    pub fn object_count(&self) -> u64 {
+        self.object_count
+    }
+
+    pub fn multipart_upload_count(&self) -> u64 {
+        self.multipart_upload_count
+    }
+
+    pub fn live_channel_count(&self) -> u64 {
+        self.live_channel_count
+    }
+
+    pub fn last_modified_time(&self) -> i64 {
+        self.last_modified_time
+    }
+}
+
+// Change between two consecutive snapshots, negative when usage shrank.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct BucketStatDelta {
+    storage: i64,
+    object_count: i64,
+    multipart_upload_count: i64,
+}
+
+impl BucketStatDelta {
+    pub fn between(prev: &BucketStat, cur: &BucketStat) -> Self {
+        let diff = |a: u64, b: u64| b as i64 - a as i64;
+        BucketStatDelta {
+            storage: diff(prev.storage, cur.storage),
+            object_count: diff(prev.object_count, cur.object_count),
+            multipart_upload_count: diff(prev.multipart_upload_count, cur.multipart_upload_count),
+        }
+    }
+
+    pub fn storage(&self) -> i64 {
+        self.storage
+    }
+
+    pub fn object_count(&self) -> i64 {
+        self.object_count
+    }
+
+    pub fn multipart_upload_count(&self) -> i64 {
+        self.multipart_upload_count
+    }
+
+    pub fn is_zero(&self) -> bool {
+        *self == BucketStatDelta::default()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BucketStatUpdate {
+    stat: BucketStat,
+    delta: Option<BucketStatDelta>,
+}
+
+impl BucketStatUpdate {
+    pub fn stat(&self) -> &BucketStat {
+        &self.stat
+    }
+
+    // Against the last successful poll, None for the first one.
+    pub fn delta(&self) -> Option<BucketStatDelta> {
+        self.delta
+    }
+}
+
+impl OSS {
+    // https://help.aliyun.com/document_detail/426056.html
+    pub async fn get_bucket_stat(&self) -> Result<BucketStat, Error> {
+        self.with_context("get_bucket_stat", "", async {
+            let params = [("stat", Some(String::new()))];
+            let resp = self.signed_get("", &params, HeaderMap::new()).await?;
+            Ok(from_str(&self.xml_text(resp).await?)?)
+        })
+        .await
+    }
+
+    // Polls `get_bucket_stat` every `interval`, starting right away. A failed
+    // poll yields its error and polling carries on, the next delta is then
+    // taken against the last successful snapshot. The stream never ends.
+    pub fn watch_bucket_stat(
+        &self,
+        interval: Duration,
+    ) -> impl Stream<Item = Result<BucketStatUpdate, Error>> + '_ {
+        stream::unfold((None, true), move |(prev, first)| async move {
+            if !first {
+                rt::sleep(interval).await;
+            }
+            let update = match self.get_bucket_stat().await {
+                Ok(stat) => stat,
+                Err(e) => return Some((Err(e), (prev, false))),
+            };
+            let delta = prev
+                .as_ref()
+                .map(|prev| BucketStatDelta::between(prev, &update));
+            let next = Some(update.clone());
+            Some((
+                Ok(BucketStatUpdate {
+                    stat: update,
+                    delta,
+                }),
+                (next, false),
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bucket_stat() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<BucketStat>
+  <Storage>1600</Storage>
+  <ObjectCount>230</ObjectCount>
+  <MultipartUploadCount>40</MultipartUploadCount>
+  <LiveChannelCount>4</LiveChannelCount>
+  <LastModifiedTime>1643341269</LastModifiedTime>
+  <StandardStorage>430</StandardStorage>
+  <StandardObjectCount>66</StandardObjectCount>
+</BucketStat>"#;
+        let stat: BucketStat = from_str(xml).unwrap();
+        assert_eq!(stat, BucketStat::new(1600, 230, 40, 4, 1643341269));
+    }
+
+    #[test]
+    fn test_bucket_stat_delta() {
+        let prev = BucketStat::new(1600, 230, 40, 4, 1643341269);
+        let cur = BucketStat::new(1000, 250, 40, 4, 1643344869);
+        let delta = BucketStatDelta::between(&prev, &cur);
+        assert_eq!(delta.storage(), -600);
+        assert_eq!(delta.object_count(), 20);
+        assert_eq!(delta.multipart_upload_count(), 0);
+        assert!(!delta.is_zero());
+        assert!(BucketStatDelta::between(&cur, &cur).is_zero());
+    }
+}
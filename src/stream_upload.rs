@@ -0,0 +1,312 @@
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use reqwest::header::{HeaderMap, CONTENT_LENGTH, ETAG};
+use reqwest::Method;
+use std::collections::HashMap;
+use std::io;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio_util::io::ReaderStream;
+
+use crate::cdn::etag;
+use crate::errors::{Error, ObjectError};
+use crate::object::PutObjectResult;
+use crate::oss::{CompleteMultipartUpload, Part, OSS};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::utils::stream_body;
+use crate::utils::{insert_traffic_limit, to_headers};
+
+// Every part but the last has to be at least this large.
+const MIN_PART_SIZE: u64 = 100 * 1024;
+
+#[derive(Clone, Debug, Default)]
+pub struct PutObjectOptions {
+    // Extra request headers, e.g. Content-Type or x-oss-meta-*.
+    pub headers: HashMap<String, String>,
+    // Has OSS throttle the upload to this many bits per second, between
+    // 819200 (100 KB/s) and 838860800 (100 MB/s).
+    pub traffic_limit: Option<u64>,
+    // Overrides the client's `OSSBuilder::timeout`.
+    pub timeout: Option<Duration>,
+}
+
+impl OSS {
+    // Uploads `len` bytes read from `reader` with a single PutObject, sending
+    // them as they are read instead of collecting them first. `len` has to be
+    // exact, OSS rejects a body that ends early or runs long.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn put_object_from_reader<R, S>(
+        &self,
+        reader: R,
+        len: u64,
+        object: S,
+        options: &PutObjectOptions,
+    ) -> Result<PutObjectResult, Error>
+    where
+        R: AsyncRead + Send + 'static,
+        S: AsRef<str>,
+    {
+        self.put_object_body(
+            "put_object_from_reader",
+            ReaderStream::new(reader),
+            len,
+            object.as_ref(),
+            options,
+        )
+        .await
+    }
+
+    // Like `put_object_from_reader`, for data produced as a stream of chunks.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub async fn put_object_from_stream<St, S>(
+        &self,
+        stream: St,
+        len: u64,
+        object: S,
+        options: &PutObjectOptions,
+    ) -> Result<PutObjectResult, Error>
+    where
+        St: Stream<Item = Bytes> + Send + 'static,
+        S: AsRef<str>,
+    {
+        self.put_object_body(
+            "put_object_from_stream",
+            stream.map(Ok::<_, io::Error>),
+            len,
+            object.as_ref(),
+            options,
+        )
+        .await
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn put_object_body<St>(
+        &self,
+        operation: &str,
+        stream: St,
+        len: u64,
+        object: &str,
+        options: &PutObjectOptions,
+    ) -> Result<PutObjectResult, Error>
+    where
+        St: Stream<Item = io::Result<Bytes>> + Send + 'static,
+    {
+        self.with_context(operation, object, async {
+            let mut headers = to_headers(options.headers.clone())?;
+            headers.insert(CONTENT_LENGTH, len.into());
+            insert_traffic_limit(&mut headers, options.traffic_limit)?;
+            let crc = self.body_crc();
+            let body = stream_body(
+                stream,
+                crc.clone(),
+                self.progress(object, len),
+                self.rate_limiter(),
+            );
+            let request = self.signed_request_body(Method::PUT, object, &[], headers, Some(body));
+            let resp = match options.timeout {
+                Some(timeout) => self.with_timeout(timeout, request).await?,
+                None => request.await?,
+            };
+            if resp.status().is_success() {
+                if let Some(crc) = crc {
+                    let crc = crc.lock().unwrap().value();
+                    self.check_crc64(crc, resp.headers())?;
+                }
+                self.invalidate(object, etag(resp.headers())).await?;
+                Ok(PutObjectResult::from_headers(resp.headers()))
+            } else {
+                Err(self
+                    .api_error(resp, |reason| {
+                        Error::Object(ObjectError::PutError {
+                            msg: format!("can not put object, reason: {:?}", reason),
+                        })
+                    })
+                    .await)
+            }
+        })
+        .await
+    }
+
+    // Uploads everything `reader` yields until EOF, for sources whose length
+    // is not known up front such as stdin or a pipe from `pg_dump`. Input is
+    // buffered one part of `part_size` bytes at a time; when it ends within
+    // the first part a single PutObject is sent, otherwise a multipart upload
+    // that finishes with whatever short part is left.
+    pub async fn upload_from_reader<S, R, H>(
+        &self,
+        object_name: S,
+        mut reader: R,
+        part_size: u64,
+        headers: H,
+    ) -> Result<(), Error>
+    where
+        S: AsRef<str>,
+        R: AsyncRead + Unpin,
+        H: Into<Option<HashMap<S, S>>>,
+    {
+        let object_name = object_name.as_ref();
+        self.with_context("upload_from_reader", object_name, async {
+            if part_size < MIN_PART_SIZE {
+                return Err(Error::E(format!(
+                    "part size {} is below the minimum of {}",
+                    part_size, MIN_PART_SIZE
+                )));
+            }
+            let headers = headers.into();
+            let first = read_part(&mut reader, part_size).await?;
+            if (first.len() as u64) < part_size {
+                let mut headers = match headers {
+                    Some(h) => to_headers(h)?,
+                    None => HeaderMap::new(),
+                };
+                self.insert_content_md5(&mut headers, &first)?;
+                let crc = self.buffer_crc(&first);
+                self.throttle(first.len() as u64).await;
+                let resp = self
+                    .signed_request(Method::PUT, object_name, &[], headers, first)
+                    .await?;
+                return if resp.status().is_success() {
+                    if let Some(crc) = crc {
+                        self.check_crc64(crc, resp.headers())?;
+                    }
+                    self.invalidate(object_name, etag(resp.headers())).await
+                } else {
+                    Err(self
+                        .api_error(resp, |reason| {
+                            Error::Object(ObjectError::PutError {
+                                msg: format!("can not put object, reason: {:?}", reason),
+                            })
+                        })
+                        .await)
+                };
+            }
+
+            let upload_id = self.initiate_multipart_upload(object_name, headers).await?;
+            let parts = async {
+                let mut parts = Vec::new();
+                let mut buf = first;
+                while !buf.is_empty() {
+                    let number = parts.len() as u64 + 1;
+                    let part = self
+                        .upload_part_bytes(object_name, &upload_id, number, buf)
+                        .await?;
+                    parts.push(part);
+                    buf = read_part(&mut reader, part_size).await?;
+                }
+                Ok::<_, Error>(parts)
+            }
+            .await;
+            let parts = match parts {
+                Ok(parts) => parts,
+                Err(e) => return Err(self.abort_after(object_name, upload_id, e).await),
+            };
+            self.complete_multipart_upload(
+                object_name,
+                upload_id,
+                CompleteMultipartUpload::new(parts),
+                None::<HashMap<&str, &str>>,
+            )
+            .await
+            .map(|_| ())
+        })
+        .await
+    }
+
+    // https://help.aliyun.com/document_detail/31993.html
+    pub(crate) async fn upload_part_bytes(
+        &self,
+        object_name: &str,
+        upload_id: &str,
+        part_number: u64,
+        buf: Vec<u8>,
+    ) -> Result<Part, Error> {
+        let params = [
+            ("partNumber", Some(part_number.to_string())),
+            ("uploadId", Some(upload_id.to_owned())),
+        ];
+        let size = buf.len() as u64;
+        let crc = self.buffer_crc(&buf);
+        let mut headers = HeaderMap::new();
+        self.insert_content_md5(&mut headers, &buf)?;
+        self.throttle(size).await;
+        let resp = self
+            .signed_request(Method::PUT, object_name, &params, headers, buf)
+            .await?;
+        if resp.status().is_success() {
+            let etag = resp
+                .headers()
+                .get(ETAG)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_owned())
+                .ok_or_else(|| Error::E("upload part response has no ETag".to_owned()))?;
+            let part = Part::new(part_number, etag);
+            match crc {
+                Some(crc) => {
+                    self.check_crc64(crc, resp.headers())?;
+                    Ok(part.with_crc64(crc, size))
+                }
+                None => Ok(part),
+            }
+        } else {
+            Err(self
+                .api_error(resp, |reason| {
+                    Error::Object(ObjectError::PutError {
+                        msg: format!("can not upload part, reason: {:?}", reason),
+                    })
+                })
+                .await)
+        }
+    }
+}
+
+// Reads up to `part_size` bytes, fewer only at EOF.
+async fn read_part<R: AsyncRead + Unpin>(reader: &mut R, part_size: u64) -> io::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    reader.take(part_size).read_to_end(&mut buf).await?;
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_part() {
+        let data = vec![7u8; 250];
+        let mut reader = &data[..];
+        assert_eq!(read_part(&mut reader, 100).await.unwrap().len(), 100);
+        assert_eq!(read_part(&mut reader, 100).await.unwrap().len(), 100);
+        assert_eq!(read_part(&mut reader, 100).await.unwrap().len(), 50);
+        assert!(read_part(&mut reader, 100).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_put_object_from_stream_fails_on_bad_host() {
+        let oss_instance = OSS::new(
+            "xxx".to_string(),
+            "xxx".to_string(),
+            "invalid host".to_string(),
+            "xxx".to_string(),
+        );
+        let chunks = futures::stream::iter(vec![Bytes::from_static(b"a,b\n")]);
+        let err = oss_instance
+            .put_object_from_stream(chunks, 4, "data.csv", &PutObjectOptions::default())
+            .await
+            .unwrap_err();
+        assert_eq!(err.context().unwrap().operation(), "put_object_from_stream");
+    }
+
+    #[tokio::test]
+    async fn test_upload_from_reader_rejects_small_parts() {
+        let oss_instance = OSS::new(
+            "xxx".to_string(),
+            "xxx".to_string(),
+            "xxx.aliyuncs.com".to_string(),
+            "xxx".to_string(),
+        );
+        let result = oss_instance
+            .upload_from_reader("dump.sql", &b"select 1;"[..], 1024, None)
+            .await;
+        assert!(result.is_err());
+    }
+}
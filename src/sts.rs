@@ -0,0 +1,321 @@
+use chrono::prelude::*;
+use serde_derive::Deserialize;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use crate::auth::hmac_sha1_base64;
+use crate::credentials::Credentials;
+use crate::errors::Error;
+use crate::oss::OSS;
+use crate::utils::url_encode;
+
+const STS_ENDPOINT: &str = "https://sts.aliyuncs.com";
+
+// A least-privilege RAM policy granting read access to a single object,
+// meant to be passed as the `Policy` parameter of STS AssumeRole so the
+// minted credentials can do nothing else.
+// https://help.aliyun.com/document_detail/100680.html
+#[derive(Clone, Debug, Default)]
+pub struct ReadObjectPolicy {
+    pub bucket: String,
+    pub key: String,
+    // CIDR blocks or addresses allowed to use the credentials, e.g. "203.0.113.7/32".
+    pub source_ips: Vec<String>,
+    // Referer patterns, `*` wildcards allowed, e.g. "https://example.com/*".
+    pub referers: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl ReadObjectPolicy {
+    pub fn new<S>(bucket: S, key: S) -> Self
+    where
+        S: Into<String>,
+    {
+        ReadObjectPolicy {
+            bucket: bucket.into(),
+            key: key.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut condition = Map::new();
+        if !self.source_ips.is_empty() {
+            condition.insert(
+                "IpAddress".to_owned(),
+                json!({ "acs:SourceIp": self.source_ips }),
+            );
+        }
+        if !self.referers.is_empty() {
+            condition.insert(
+                "StringLike".to_owned(),
+                json!({ "acs:Referer": self.referers }),
+            );
+        }
+        if let Some(expires_at) = self.expires_at {
+            condition.insert(
+                "DateLessThan".to_owned(),
+                json!({
+                    "acs:CurrentTime": expires_at.to_rfc3339_opts(SecondsFormat::Secs, true)
+                }),
+            );
+        }
+
+        let mut statement = json!({
+            "Effect": "Allow",
+            "Action": ["oss:GetObject"],
+            "Resource": [format!("acs:oss:*:*:{}/{}", self.bucket, self.key)],
+        });
+        if !condition.is_empty() {
+            statement["Condition"] = Value::Object(condition);
+        }
+        json!({ "Version": "1", "Statement": [statement] }).to_string()
+    }
+}
+
+// Parameters of an STS AssumeRole call.
+// https://help.aliyun.com/document_detail/371864.html
+#[derive(Clone, Debug)]
+pub struct AssumeRoleOptions {
+    // e.g. "acs:ram::1234567890123456:role/oss-upload".
+    pub role_arn: String,
+    // Shows up in the audit logs of requests made with the credentials.
+    pub session_name: String,
+    // From 15 minutes up to the role's maximum session duration.
+    pub duration: Duration,
+    // Narrows the role's permissions further, e.g. `ReadObjectPolicy::to_json`.
+    pub policy: Option<String>,
+}
+
+impl AssumeRoleOptions {
+    pub fn new<S>(role_arn: S, session_name: S) -> Self
+    where
+        S: Into<String>,
+    {
+        AssumeRoleOptions {
+            role_arn: role_arn.into(),
+            session_name: session_name.into(),
+            duration: Duration::from_secs(3600),
+            policy: None,
+        }
+    }
+}
+
+impl OSS {
+    // Mints temporary credentials for `options.role_arn` with this client's
+    // credentials, which have to belong to a RAM user allowed to assume it.
+    pub async fn assume_role(&self, options: &AssumeRoleOptions) -> Result<Credentials, Error> {
+        self.with_context("assume_role", "", async {
+            let credentials = self.credentials().await?;
+            let query = assume_role_query(&credentials, options, &nonce(), self.now());
+            let resp = self
+                .send(self.client.get(format!("{}/?{}", STS_ENDPOINT, query)))
+                .await?;
+            if !resp.status().is_success() {
+                return Err(self
+                    .api_error(resp, |reason| {
+                        Error::E(format!(
+                            "can not assume role {}, reason: {:?}",
+                            options.role_arn, reason
+                        ))
+                    })
+                    .await);
+            }
+            parse_assume_role(&resp.text().await?)
+        })
+        .await
+    }
+}
+
+// Unique per call within the process, STS rejects a reused nonce.
+fn nonce() -> String {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    format!(
+        "{}-{}-{}",
+        Utc::now().timestamp_nanos_opt().unwrap_or_default(),
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+// RPC style signature, the sorted parameters signed with HMAC-SHA1.
+// https://help.aliyun.com/document_detail/315526.html
+fn assume_role_query(
+    credentials: &Credentials,
+    options: &AssumeRoleOptions,
+    nonce: &str,
+    now: DateTime<Utc>,
+) -> String {
+    let mut params = BTreeMap::new();
+    params.insert("Action", "AssumeRole".to_owned());
+    params.insert("Version", "2015-04-01".to_owned());
+    params.insert("Format", "JSON".to_owned());
+    params.insert("AccessKeyId", credentials.key_id().to_owned());
+    params.insert("SignatureMethod", "HMAC-SHA1".to_owned());
+    params.insert("SignatureVersion", "1.0".to_owned());
+    params.insert("SignatureNonce", nonce.to_owned());
+    params.insert("Timestamp", now.to_rfc3339_opts(SecondsFormat::Secs, true));
+    params.insert("RoleArn", options.role_arn.clone());
+    params.insert("RoleSessionName", options.session_name.clone());
+    params.insert("DurationSeconds", options.duration.as_secs().to_string());
+    if let Some(policy) = &options.policy {
+        params.insert("Policy", policy.clone());
+    }
+    if let Some(token) = credentials.security_token() {
+        params.insert("SecurityToken", token.to_owned());
+    }
+    let query = params
+        .iter()
+        .map(|(k, v)| format!("{}={}", url_encode(k), url_encode(v)))
+        .collect::<Vec<_>>()
+        .join("&");
+    let sign_str = format!("GET&%2F&{}", url_encode(&query));
+    let signature = hmac_sha1_base64(&format!("{}&", credentials.key_secret()), &sign_str);
+    format!("{}&Signature={}", query, url_encode(&signature))
+}
+
+fn parse_assume_role(body: &str) -> Result<Credentials, Error> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct AssumeRoleResponse {
+        credentials: StsCredentials,
+    }
+
+    #[derive(Deserialize)]
+    #[serde(rename_all = "PascalCase")]
+    struct StsCredentials {
+        access_key_id: String,
+        access_key_secret: String,
+        security_token: String,
+        expiration: String,
+    }
+
+    let resp: AssumeRoleResponse = serde_json::from_str(body)
+        .map_err(|e| Error::E(format!("invalid AssumeRole response: {}", e)))?;
+    let c = resp.credentials;
+    let expiration = DateTime::parse_from_rfc3339(&c.expiration)
+        .map_err(|e| Error::E(format!("invalid Expiration {:?}: {}", c.expiration, e)))?;
+    Ok(
+        Credentials::new(c.access_key_id, c.access_key_secret, Some(c.security_token))
+            .with_expiration(expiration.with_timezone(&Utc)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{BoxFuture, HttpTransport};
+    use reqwest::{Request, Response};
+
+    #[test]
+    fn test_read_object_policy() {
+        let mut policy = ReadObjectPolicy::new("examplebucket", "photos/a.png");
+        policy.source_ips = vec!["203.0.113.7/32".to_owned()];
+        policy.referers = vec!["https://example.com/*".to_owned()];
+        policy.expires_at = Some(Utc.with_ymd_and_hms(2026, 10, 15, 12, 0, 0).unwrap());
+
+        let value: Value = serde_json::from_str(&policy.to_json()).unwrap();
+        let statement = &value["Statement"][0];
+        assert_eq!(value["Version"], "1");
+        assert_eq!(statement["Action"][0], "oss:GetObject");
+        assert_eq!(
+            statement["Resource"][0],
+            "acs:oss:*:*:examplebucket/photos/a.png"
+        );
+        assert_eq!(
+            statement["Condition"]["IpAddress"]["acs:SourceIp"][0],
+            "203.0.113.7/32"
+        );
+        assert_eq!(
+            statement["Condition"]["StringLike"]["acs:Referer"][0],
+            "https://example.com/*"
+        );
+        assert_eq!(
+            statement["Condition"]["DateLessThan"]["acs:CurrentTime"],
+            "2026-10-15T12:00:00Z"
+        );
+
+        let value: Value =
+            serde_json::from_str(&ReadObjectPolicy::new("b", "k").to_json()).unwrap();
+        assert!(value["Statement"][0].get("Condition").is_none());
+    }
+
+    // Expected signature computed independently from the RPC signature
+    // documentation.
+    #[test]
+    fn test_assume_role_query() {
+        let credentials = Credentials::new("id".to_owned(), "secret".to_owned(), None);
+        let mut options = AssumeRoleOptions::new("acs:ram::123:role/upload", "app");
+        options.policy = Some(r#"{"Version":"1"}"#.to_owned());
+        let now = Utc.with_ymd_and_hms(2023, 11, 14, 22, 13, 20).unwrap();
+        let query = assume_role_query(&credentials, &options, "nonce", now);
+        assert!(query.starts_with("AccessKeyId=id&Action=AssumeRole&DurationSeconds=3600&"));
+        assert!(query.contains("&Policy=%7B%22Version%22%3A%221%22%7D&"));
+        assert!(query.contains("&Timestamp=2023-11-14T22%3A13%3A20Z&"));
+        assert!(query.ends_with("&Signature=pu1w2Hx%2B9AaUWbobioSP5y56r8Q%3D"));
+    }
+
+    #[test]
+    fn test_parse_assume_role() {
+        let body = r#"{
+  "RequestId": "964F3A4A-0DC9-4BE8-B2DE-8F3B0B47D1A8",
+  "AssumedRoleUser": {
+    "AssumedRoleId": "34458433936495****:alice",
+    "Arn": "acs:ram::123456789012****:role/test-role/alice"
+  },
+  "Credentials": {
+    "SecurityToken": "********",
+    "Expiration": "2015-04-09T11:52:19Z",
+    "AccessKeySecret": "wyLTSmsyPGP1ohvvw8xYgB29dlGI8KMiH2pK****",
+    "AccessKeyId": "STS.L4aBSCSJVMuKg5U1****"
+  }
+}"#;
+        let c = parse_assume_role(body).unwrap();
+        assert_eq!(c.key_id(), "STS.L4aBSCSJVMuKg5U1****");
+        assert_eq!(c.security_token(), Some("********"));
+        assert_eq!(
+            c.expiration().unwrap().to_rfc3339(),
+            "2015-04-09T11:52:19+00:00"
+        );
+        assert!(parse_assume_role(r#"{"Code":"NoPermission"}"#).is_err());
+    }
+
+    #[derive(Debug)]
+    struct Denied;
+
+    impl HttpTransport for Denied {
+        fn execute(&self, request: Request) -> BoxFuture<'_, Result<Response, Error>> {
+            assert!(request.url().as_str().starts_with(STS_ENDPOINT));
+            let body = format!(
+                r#"{{"Code":"NoPermission","Message":"{}"}}"#,
+                "x".repeat(4096)
+            );
+            let resp = http::Response::builder().status(403).body(body).unwrap();
+            Box::pin(async move { Ok(Response::from(resp)) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_assume_role_error_is_bounded() {
+        let oss_instance = OSS::builder(
+            "id".to_owned(),
+            "secret".to_owned(),
+            "oss-cn-hangzhou.aliyuncs.com".to_owned(),
+            "examplebucket".to_owned(),
+        )
+        .error_body_limit(64)
+        .transport(Denied)
+        .build();
+
+        let err = oss_instance
+            .assume_role(&AssumeRoleOptions::new("acs:ram::123:role/upload", "app"))
+            .await
+            .unwrap_err();
+        let msg = err.to_string();
+        assert!(msg.contains("can not assume role acs:ram::123:role/upload"));
+        assert!(msg.contains("NoPermission"));
+        assert!(msg.len() < 512, "{}", msg);
+    }
+}
@@ -0,0 +1,78 @@
+use reqwest::header::HeaderMap;
+use reqwest::Method;
+
+use crate::errors::{Error, ObjectError};
+use crate::oss::OSS;
+use crate::utils::{url_decode, url_encode};
+
+const SYMLINK_TARGET: &str = "x-oss-symlink-target";
+
+impl OSS {
+    // Creates `symlink_name` pointing at `target_object` in the same bucket.
+    // https://help.aliyun.com/document_detail/45126.html
+    pub async fn put_symlink<S>(&self, symlink_name: S, target_object: S) -> Result<(), Error>
+    where
+        S: AsRef<str>,
+    {
+        let symlink_name = symlink_name.as_ref();
+        self.with_context("put_symlink", symlink_name, async {
+            let mut headers = HeaderMap::new();
+            headers.insert(SYMLINK_TARGET, url_encode(target_object.as_ref()).parse()?);
+            let params = [("symlink", Some(String::new()))];
+            let resp = self
+                .signed_request(Method::PUT, symlink_name, &params, headers, Vec::new())
+                .await?;
+            if resp.status().is_success() {
+                Ok(())
+            } else {
+                Err(self
+                    .api_error(resp, |reason| {
+                        Error::Object(ObjectError::PutError {
+                            msg: format!("can not put symlink, reason: {:?}", reason),
+                        })
+                    })
+                    .await)
+            }
+        })
+        .await
+    }
+
+    // Returns the key `symlink_name` points at.
+    // https://help.aliyun.com/document_detail/45146.html
+    pub async fn get_symlink<S>(&self, symlink_name: S) -> Result<String, Error>
+    where
+        S: AsRef<str>,
+    {
+        let symlink_name = symlink_name.as_ref();
+        self.with_context("get_symlink", symlink_name, async {
+            let params = [("symlink", Some(String::new()))];
+            let resp = self
+                .signed_get(symlink_name, &params, HeaderMap::new())
+                .await?;
+            symlink_target(resp.headers())
+        })
+        .await
+    }
+}
+
+fn symlink_target(headers: &HeaderMap) -> Result<String, Error> {
+    let target = headers
+        .get(SYMLINK_TARGET)
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| Error::E(format!("missing {} header", SYMLINK_TARGET)))?;
+    Ok(url_decode(target))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symlink_target() {
+        let mut headers = HeaderMap::new();
+        assert!(symlink_target(&headers).is_err());
+        let target = "dir/中文 name.txt";
+        headers.insert(SYMLINK_TARGET, url_encode(target).parse().unwrap());
+        assert_eq!(symlink_target(&headers).unwrap(), target);
+    }
+}
@@ -0,0 +1,321 @@
+use chrono::{DateTime, Utc};
+use md5::{Digest, Md5};
+use reqwest::header::{CONTENT_LENGTH, ETAG, LAST_MODIFIED};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::bucket::Object;
+use crate::bulk::{local_keys, run_bulk, BulkOptions, BulkResult};
+use crate::errors::Error;
+use crate::object::HeadObjectResult;
+use crate::oss::OSS;
+use crate::rt::{self, AsyncReadExt};
+use crate::utils::Crc64;
+
+// How a local file is compared against its remote object to decide whether
+// it has to be uploaded again. A size mismatch always counts as a change.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SyncPolicy {
+    // Changed when the local mtime is later than LastModified by more than
+    // `tolerance`, to absorb clock skew and coarse filesystem timestamps.
+    Mtime { tolerance: Duration },
+    // Changed when the MD5 of the file differs from the ETag. ETags of
+    // multipart and appendable objects are not MD5s, so those always differ.
+    Etag,
+    // Changed when the CRC64 of the file differs from x-oss-hash-crc64ecma,
+    // or the object has no CRC64.
+    Crc64,
+}
+
+impl Default for SyncPolicy {
+    fn default() -> Self {
+        SyncPolicy::Mtime {
+            tolerance: Duration::from_secs(2),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RemoteState {
+    size: u64,
+    last_modified: DateTime<Utc>,
+    etag: String,
+    crc64: Option<u64>,
+}
+
+impl RemoteState {
+    pub fn new(size: u64, last_modified: DateTime<Utc>, etag: String, crc64: Option<u64>) -> Self {
+        RemoteState {
+            size,
+            last_modified,
+            etag,
+            crc64,
+        }
+    }
+
+    // Listings carry no CRC64, use `from_head` for `SyncPolicy::Crc64`.
+    pub fn from_object(object: &Object) -> Result<Self, Error> {
+        Ok(RemoteState::new(
+            object.size(),
+            object.last_modified_time()?,
+            object.etag().to_owned(),
+            None,
+        ))
+    }
+
+    pub fn from_head(head: &HeadObjectResult) -> Result<Self, Error> {
+        let header = |name: &str| {
+            head.headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .ok_or_else(|| Error::E(format!("missing {} header", name)))
+        };
+        let size = header(CONTENT_LENGTH.as_str())?
+            .parse()
+            .map_err(|_| Error::E("invalid Content-Length".to_owned()))?;
+        let last_modified = DateTime::parse_from_rfc2822(header(LAST_MODIFIED.as_str())?)
+            .map_err(|e| Error::E(format!("invalid Last-Modified: {}", e)))?
+            .with_timezone(&Utc);
+        let crc64 = header("x-oss-hash-crc64ecma")
+            .ok()
+            .and_then(|v| v.parse().ok());
+        Ok(RemoteState::new(
+            size,
+            last_modified,
+            header(ETAG.as_str())?.to_owned(),
+            crc64,
+        ))
+    }
+
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub fn last_modified(&self) -> DateTime<Utc> {
+        self.last_modified
+    }
+
+    pub fn etag(&self) -> &str {
+        &self.etag
+    }
+
+    pub fn crc64(&self) -> Option<u64> {
+        self.crc64
+    }
+}
+
+impl OSS {
+    // Like `upload_dir`, but skips files whose object under `prefix` is
+    // unchanged according to `policy`. Succeeds with whether each file was
+    // uploaded.
+    pub async fn sync_dir<P, S>(
+        &self,
+        dir: P,
+        prefix: S,
+        policy: SyncPolicy,
+        options: BulkOptions,
+    ) -> Result<BulkResult<bool>, Error>
+    where
+        P: AsRef<Path>,
+        S: AsRef<str>,
+    {
+        let prefix = prefix.as_ref();
+        let (keys, paths) = local_keys(dir.as_ref(), prefix).await?;
+        let remote: HashMap<String, Object> = self
+            .list_all_objects(prefix)
+            .await?
+            .into_iter()
+            .map(|o| (o.key().to_owned(), o))
+            .collect();
+
+        let (paths, remote) = (&paths, &remote);
+        Ok(run_bulk(keys, options, |key| async move {
+            self.with_context("sync_dir", &key, async {
+                let path = &paths[&key];
+                if let Some(object) = remote.get(&key) {
+                    let state = match policy {
+                        SyncPolicy::Crc64 => {
+                            RemoteState::from_head(&self.head_object(&key, None, None).await?)?
+                        }
+                        _ => RemoteState::from_object(object)?,
+                    };
+                    if !needs_upload(path, &state, policy).await? {
+                        return Ok(false);
+                    }
+                }
+                let file = path.to_string_lossy();
+                self.put_object_from_file(
+                    file.as_ref(),
+                    key.as_str(),
+                    None::<HashMap<&str, &str>>,
+                    None,
+                )
+                .await?;
+                Ok(true)
+            })
+            .await
+        })
+        .await)
+    }
+}
+
+// Compares size and mtime only, without reading the file.
+pub fn is_newer(
+    local_size: u64,
+    local_modified: DateTime<Utc>,
+    remote: &RemoteState,
+    tolerance: Duration,
+) -> bool {
+    if local_size != remote.size() {
+        return true;
+    }
+    match (local_modified - remote.last_modified()).to_std() {
+        Ok(ahead) => ahead > tolerance,
+        // Local is older than the remote copy.
+        Err(_) => false,
+    }
+}
+
+// Whether the file at `path` differs from `remote` under `policy`.
+pub async fn needs_upload<P: AsRef<Path>>(
+    path: P,
+    remote: &RemoteState,
+    policy: SyncPolicy,
+) -> Result<bool, Error> {
+    let path = path.as_ref();
+    let metadata = rt::metadata(path).await?;
+    if metadata.len() != remote.size() {
+        return Ok(true);
+    }
+    match policy {
+        SyncPolicy::Mtime { tolerance } => {
+            let modified = DateTime::<Utc>::from(metadata.modified()?);
+            Ok(is_newer(metadata.len(), modified, remote, tolerance))
+        }
+        SyncPolicy::Etag => {
+            let etag = remote.etag().trim_matches('"');
+            if etag.len() != 32 {
+                return Ok(true);
+            }
+            let mut md5 = Md5::new();
+            hash_file(path, |buf| md5.update(buf)).await?;
+            Ok(!format!("{:x}", md5.finalize()).eq_ignore_ascii_case(etag))
+        }
+        SyncPolicy::Crc64 => match remote.crc64() {
+            Some(expected) => {
+                let mut crc = Crc64::new();
+                hash_file(path, |buf| crc.update(buf)).await?;
+                Ok(crc.value() != expected)
+            }
+            None => Ok(true),
+        },
+    }
+}
+
+async fn hash_file<F: FnMut(&[u8])>(path: &Path, mut f: F) -> Result<(), Error> {
+    let mut file = rt::open(path).await?;
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        f(&buf[..n]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bucket::Owner;
+    use chrono::TimeZone;
+    use reqwest::header::HeaderMap;
+
+    fn remote(size: u64, etag: &str, crc64: Option<u64>) -> RemoteState {
+        RemoteState::new(
+            size,
+            Utc.with_ymd_and_hms(2022, 12, 28, 10, 27, 41).unwrap(),
+            etag.to_owned(),
+            crc64,
+        )
+    }
+
+    #[test]
+    fn test_is_newer() {
+        let remote = remote(3, "", None);
+        let at = |secs| remote.last_modified() + chrono::Duration::seconds(secs);
+        let tolerance = Duration::from_secs(2);
+        assert!(!is_newer(3, at(-60), &remote, tolerance));
+        assert!(!is_newer(3, at(2), &remote, tolerance));
+        assert!(is_newer(3, at(3), &remote, tolerance));
+        assert!(is_newer(4, at(-60), &remote, tolerance));
+    }
+
+    #[test]
+    fn test_remote_state_from_listing_and_head() {
+        let object = Object::new(
+            "a".to_owned(),
+            "2022-12-28T10:27:41.000Z".to_owned(),
+            "\"900150983CD24FB0D6963F7D28E17F72\"".to_owned(),
+            3,
+            "Standard".to_owned(),
+            Owner::default(),
+        );
+        let from_object = RemoteState::from_object(&object).unwrap();
+        assert_eq!(
+            from_object.last_modified(),
+            remote(3, "", None).last_modified()
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_LENGTH, "3".parse().unwrap());
+        headers.insert(
+            LAST_MODIFIED,
+            "Wed, 28 Dec 2022 10:27:41 GMT".parse().unwrap(),
+        );
+        headers.insert(ETAG, object.etag().parse().unwrap());
+        headers.insert("x-oss-hash-crc64ecma", "42".parse().unwrap());
+        let from_head = RemoteState::from_head(&HeadObjectResult::from_headers(headers)).unwrap();
+        assert_eq!(from_head.crc64(), Some(42));
+        assert_eq!(from_head.last_modified(), from_object.last_modified());
+        assert_eq!(from_head.etag(), from_object.etag());
+    }
+
+    #[tokio::test]
+    async fn test_needs_upload_by_content() {
+        let path = std::env::temp_dir().join("oss-sdk-sync-test.txt");
+        tokio::fs::write(&path, b"abc").await.unwrap();
+
+        let etag = "\"900150983CD24FB0D6963F7D28E17F72\"";
+        assert!(
+            !needs_upload(&path, &remote(3, etag, None), SyncPolicy::Etag)
+                .await
+                .unwrap()
+        );
+        assert!(
+            needs_upload(&path, &remote(3, "\"0-1\"", None), SyncPolicy::Etag)
+                .await
+                .unwrap()
+        );
+
+        let mut crc = Crc64::new();
+        crc.update(b"abc");
+        let value = crc.value();
+        assert!(
+            !needs_upload(&path, &remote(3, "", Some(value)), SyncPolicy::Crc64)
+                .await
+                .unwrap()
+        );
+        assert!(needs_upload(&path, &remote(3, "", None), SyncPolicy::Crc64)
+            .await
+            .unwrap());
+        assert!(
+            needs_upload(&path, &remote(4, etag, None), SyncPolicy::Etag)
+                .await
+                .unwrap()
+        );
+
+        tokio::fs::remove_file(&path).await.unwrap();
+    }
+}
@@ -0,0 +1,127 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use crate::errors::Error;
+use crate::oss::OSS;
+use crate::rt::{self, JoinHandle};
+use crate::timeout::{request_timeout, with_request_timeout};
+use crate::trace;
+use crate::transport::MaybeSend;
+
+// Decrements the live task count when the task finishes or is aborted.
+struct TaskGuard(Arc<AtomicUsize>);
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl OSS {
+    // Number of background tasks spawned by this client (and its bucket
+    // handles) that have not finished yet.
+    pub fn active_tasks(&self) -> usize {
+        self.tasks().load(Ordering::SeqCst)
+    }
+
+    // Spawns an internal task named `oss-sdk:<name>` on the runtime in use,
+    // see `rt::spawn`.
+    pub(crate) fn spawn_named<F>(&self, name: &str, fut: F) -> JoinHandle<F::Output>
+    where
+        F: Future + MaybeSend + 'static,
+        F::Output: MaybeSend + 'static,
+    {
+        let tasks = self.tasks().clone();
+        tasks.fetch_add(1, Ordering::SeqCst);
+        let guard = TaskGuard(tasks);
+        let fut = async move {
+            let _guard = guard;
+            fut.await
+        };
+        rt::spawn(&format!("oss-sdk:{}", name), fut)
+    }
+
+    // Runs `fut`, one of the parts of a transfer or the pages of a listing,
+    // in a task of its own named like `spawn_named`. It keeps the timeout of
+    // an enclosing `with_timeout` and the current span, and is aborted when
+    // the returned future is dropped, as the part would be inline.
+    pub(crate) fn spawn_child<F, T>(&self, name: &str, fut: F) -> Child<T>
+    where
+        F: Future<Output = Result<T, Error>> + MaybeSend + 'static,
+        T: MaybeSend + 'static,
+    {
+        let fut = trace::in_current_span(with_request_timeout(request_timeout(), fut));
+        Child(self.spawn_named(name, fut))
+    }
+}
+
+pub(crate) struct Child<T>(JoinHandle<Result<T, Error>>);
+
+impl<T> Future for Child<T> {
+    type Output = Result<T, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.0)
+            .poll(cx)
+            .map(|joined| joined.and_then(|result| result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_active_tasks() {
+        let oss_instance = OSS::new(
+            "xxx".to_string(),
+            "xxx".to_string(),
+            "xxx.aliyuncs.com".to_string(),
+            "xxx".to_string(),
+        );
+        let (tx, rx) = tokio::sync::oneshot::channel::<()>();
+        let handle = oss_instance.spawn_named("test", async move {
+            let _ = rx.await;
+        });
+        assert_eq!(oss_instance.active_tasks(), 1);
+        assert_eq!(oss_instance.bucket_handle("other").active_tasks(), 1);
+        tx.send(()).unwrap();
+        handle.await.unwrap();
+        assert_eq!(oss_instance.active_tasks(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_spawn_child() {
+        let oss_instance = OSS::new(
+            "xxx".to_string(),
+            "xxx".to_string(),
+            "xxx.aliyuncs.com".to_string(),
+            "xxx".to_string(),
+        );
+        let timeout = Duration::from_secs(5);
+        let inherited = oss_instance
+            .with_timeout(timeout, async {
+                oss_instance
+                    .spawn_child("test", async { Ok(request_timeout()) })
+                    .await
+            })
+            .await;
+        assert_eq!(inherited.unwrap(), Some(timeout));
+
+        let child = oss_instance.spawn_child("test", future::pending::<Result<(), Error>>());
+        assert_eq!(oss_instance.active_tasks(), 1);
+        drop(child);
+        tokio::time::timeout(timeout, async {
+            while oss_instance.active_tasks() > 0 {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+}
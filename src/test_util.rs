@@ -0,0 +1,992 @@
+use bytes::Bytes;
+use chrono::{DateTime, SecondsFormat, Utc};
+use hyper::header::{CONTENT_LENGTH, CONTENT_TYPE, ETAG, HOST, LAST_MODIFIED, RANGE};
+use hyper::http::request::Parts;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use md5::{Digest, Md5};
+use reqwest::Client;
+use serde_xml_rs::from_str;
+use std::collections::{BTreeMap, HashMap};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+
+use crate::auth::verify_signature;
+use crate::errors::Error;
+use crate::oss::{CompleteMultipartUpload, OSSBuilder, OSS, RESOURCES};
+use crate::utils::{content_md5, url_decode, url_encode, Crc64};
+
+// Buckets are served as subdomains of this, which the clients handed out by
+// `FakeOss::builder` resolve to the fake.
+const DOMAIN: &str = "oss-test.local";
+
+// An in-memory OSS for tests, listening on a local port. It serves
+// PutObject, GetObject (with a single range), HeadObject, DeleteObject,
+// CopyObject, ListObjects and the multipart upload calls, UploadPartCopy
+// included, for any bucket, and rejects requests without a valid V1
+// signature for its keys. Every write creates a new version but only the
+// latest one is kept, to read or copy by version id. Anything else is
+// answered with NotImplemented. Runs on the tokio runtime it was started on
+// until dropped.
+#[derive(Debug)]
+pub struct FakeOss {
+    addr: SocketAddr,
+    state: Arc<State>,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+#[derive(Debug)]
+struct State {
+    key_id: String,
+    key_secret: String,
+    store: Mutex<Store>,
+    requests: AtomicU64,
+}
+
+#[derive(Debug, Default)]
+struct Store {
+    // By bucket and key, sorted for listing.
+    objects: BTreeMap<(String, String), StoredObject>,
+    uploads: HashMap<String, Upload>,
+    next_upload: u64,
+    next_version: u64,
+}
+
+impl Store {
+    // Stores `object` as a new version, only the latest of which is kept,
+    // and returns its version id.
+    fn put(&mut self, id: (String, String), mut object: StoredObject) -> String {
+        self.next_version += 1;
+        object.version_id = format!("{:032X}", self.next_version);
+        let version_id = object.version_id.clone();
+        self.objects.insert(id, object);
+        version_id
+    }
+}
+
+#[derive(Clone, Debug)]
+struct StoredObject {
+    data: Bytes,
+    etag: String,
+    content_type: Option<String>,
+    // The x-oss-meta-* headers it was written with.
+    metadata: BTreeMap<String, String>,
+    last_modified: DateTime<Utc>,
+    version_id: String,
+}
+
+#[derive(Debug)]
+struct Upload {
+    bucket: String,
+    key: String,
+    content_type: Option<String>,
+    metadata: BTreeMap<String, String>,
+    parts: BTreeMap<u64, (String, Bytes)>,
+}
+
+impl FakeOss {
+    pub async fn start() -> Result<FakeOss, Error> {
+        let state = Arc::new(State {
+            key_id: "fake-key-id".to_owned(),
+            key_secret: "fake-key-secret".to_owned(),
+            store: Mutex::new(Store::default()),
+            requests: AtomicU64::new(0),
+        });
+        let service_state = state.clone();
+        let make_service = make_service_fn(move |_| {
+            let state = service_state.clone();
+            async move { Ok::<_, Infallible>(service_fn(move |req| handle(state.clone(), req))) }
+        });
+        let server = Server::try_bind(&SocketAddr::from(([127, 0, 0, 1], 0)))
+            .map_err(|e| Error::E(format!("can not start fake OSS: {}", e)))?
+            .serve(make_service);
+        let addr = server.local_addr();
+        let (shutdown, stopped) = oneshot::channel::<()>();
+        tokio::spawn(server.with_graceful_shutdown(async {
+            let _ = stopped.await;
+        }));
+        Ok(FakeOss {
+            addr,
+            state,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    pub fn endpoint(&self) -> String {
+        format!("http://{}:{}", DOMAIN, self.addr.port())
+    }
+
+    pub fn key_id(&self) -> &str {
+        &self.state.key_id
+    }
+
+    pub fn key_secret(&self) -> &str {
+        &self.state.key_secret
+    }
+
+    // A builder for clients of `bucket` on this fake, to configure further.
+    pub fn builder(&self, bucket: &str) -> OSSBuilder {
+        let client = Client::builder()
+            .resolve(&format!("{}.{}", bucket, DOMAIN), self.addr)
+            .build()
+            .unwrap_or_default();
+        OSS::builder(
+            self.key_id().to_owned(),
+            self.key_secret().to_owned(),
+            self.endpoint(),
+            bucket.to_owned(),
+        )
+        .client(client)
+    }
+
+    pub fn oss(&self, bucket: &str) -> OSS {
+        self.builder(bucket).build()
+    }
+
+    // What is stored as `key` in `bucket`, to check what a test wrote.
+    pub fn object(&self, bucket: &str, key: &str) -> Option<Bytes> {
+        let store = self.state.store.lock().unwrap();
+        store
+            .objects
+            .get(&(bucket.to_owned(), key.to_owned()))
+            .map(|o| o.data.clone())
+    }
+
+    // Stores `data` as `key` in `bucket`, to set up what a test reads.
+    pub fn insert<B: Into<Bytes>>(&self, bucket: &str, key: &str, data: B) {
+        let data = data.into();
+        let object = StoredObject {
+            etag: etag(&data, None),
+            data,
+            content_type: None,
+            metadata: BTreeMap::new(),
+            last_modified: Utc::now(),
+            version_id: String::new(),
+        };
+        let mut store = self.state.store.lock().unwrap();
+        store.put((bucket.to_owned(), key.to_owned()), object);
+    }
+}
+
+impl Drop for FakeOss {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+async fn handle(state: Arc<State>, req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let request_id = format!(
+        "{:024X}",
+        state.requests.fetch_add(1, Ordering::Relaxed) + 1
+    );
+    let (parts, body) = req.into_parts();
+    let mut resp = match hyper::body::to_bytes(body).await {
+        Ok(body) => state.respond(&parts, body, &request_id),
+        Err(e) => error(
+            StatusCode::BAD_REQUEST,
+            "InvalidRequest",
+            &e.to_string(),
+            &request_id,
+        ),
+    };
+    if let Ok(id) = request_id.parse() {
+        resp.headers_mut().insert("x-oss-request-id", id);
+    }
+    Ok(resp)
+}
+
+impl State {
+    fn respond(&self, parts: &Parts, body: Bytes, request_id: &str) -> Response<Body> {
+        let host = header(parts, HOST.as_str()).unwrap_or_default();
+        let bucket = match host.split_once('.') {
+            Some((bucket, rest)) if rest.starts_with(DOMAIN) => bucket.to_owned(),
+            _ => {
+                return error(
+                    StatusCode::NOT_IMPLEMENTED,
+                    "NotImplemented",
+                    "only bucket requests are served",
+                    request_id,
+                )
+            }
+        };
+        let key = url_decode(parts.uri.path().trim_start_matches('/'));
+        let query: Vec<(String, Option<String>)> = parts
+            .uri
+            .query()
+            .unwrap_or_default()
+            .split('&')
+            .filter(|p| !p.is_empty())
+            .map(|p| match p.split_once('=') {
+                Some((k, v)) => (url_decode(k), Some(url_decode(v))),
+                None => (url_decode(p), None),
+            })
+            .collect();
+        let param = |name: &str| {
+            query
+                .iter()
+                .find(|(k, _)| k == name)
+                .map(|(_, v)| v.clone().unwrap_or_default())
+        };
+
+        let mut resources: Vec<&(String, Option<String>)> = query
+            .iter()
+            .filter(|(k, _)| RESOURCES.contains(&k.as_str()))
+            .collect();
+        resources.sort_by(|a, b| a.0.cmp(&b.0));
+        let resources = resources
+            .iter()
+            .map(|(k, v)| match v {
+                Some(v) => format!("{}={}", k, v),
+                None => k.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join("&");
+        let signed = verify_signature(
+            &self.key_id,
+            &self.key_secret,
+            parts.method.as_str(),
+            &bucket,
+            &key,
+            &resources,
+            &parts.headers,
+        );
+        if !signed {
+            return error(
+                StatusCode::FORBIDDEN,
+                "SignatureDoesNotMatch",
+                "The request signature we calculated does not match the signature you provided.",
+                request_id,
+            );
+        }
+        if let Some(md5) = header(parts, "content-md5") {
+            if md5 != content_md5(&body) {
+                return error(
+                    StatusCode::BAD_REQUEST,
+                    "InvalidDigest",
+                    "The Content-MD5 you specified is not valid.",
+                    request_id,
+                );
+            }
+        }
+
+        let mut store = self.store.lock().unwrap();
+        let method = &parts.method;
+        let source = match header(parts, "x-oss-copy-source") {
+            Some(source) => match copy_source(&store, source) {
+                Ok(object) => Some(object),
+                Err((code, message)) => {
+                    return error(StatusCode::NOT_FOUND, code, message, request_id)
+                }
+            },
+            None => None,
+        };
+        if *method == Method::GET && key.is_empty() && resources.is_empty() {
+            return list_objects(&store, &bucket, &param);
+        }
+        if key.is_empty() {
+            return not_implemented(request_id);
+        }
+        if *method == Method::POST && param("uploads").is_some() {
+            store.next_upload += 1;
+            let upload_id = format!("{:032X}", store.next_upload);
+            store.uploads.insert(
+                upload_id.clone(),
+                Upload {
+                    bucket: bucket.clone(),
+                    key: key.clone(),
+                    content_type: header(parts, CONTENT_TYPE.as_str()).map(|v| v.to_owned()),
+                    metadata: metadata(parts),
+                    parts: BTreeMap::new(),
+                },
+            );
+            return xml(format!(
+                "<InitiateMultipartUploadResult><Bucket>{}</Bucket><Key>{}</Key>\
+                 <UploadId>{}</UploadId></InitiateMultipartUploadResult>",
+                bucket,
+                url_encode(&key),
+                upload_id
+            ));
+        }
+        if let Some(upload_id) = param("uploadId") {
+            let upload = match store.uploads.get_mut(&upload_id) {
+                Some(upload) if upload.bucket == bucket && upload.key == key => upload,
+                _ => {
+                    return error(
+                        StatusCode::NOT_FOUND,
+                        "NoSuchUpload",
+                        "The specified upload does not exist.",
+                        request_id,
+                    )
+                }
+            };
+            return match *method {
+                Method::PUT => {
+                    let number = param("partNumber").and_then(|n| n.parse::<u64>().ok());
+                    let number = match number {
+                        Some(n) if (1..=10000).contains(&n) => n,
+                        _ => {
+                            return error(
+                                StatusCode::BAD_REQUEST,
+                                "InvalidArgument",
+                                "Part number must be an integer between 1 and 10000.",
+                                request_id,
+                            )
+                        }
+                    };
+                    let source = source.map(|object| {
+                        let len = object.data.len() as u64;
+                        let data = match header(parts, "x-oss-copy-source-range")
+                            .and_then(|r| range(r, len))
+                        {
+                            Some((start, end)) => {
+                                object.data.slice(start as usize..end as usize + 1)
+                            }
+                            None => object.data,
+                        };
+                        (data, object.version_id)
+                    });
+                    let copied = source.as_ref().map(|(_, version_id)| version_id.clone());
+                    let body = source.map_or(body, |(data, _)| data);
+                    let part_etag = etag(&body, None);
+                    let resp = stored(&part_etag, &body);
+                    upload.parts.insert(number, (part_etag.clone(), body));
+                    if let Some(source) = copied {
+                        return copy_result("CopyPartResult", &part_etag, &source, None);
+                    }
+                    resp.body(Body::empty()).unwrap()
+                }
+                Method::POST => {
+                    let complete = match complete(upload, &body) {
+                        Ok(object) => object,
+                        Err((code, message)) => {
+                            return error(StatusCode::BAD_REQUEST, code, message, request_id)
+                        }
+                    };
+                    store.uploads.remove(&upload_id);
+                    let mut resp = stored(&complete.etag, &complete.data)
+                        .header(CONTENT_TYPE, "application/xml");
+                    let body = format!(
+                        "<CompleteMultipartUploadResult><Bucket>{}</Bucket><Key>{}</Key>\
+                         <ETag>{}</ETag></CompleteMultipartUploadResult>",
+                        bucket,
+                        url_encode(&key),
+                        complete.etag
+                    );
+                    let version_id = store.put((bucket, key), complete);
+                    resp = resp.header("x-oss-version-id", version_id);
+                    resp.body(Body::from(body)).unwrap()
+                }
+                Method::DELETE => {
+                    store.uploads.remove(&upload_id);
+                    status(StatusCode::NO_CONTENT)
+                }
+                _ => not_implemented(request_id),
+            };
+        }
+        let version_id = param("versionId");
+        // Reading a version is the only subresource served past this point.
+        let versioned_read = matches!(*method, Method::GET | Method::HEAD)
+            && resources.split('&').all(|r| r.starts_with("versionId="));
+        if !resources.is_empty() && !versioned_read {
+            return not_implemented(request_id);
+        }
+
+        let id = (bucket, key);
+        match *method {
+            Method::PUT => {
+                let copied = source.as_ref().map(|s| s.version_id.clone());
+                let replace = header(parts, "x-oss-metadata-directive") == Some("REPLACE");
+                let object = match source {
+                    // COPY, the default, keeps the metadata of the source.
+                    Some(source) if !replace => StoredObject {
+                        last_modified: Utc::now(),
+                        ..source
+                    },
+                    source => {
+                        let data = source.map_or(body, |s| s.data);
+                        StoredObject {
+                            etag: etag(&data, None),
+                            data,
+                            content_type: header(parts, CONTENT_TYPE.as_str())
+                                .map(|v| v.to_owned()),
+                            metadata: metadata(parts),
+                            last_modified: Utc::now(),
+                            version_id: String::new(),
+                        }
+                    }
+                };
+                let resp = stored(&object.etag, &object.data);
+                let etag = object.etag.clone();
+                let version_id = store.put(id, object);
+                if let Some(source) = copied {
+                    return copy_result("CopyObjectResult", &etag, &source, Some(&version_id));
+                }
+                resp.header("x-oss-version-id", version_id)
+                    .body(Body::empty())
+                    .unwrap()
+            }
+            Method::DELETE => {
+                store.objects.remove(&id);
+                status(StatusCode::NO_CONTENT)
+            }
+            Method::GET | Method::HEAD => {
+                let object = match store.objects.get(&id) {
+                    Some(object) => object,
+                    None => {
+                        return error(
+                            StatusCode::NOT_FOUND,
+                            "NoSuchKey",
+                            "The specified key does not exist.",
+                            request_id,
+                        )
+                    }
+                };
+                if version_id.is_some_and(|v| v != object.version_id) {
+                    return error(
+                        StatusCode::NOT_FOUND,
+                        "NoSuchVersion",
+                        "The specified version does not exist.",
+                        request_id,
+                    );
+                }
+                let mut resp = stored(&object.etag, &object.data)
+                    .header(
+                        LAST_MODIFIED,
+                        object
+                            .last_modified
+                            .format("%a, %d %b %Y %T GMT")
+                            .to_string(),
+                    )
+                    .header("x-oss-object-type", "Normal")
+                    .header("x-oss-version-id", &object.version_id)
+                    .header(
+                        CONTENT_TYPE,
+                        object
+                            .content_type
+                            .as_deref()
+                            .unwrap_or("application/octet-stream"),
+                    );
+                for (k, v) in &object.metadata {
+                    resp = resp.header(k, v);
+                }
+                let len = object.data.len() as u64;
+                let mut data = object.data.clone();
+                if let Some((start, end)) =
+                    header(parts, RANGE.as_str()).and_then(|r| range(r, len))
+                {
+                    resp = resp
+                        .status(StatusCode::PARTIAL_CONTENT)
+                        .header("Content-Range", format!("bytes {}-{}/{}", start, end, len));
+                    data = data.slice(start as usize..end as usize + 1);
+                }
+                if *method == Method::HEAD {
+                    resp.header(CONTENT_LENGTH, data.len())
+                        .body(Body::empty())
+                        .unwrap()
+                } else {
+                    resp.body(Body::from(data)).unwrap()
+                }
+            }
+            _ => not_implemented(request_id),
+        }
+    }
+}
+
+// Joins the parts listed in a CompleteMultipartUpload body, which must have
+// been uploaded with those ETags and be in ascending order.
+fn complete(upload: &Upload, body: &[u8]) -> Result<StoredObject, (&'static str, &'static str)> {
+    let body = String::from_utf8_lossy(body);
+    let listed: CompleteMultipartUpload = from_str(&body)
+        .map_err(|_| ("MalformedXML", "The XML you provided was not well-formed."))?;
+    let mut data = Vec::new();
+    let mut last = 0;
+    for part in listed.parts() {
+        if part.part_number() <= last {
+            return Err((
+                "InvalidPartOrder",
+                "The list of parts was not in ascending order.",
+            ));
+        }
+        last = part.part_number();
+        match upload.parts.get(&part.part_number()) {
+            Some((etag, bytes)) if etag == part.etag() => data.extend_from_slice(bytes),
+            _ => {
+                return Err((
+                    "InvalidPart",
+                    "One or more of the specified parts could not be found.",
+                ))
+            }
+        }
+    }
+    let data = Bytes::from(data);
+    Ok(StoredObject {
+        etag: etag(&data, Some(listed.parts().len())),
+        data,
+        content_type: upload.content_type.clone(),
+        metadata: upload.metadata.clone(),
+        last_modified: Utc::now(),
+        version_id: String::new(),
+    })
+}
+
+fn list_objects(
+    store: &Store,
+    bucket: &str,
+    param: &dyn Fn(&str) -> Option<String>,
+) -> Response<Body> {
+    let prefix = param("prefix").unwrap_or_default();
+    let marker = param("marker").unwrap_or_default();
+    let delimiter = param("delimiter").unwrap_or_default();
+    let max_keys = param("max-keys")
+        .and_then(|m| m.parse().ok())
+        .unwrap_or(100usize);
+
+    let mut contents = String::new();
+    let mut prefixes: Vec<String> = Vec::new();
+    let mut count = 0;
+    let mut next_marker = None;
+    let keys = store
+        .objects
+        .iter()
+        .filter(|((b, k), _)| b == bucket && k.starts_with(&prefix) && *k > marker);
+    for ((_, key), object) in keys {
+        // Keys below a delimiter after the prefix are rolled up into one
+        // common prefix, listed once.
+        let common = match key[prefix.len()..].find(&delimiter) {
+            Some(i) if !delimiter.is_empty() => Some(&key[..prefix.len() + i + delimiter.len()]),
+            _ => None,
+        };
+        if let Some(common) = common {
+            if prefixes.last().map(|p| p.as_str()) == Some(common) {
+                continue;
+            }
+        }
+        if count == max_keys {
+            next_marker = Some(key.clone());
+            break;
+        }
+        count += 1;
+        match common {
+            Some(common) => prefixes.push(common.to_owned()),
+            None => {
+                contents += &format!(
+                    "<Contents><Key>{}</Key><LastModified>{}</LastModified><ETag>{}</ETag>\
+                 <Type>Normal</Type><Size>{}</Size><StorageClass>Standard</StorageClass>\
+                 <Owner><ID>0</ID><DisplayName>0</DisplayName></Owner></Contents>",
+                    url_encode(key),
+                    object.last_modified.format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+                    object.etag,
+                    object.data.len()
+                )
+            }
+        }
+    }
+    let prefixes: String = prefixes
+        .iter()
+        .map(|p| {
+            format!(
+                "<CommonPrefixes><Prefix>{}</Prefix></CommonPrefixes>",
+                url_encode(p)
+            )
+        })
+        .collect();
+    xml(format!(
+        "<ListBucketResult><Name>{}</Name><Prefix>{}</Prefix><Marker>{}</Marker>\
+         <MaxKeys>{}</MaxKeys><Delimiter>{}</Delimiter><EncodingType>url</EncodingType>\
+         <IsTruncated>{}</IsTruncated>{}{}{}</ListBucketResult>",
+        bucket,
+        url_encode(&prefix),
+        url_encode(&marker),
+        max_keys,
+        url_encode(&delimiter),
+        next_marker.is_some(),
+        next_marker
+            .map(|m| format!("<NextMarker>{}</NextMarker>", url_encode(&m)))
+            .unwrap_or_default(),
+        contents,
+        prefixes
+    ))
+}
+
+// The object named by an x-oss-copy-source header, `/bucket/key` with an
+// optional `?versionId=`.
+fn copy_source(store: &Store, source: &str) -> Result<StoredObject, (&'static str, &'static str)> {
+    let no_such_key = ("NoSuchKey", "The specified key does not exist.");
+    let (bucket, key) = source
+        .strip_prefix('/')
+        .and_then(|s| s.split_once('/'))
+        .ok_or(no_such_key)?;
+    let (key, version_id) = match key.split_once("?versionId=") {
+        Some((key, version_id)) => (key, Some(url_decode(version_id))),
+        None => (key, None),
+    };
+    let object = store
+        .objects
+        .get(&(bucket.to_owned(), url_decode(key)))
+        .ok_or(no_such_key)?;
+    match version_id {
+        Some(v) if v != object.version_id => {
+            Err(("NoSuchVersion", "The specified version does not exist."))
+        }
+        _ => Ok(object.clone()),
+    }
+}
+
+// The body of a CopyObject or UploadPartCopy response, `version_id` is the
+// one of the new object.
+fn copy_result(
+    root: &str,
+    etag: &str,
+    source_version_id: &str,
+    version_id: Option<&str>,
+) -> Response<Body> {
+    let mut resp = xml(format!(
+        "<{0}><LastModified>{1}</LastModified><ETag>{2}</ETag></{0}>",
+        root,
+        Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        etag
+    ));
+    let headers = resp.headers_mut();
+    headers.insert(
+        "x-oss-copy-source-version-id",
+        source_version_id.parse().unwrap(),
+    );
+    if let Some(version_id) = version_id {
+        headers.insert("x-oss-version-id", version_id.parse().unwrap());
+    }
+    resp
+}
+
+// The x-oss-meta-* headers of a request.
+fn metadata(parts: &Parts) -> BTreeMap<String, String> {
+    parts
+        .headers
+        .iter()
+        .filter(|(k, _)| k.as_str().starts_with("x-oss-meta-"))
+        .filter_map(|(k, v)| Some((k.as_str().to_owned(), v.to_str().ok()?.to_owned())))
+        .collect()
+}
+
+fn header<'a>(parts: &'a Parts, name: &str) -> Option<&'a str> {
+    parts.headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+// The first and last byte of a `bytes=first-last` range within `len` bytes,
+// a suffix or open range too. None for anything else, which serves the
+// whole object as OSS does.
+fn range(header: &str, len: u64) -> Option<(u64, u64)> {
+    let (first, last) = header.strip_prefix("bytes=")?.split_once('-')?;
+    let (first, last) = match (first.parse::<u64>().ok(), last.parse::<u64>().ok()) {
+        (Some(first), Some(last)) => (first, last.min(len.checked_sub(1)?)),
+        (Some(first), None) => (first, len.checked_sub(1)?),
+        (None, Some(suffix)) => (len.saturating_sub(suffix), len.checked_sub(1)?),
+        (None, None) => return None,
+    };
+    if first <= last {
+        Some((first, last))
+    } else {
+        None
+    }
+}
+
+// An OSS style ETag, with the part count for multipart objects.
+fn etag(data: &[u8], parts: Option<usize>) -> String {
+    let hex = format!("{:X}", Md5::digest(data));
+    match parts {
+        Some(n) => format!("\"{}-{}\"", hex, n),
+        None => format!("\"{}\"", hex),
+    }
+}
+
+// A response for a write or read of `data` stored under `etag`.
+fn stored(etag: &str, data: &[u8]) -> hyper::http::response::Builder {
+    let mut crc = Crc64::new();
+    crc.update(data);
+    Response::builder()
+        .header(ETAG, etag)
+        .header("x-oss-hash-crc64ecma", crc.value().to_string())
+}
+
+fn xml(body: String) -> Response<Body> {
+    Response::builder()
+        .header(CONTENT_TYPE, "application/xml")
+        .body(Body::from(format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n{}",
+            body
+        )))
+        .unwrap()
+}
+
+fn status(status: StatusCode) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .body(Body::empty())
+        .unwrap()
+}
+
+fn error(status: StatusCode, code: &str, message: &str, request_id: &str) -> Response<Body> {
+    let mut resp = xml(format!(
+        "<Error><Code>{}</Code><Message>{}</Message><RequestId>{}</RequestId>\
+         <HostId>{}</HostId></Error>",
+        code, message, request_id, DOMAIN
+    ));
+    *resp.status_mut() = status;
+    resp
+}
+
+fn not_implemented(request_id: &str) -> Response<Body> {
+    error(
+        StatusCode::NOT_IMPLEMENTED,
+        "NotImplemented",
+        "This operation is not supported by the fake.",
+        request_id,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bucket::ListObjectsOptions;
+    use crate::copy::MultipartCopyOptions;
+    use crate::oss::Part;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn test_fake_oss() {
+        let fake = FakeOss::start().await.unwrap();
+        let oss = fake.oss("examplebucket");
+
+        let put = oss
+            .put_object_from_buffer(b"hello", "dir/a.txt", None::<HashMap<&str, &str>>, None)
+            .await
+            .unwrap();
+        assert!(put.request_id().is_some());
+        assert!(put.etag().is_some());
+        assert!(put.body().is_empty());
+        fake.insert("examplebucket", "dir/sub/b.txt", "world");
+        fake.insert("examplebucket", "c.txt", "!");
+        assert_eq!(
+            oss.get_object("dir/a.txt", None, None).await.unwrap(),
+            "hello"
+        );
+        let head = oss.head_object("dir/a.txt", None, None).await.unwrap();
+        assert_eq!(head.headers()[CONTENT_LENGTH], "5");
+
+        let listed = oss
+            .list_objects(&ListObjectsOptions {
+                prefix: Some("dir/".to_owned()),
+                delimiter: Some("/".to_owned()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        let keys: Vec<&str> = listed.objects().iter().map(|o| o.key()).collect();
+        assert_eq!(keys, ["dir/a.txt"]);
+        assert_eq!(*listed.common_prefixes(), ["dir/sub/"]);
+
+        let upload_id = oss
+            .initiate_multipart_upload("big.bin", None::<HashMap<&str, &str>>)
+            .await
+            .unwrap();
+        let first = oss
+            .upload_part("big.bin", &upload_id, 1, b"abc".to_vec())
+            .await
+            .unwrap();
+        let second = oss
+            .upload_part_from_buffer("big.bin", &upload_id, 2, Bytes::from_static(b"def"))
+            .await
+            .unwrap();
+        let completed = oss
+            .complete_multipart_upload(
+                "big.bin",
+                upload_id,
+                CompleteMultipartUpload::new(vec![
+                    Part::new(1, first.etag().to_owned()),
+                    Part::new(2, second.etag().to_owned()),
+                ]),
+                None::<HashMap<&str, &str>>,
+            )
+            .await
+            .unwrap();
+        assert!(completed.request_id().is_some());
+        assert!(completed.etag().is_some());
+        assert_ne!(completed.request_id(), put.request_id());
+        assert_eq!(fake.object("examplebucket", "big.bin").unwrap(), "abcdef");
+
+        let upload_id = oss
+            .initiate_multipart_upload("aborted.bin", None::<HashMap<&str, &str>>)
+            .await
+            .unwrap();
+        oss.upload_part("aborted.bin", &upload_id, 1, b"abc".to_vec())
+            .await
+            .unwrap();
+        oss.abort_multipart_upload("aborted.bin", upload_id.clone())
+            .await
+            .unwrap();
+        let err = oss
+            .upload_part("aborted.bin", &upload_id, 2, b"def".to_vec())
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Some("NoSuchUpload"));
+
+        let deleted = oss.delete_object("dir/a.txt").await.unwrap();
+        assert!(deleted.request_id().is_some());
+        assert!(fake.object("examplebucket", "dir/a.txt").is_none());
+        let err = oss.get_object("dir/a.txt", None, None).await.unwrap_err();
+        assert_eq!(err.code(), Some("NoSuchKey"));
+        let err = oss.head_object("dir/a.txt", None, None).await.unwrap_err();
+        assert!(err.to_string().contains("404"));
+        let request_id = err.request_id().unwrap();
+        assert_ne!(Some(request_id), deleted.request_id());
+        assert_eq!(err.context().unwrap().request_id(), Some(request_id));
+
+        let forged = OSS::builder(
+            fake.key_id().to_owned(),
+            "wrong-secret".to_owned(),
+            fake.endpoint(),
+            "examplebucket".to_owned(),
+        )
+        .client(
+            Client::builder()
+                .resolve(&format!("examplebucket.{}", DOMAIN), fake.addr)
+                .build()
+                .unwrap(),
+        )
+        .build();
+        let err = forged
+            .list_objects(&ListObjectsOptions::default())
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Some("SignatureDoesNotMatch"));
+    }
+
+    #[tokio::test]
+    async fn test_fake_copy() {
+        let fake = FakeOss::start().await.unwrap();
+        let oss = fake.oss("examplebucket");
+        let mut headers = HashMap::new();
+        headers.insert("Content-Type", "text/plain");
+        headers.insert("x-oss-meta-owner", "alice");
+        headers.insert("x-oss-meta-team", "infra");
+        oss.put_object_from_buffer(b"0123456789", "src.txt", headers.clone(), None)
+            .await
+            .unwrap();
+        oss.put_object_from_buffer(b"", "empty.txt", headers, None)
+            .await
+            .unwrap();
+
+        let head = oss.head_object("src.txt", None, None).await.unwrap();
+        let version_id = head.headers()["x-oss-version-id"].to_str().unwrap();
+        let mut metadata_changes = HashMap::new();
+        metadata_changes.insert("x-oss-meta-team".to_owned(), None);
+        let options = MultipartCopyOptions {
+            part_size: 4,
+            metadata_changes,
+            ..Default::default()
+        };
+        let result = oss
+            .copy_large_object("examplebucket", "src.txt", "parts.txt", options)
+            .await
+            .unwrap();
+        assert_eq!(result.etag(), etag(b"0123456789", Some(3)));
+        assert!(!result.last_modified().is_empty());
+        assert_eq!(result.source_version_id(), Some(version_id));
+        assert!(result.version_id().is_some());
+        assert_eq!(
+            fake.object("examplebucket", "parts.txt").unwrap(),
+            "0123456789"
+        );
+        let head = oss.head_object("parts.txt", None, None).await.unwrap();
+        assert_eq!(head.headers()[CONTENT_TYPE], "text/plain");
+        assert_eq!(head.headers()["x-oss-meta-owner"], "alice");
+        assert!(head.headers().get("x-oss-meta-team").is_none());
+
+        for (src, dst) in [("src.txt", "whole.txt"), ("empty.txt", "empty-copy.txt")] {
+            let result = oss
+                .copy_large_object("examplebucket", src, dst, MultipartCopyOptions::default())
+                .await
+                .unwrap();
+            let source = fake.object("examplebucket", src).unwrap();
+            assert_eq!(result.etag(), etag(&source, None));
+            assert_eq!(fake.object("examplebucket", dst).unwrap(), source);
+            let head = oss.head_object(dst, None, None).await.unwrap();
+            assert_eq!(head.headers()[CONTENT_TYPE], "text/plain");
+            assert_eq!(head.headers()["x-oss-meta-team"], "infra");
+            assert!(result.version_id().is_some());
+        }
+
+        for part_size in [4, 100] {
+            let options = MultipartCopyOptions {
+                source_version_id: Some(version_id.to_owned()),
+                part_size,
+                ..Default::default()
+            };
+            let result = oss
+                .copy_large_object("examplebucket", "src.txt", "pinned.txt", options)
+                .await
+                .unwrap();
+            assert_eq!(result.source_version_id(), Some(version_id));
+            let options = MultipartCopyOptions {
+                source_version_id: Some("stale".to_owned()),
+                part_size,
+                ..Default::default()
+            };
+            let err = oss
+                .copy_large_object("examplebucket", "src.txt", "pinned.txt", options)
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("404"));
+        }
+    }
+
+    #[test]
+    fn test_range() {
+        assert_eq!(range("bytes=0-9", 100), Some((0, 9)));
+        assert_eq!(range("bytes=90-", 100), Some((90, 99)));
+        assert_eq!(range("bytes=-10", 100), Some((90, 99)));
+        assert_eq!(range("bytes=50-200", 100), Some((50, 99)));
+        assert_eq!(range("bytes=200-300", 100), None);
+        assert_eq!(range("bytes=0-9", 0), None);
+    }
+
+    // Latency of get_object on a client reused across requests against one
+    // built per request, the way requests were sent before they shared the
+    // client's connection pool; building one loads the TLS roots, so fewer
+    // of those are timed. Run with
+    // `cargo test --release --features test-util -- --ignored --nocapture bench_get_object`.
+    #[tokio::test(flavor = "multi_thread")]
+    #[ignore]
+    async fn bench_get_object() {
+        const N: u32 = 2000;
+        let fake = FakeOss::start().await.unwrap();
+        fake.insert("examplebucket", "a.bin", vec![7u8; 4096]);
+
+        let oss = fake.oss("examplebucket");
+        oss.get_object("a.bin", None, None).await.unwrap();
+        let start = web_time::Instant::now();
+        for _ in 0..N {
+            oss.get_object("a.bin", None, None).await.unwrap();
+        }
+        let shared = start.elapsed() / N;
+
+        let start = web_time::Instant::now();
+        for _ in 0..N / 10 {
+            let oss = fake.oss("examplebucket");
+            oss.get_object("a.bin", None, None).await.unwrap();
+        }
+        let fresh = start.elapsed() / (N / 10);
+
+        println!(
+            "get_object of 4 KiB: shared client {:?} (mean of {}), client per request {:?} (mean of {})",
+            shared,
+            N,
+            fresh,
+            N / 10
+        );
+    }
+}
@@ -0,0 +1,74 @@
+use std::sync::Mutex;
+use std::time::Duration;
+use web_time::Instant;
+
+use crate::oss::OSS;
+use crate::rt;
+
+// Token bucket shared by every transfer of a client, set with
+// `OSSBuilder::rate_limit`. Holds up to one second worth of bytes, so an
+// idle client may burst that much. A chunk larger than what is left waits
+// for the shortfall, the bucket going negative until it refills.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    bytes_per_second: f64,
+    // Available bytes, negative while in debt, and when that was computed.
+    state: Mutex<(f64, Instant)>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(bytes_per_second: u64) -> Self {
+        let rate = bytes_per_second.max(1) as f64;
+        RateLimiter {
+            bytes_per_second: rate,
+            state: Mutex::new((rate, Instant::now())),
+        }
+    }
+
+    // Waits until `n` more bytes may be sent or received.
+    pub(crate) async fn acquire(&self, n: u64) {
+        let wait = self.reserve(n);
+        if !wait.is_zero() {
+            rt::sleep(wait).await;
+        }
+    }
+
+    // Takes `n` bytes from the bucket and returns how long to wait for them.
+    fn reserve(&self, n: u64) -> Duration {
+        let mut state = self.state.lock().unwrap();
+        let (tokens, last) = *state;
+        let now = Instant::now();
+        let refill = now.duration_since(last).as_secs_f64() * self.bytes_per_second;
+        let tokens = (tokens + refill).min(self.bytes_per_second) - n as f64;
+        *state = (tokens, now);
+        if tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-tokens / self.bytes_per_second)
+        }
+    }
+}
+
+impl OSS {
+    // Waits for the rate limit, if any, to allow `n` more bytes.
+    pub(crate) async fn throttle(&self, n: u64) {
+        if let Some(limiter) = self.rate_limiter() {
+            limiter.acquire(n).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_limiter_reserve() {
+        let limiter = RateLimiter::new(1000);
+        assert_eq!(limiter.reserve(1000), Duration::ZERO);
+        let wait = limiter.reserve(500);
+        assert!(wait > Duration::from_millis(450) && wait <= Duration::from_millis(500));
+        let wait = limiter.reserve(500);
+        assert!(wait > Duration::from_millis(950) && wait <= Duration::from_millis(1000));
+    }
+}
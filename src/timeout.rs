@@ -0,0 +1,106 @@
+use reqwest::RequestBuilder;
+use std::future::Future;
+use std::time::Duration;
+
+#[cfg(target_arch = "wasm32")]
+use futures::future::{self, Either};
+
+#[cfg(target_arch = "wasm32")]
+use crate::errors::Error;
+use crate::oss::OSS;
+#[cfg(target_arch = "wasm32")]
+use crate::rt;
+
+tokio::task_local! {
+    static REQUEST_TIMEOUT: Duration;
+}
+
+impl OSS {
+    // Runs `f`, typically a single call on this client, with every request
+    // it sends bounded by `timeout` from connecting to the end of the body
+    // instead of the client's `OSSBuilder::timeout`. The parts of a transfer
+    // and the pages of a listing are covered too, requests made from
+    // background tasks such as cache revalidation keep the client's.
+    pub async fn with_timeout<T, F>(&self, timeout: Duration, f: F) -> T
+    where
+        F: Future<Output = T>,
+    {
+        REQUEST_TIMEOUT.scope(timeout, f).await
+    }
+}
+
+// Runs `f` with `timeout` as if in `with_timeout`, to carry it into a
+// spawned task.
+pub(crate) async fn with_request_timeout<F: Future>(timeout: Option<Duration>, f: F) -> F::Output {
+    match timeout {
+        Some(timeout) => REQUEST_TIMEOUT.scope(timeout, f).await,
+        None => f.await,
+    }
+}
+
+// The timeout set by an enclosing `with_timeout`, if any.
+pub(crate) fn request_timeout() -> Option<Duration> {
+    REQUEST_TIMEOUT.try_with(|timeout| *timeout).ok()
+}
+
+pub(crate) trait Timed {
+    // Applies the timeout of an enclosing `with_timeout`.
+    fn timed(self) -> Self;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Timed for RequestBuilder {
+    fn timed(self) -> Self {
+        match request_timeout() {
+            Some(timeout) => self.timeout(timeout),
+            None => self,
+        }
+    }
+}
+
+// reqwest has no request timeouts on wasm32, `OSS::send` races the fetch
+// with `with_deadline` there instead.
+#[cfg(target_arch = "wasm32")]
+impl Timed for RequestBuilder {
+    fn timed(self) -> Self {
+        self
+    }
+}
+
+// Runs `f`, failing once the timeout of an enclosing `with_timeout` passes.
+#[cfg(target_arch = "wasm32")]
+pub(crate) async fn with_deadline<T, F>(f: F) -> Result<T, Error>
+where
+    F: Future<Output = Result<T, Error>>,
+{
+    let timeout = match request_timeout() {
+        Some(timeout) => timeout,
+        None => return f.await,
+    };
+    let sleep = rt::sleep(timeout);
+    futures::pin_mut!(f, sleep);
+    match future::select(f, sleep).await {
+        Either::Left((result, _)) => result,
+        Either::Right(_) => Err(Error::E(format!("request timed out after {:?}", timeout))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_with_timeout() {
+        let oss_instance = OSS::new(
+            "xxx".to_string(),
+            "xxx".to_string(),
+            "xxx.aliyuncs.com".to_string(),
+            "xxx".to_string(),
+        );
+        assert_eq!(request_timeout(), None);
+        let timeout = oss_instance
+            .with_timeout(Duration::from_secs(3), async { request_timeout() })
+            .await;
+        assert_eq!(timeout, Some(Duration::from_secs(3)));
+    }
+}
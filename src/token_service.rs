@@ -0,0 +1,254 @@
+use base64::encode;
+use chrono::SecondsFormat;
+use serde_derive::Serialize;
+use serde_json::{json, Map, Value};
+use std::fmt;
+
+use crate::credentials::Credentials;
+use crate::errors::Error;
+use crate::oss::OSS;
+use crate::post_policy::PostPolicyOptions;
+use crate::sts::AssumeRoleOptions;
+
+// The request OSS makes to the app server once a direct upload finished,
+// its response is relayed to the uploading client.
+// https://help.aliyun.com/document_detail/31989.html
+#[derive(Clone, Debug, Default)]
+pub struct UploadCallback {
+    pub url: String,
+    // `${object}`, `${size}`, `${etag}`, `${mimeType}` and `${x:var}`
+    // placeholders are filled in by OSS, e.g. "object=${object}&size=${size}".
+    pub body: String,
+    // application/x-www-form-urlencoded unless set.
+    pub body_type: Option<String>,
+    // Host header of the callback request, taken from `url` unless set.
+    pub host: Option<String>,
+}
+
+impl UploadCallback {
+    pub fn new<S>(url: S, body: S) -> Self
+    where
+        S: Into<String>,
+    {
+        UploadCallback {
+            url: url.into(),
+            body: body.into(),
+            ..Default::default()
+        }
+    }
+
+    // Value of the `callback` form field or the x-oss-callback header.
+    pub fn to_base64(&self) -> String {
+        let mut callback = Map::new();
+        callback.insert("callbackUrl".to_owned(), json!(self.url));
+        callback.insert("callbackBody".to_owned(), json!(self.body));
+        if let Some(body_type) = &self.body_type {
+            callback.insert("callbackBodyType".to_owned(), json!(body_type));
+        }
+        if let Some(host) = &self.host {
+            callback.insert("callbackHost".to_owned(), json!(host));
+        }
+        encode(Value::Object(callback).to_string())
+    }
+}
+
+// What an app backend hands to a mobile or browser client for one direct
+// upload: temporary credentials for the OSS mobile SDKs, and a POST policy
+// signed with them for plain form uploads. Serializes to the JSON of the
+// official app server examples, see `to_json`.
+#[derive(Clone, PartialEq, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct UploadToken {
+    // Always 200, the mobile SDKs' federation providers expect it.
+    status_code: u16,
+    access_key_id: String,
+    access_key_secret: String,
+    security_token: String,
+    expiration: String,
+    host: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dir: Option<String>,
+    policy: String,
+    signature: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    callback: Option<String>,
+}
+
+impl UploadToken {
+    pub fn access_key_id(&self) -> &str {
+        &self.access_key_id
+    }
+
+    pub fn access_key_secret(&self) -> &str {
+        &self.access_key_secret
+    }
+
+    pub fn security_token(&self) -> &str {
+        &self.security_token
+    }
+
+    pub fn expiration(&self) -> &str {
+        &self.expiration
+    }
+
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    pub fn dir(&self) -> Option<&str> {
+        self.dir.as_deref()
+    }
+
+    pub fn policy(&self) -> &str {
+        &self.policy
+    }
+
+    pub fn signature(&self) -> &str {
+        &self.signature
+    }
+
+    pub fn callback(&self) -> Option<&str> {
+        self.callback.as_deref()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+// Keeps the secret and token out of logs.
+impl fmt::Debug for UploadToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("UploadToken")
+            .field("access_key_id", &self.access_key_id)
+            .field("expiration", &self.expiration)
+            .field("host", &self.host)
+            .field("dir", &self.dir)
+            .finish()
+    }
+}
+
+impl OSS {
+    // Mints credentials for `role` and signs a POST policy restricted by
+    // `options` with them, both expiring together. The body of a typical
+    // `GET /upload-token` endpoint is then `token.to_json()`.
+    pub async fn issue_upload_token(
+        &self,
+        role: &AssumeRoleOptions,
+        options: &PostPolicyOptions,
+        callback: Option<&UploadCallback>,
+    ) -> Result<UploadToken, Error> {
+        let credentials = self.assume_role(role).await?;
+        self.upload_token(&credentials, role, options, callback)
+    }
+
+    fn upload_token(
+        &self,
+        credentials: &Credentials,
+        role: &AssumeRoleOptions,
+        options: &PostPolicyOptions,
+        callback: Option<&UploadCallback>,
+    ) -> Result<UploadToken, Error> {
+        let security_token = credentials
+            .security_token()
+            .ok_or_else(|| Error::E("STS credentials have no security token".to_owned()))?;
+        let expiration = match credentials.expiration() {
+            Some(expiration) => expiration,
+            None => {
+                self.now()
+                    + chrono::Duration::from_std(role.duration)
+                        .map_err(|e| Error::E(format!("invalid duration: {}", e)))?
+            }
+        };
+        let policy = self.post_policy_at(credentials, expiration, options);
+        let field = |name: &str| {
+            policy
+                .fields()
+                .iter()
+                .find(|(k, _)| k == name)
+                .map(|(_, v)| v.clone())
+                .unwrap_or_default()
+        };
+        Ok(UploadToken {
+            status_code: 200,
+            access_key_id: credentials.key_id().to_owned(),
+            access_key_secret: credentials.key_secret().to_owned(),
+            security_token: security_token.to_owned(),
+            expiration: expiration.to_rfc3339_opts(SecondsFormat::Secs, true),
+            host: policy.url().to_owned(),
+            dir: options.key_prefix.clone(),
+            policy: field("policy"),
+            signature: field("Signature"),
+            callback: callback.map(|c| c.to_base64()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, TimeZone, Utc};
+
+    #[test]
+    fn test_upload_callback() {
+        let mut callback = UploadCallback::new(
+            "https://app.example.com/oss-callback",
+            "object=${object}&size=${size}",
+        );
+        callback.body_type = Some("application/json".to_owned());
+        let decoded = base64::decode(callback.to_base64()).unwrap();
+        let value: Value = serde_json::from_slice(&decoded).unwrap();
+        assert_eq!(value["callbackUrl"], "https://app.example.com/oss-callback");
+        assert_eq!(value["callbackBody"], "object=${object}&size=${size}");
+        assert_eq!(value["callbackBodyType"], "application/json");
+        assert!(value.get("callbackHost").is_none());
+    }
+
+    #[test]
+    fn test_upload_token() {
+        let oss_instance = OSS::new(
+            "access-key-id".to_string(),
+            "access-key-secret".to_string(),
+            "https://oss-cn-hangzhou.aliyuncs.com".to_string(),
+            "examplebucket".to_string(),
+        );
+        let expiration: DateTime<Utc> = Utc.with_ymd_and_hms(2023, 11, 14, 22, 13, 20).unwrap();
+        let credentials = Credentials::new(
+            "STS.id".to_owned(),
+            "sts-secret".to_owned(),
+            Some("sts-token".to_owned()),
+        )
+        .with_expiration(expiration);
+        let role = AssumeRoleOptions::new("acs:ram::123:role/upload", "app");
+        let options = PostPolicyOptions {
+            key_prefix: Some("user/1/".to_owned()),
+            ..Default::default()
+        };
+        let callback = UploadCallback::new("https://app.example.com/cb", "object=${object}");
+        let token = oss_instance
+            .upload_token(&credentials, &role, &options, Some(&callback))
+            .unwrap();
+        assert_eq!(token.expiration(), "2023-11-14T22:13:20Z");
+        assert_eq!(
+            token.host(),
+            "https://examplebucket.oss-cn-hangzhou.aliyuncs.com/"
+        );
+        assert_eq!(
+            token.signature(),
+            crate::auth::hmac_sha1_base64("sts-secret", token.policy())
+        );
+        assert!(!format!("{:?}", token).contains("sts-secret"));
+
+        let value: Value = serde_json::from_str(&token.to_json()).unwrap();
+        assert_eq!(value["StatusCode"], 200);
+        assert_eq!(value["AccessKeyId"], "STS.id");
+        assert_eq!(value["SecurityToken"], "sts-token");
+        assert_eq!(value["Dir"], "user/1/");
+        assert_eq!(value["Callback"], callback.to_base64());
+
+        let long_lived = Credentials::new("id".to_owned(), "secret".to_owned(), None);
+        assert!(oss_instance
+            .upload_token(&long_lived, &role, &options, None)
+            .is_err());
+    }
+}
@@ -0,0 +1,128 @@
+// Spans for the `tracing` feature. Every API call runs in an `oss` span with
+// its operation, bucket and key, to which the status and x-oss-request-id of
+// each response and finally the call's duration are recorded. Without the
+// feature these do nothing.
+use reqwest::Response;
+use std::future::Future;
+
+#[cfg(feature = "tracing")]
+use tracing::{field, Instrument, Span};
+
+#[cfg(feature = "tracing")]
+pub(crate) async fn in_span<F: Future>(
+    operation: &str,
+    bucket: &str,
+    object: &str,
+    f: F,
+) -> F::Output {
+    let span = tracing::info_span!(
+        "oss",
+        operation,
+        bucket,
+        key = object,
+        status = field::Empty,
+        request_id = field::Empty,
+        duration_ms = field::Empty,
+    );
+    let start = web_time::Instant::now();
+    let output = f.instrument(span.clone()).await;
+    span.record("duration_ms", start.elapsed().as_millis() as u64);
+    output
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) async fn in_span<F: Future>(_: &str, _: &str, _: &str, f: F) -> F::Output {
+    f.await
+}
+
+// Keeps `f` in the current span once it is spawned.
+#[cfg(feature = "tracing")]
+pub(crate) fn in_current_span<F: Future>(f: F) -> impl Future<Output = F::Output> {
+    f.in_current_span()
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn in_current_span<F: Future>(f: F) -> impl Future<Output = F::Output> {
+    f
+}
+
+#[cfg(feature = "tracing")]
+pub(crate) fn record_response(resp: &Response) {
+    let span = Span::current();
+    span.record("status", resp.status().as_u16());
+    if let Some(id) = resp
+        .headers()
+        .get("x-oss-request-id")
+        .and_then(|v| v.to_str().ok())
+    {
+        span.record("request_id", id);
+    }
+}
+
+#[cfg(not(feature = "tracing"))]
+pub(crate) fn record_response(_: &Response) {}
+
+#[cfg(all(test, feature = "tracing"))]
+mod tests {
+    use super::*;
+    use std::fmt::Debug;
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    // Collects the fields recorded to spans as `name=value`.
+    #[derive(Clone, Default)]
+    struct Fields(Arc<Mutex<Vec<String>>>);
+
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("{}={:?}", field.name(), value));
+        }
+    }
+
+    impl Subscriber for Fields {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            span.record(&mut self.clone());
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _: &Id, values: &Record<'_>) {
+            values.record(&mut self.clone());
+        }
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, _: &Event<'_>) {}
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[tokio::test]
+    async fn test_in_span() {
+        let fields = Fields::default();
+        let _guard = tracing::subscriber::set_default(fields.clone());
+        let value = in_span("get_object", "examplebucket", "a.txt", async { 42 }).await;
+        assert_eq!(value, 42);
+
+        let fields = fields.0.lock().unwrap();
+        assert_eq!(
+            fields[..3],
+            [
+                "operation=\"get_object\"",
+                "bucket=\"examplebucket\"",
+                "key=\"a.txt\"",
+            ]
+        );
+        assert!(fields[3].starts_with("duration_ms="));
+    }
+}
@@ -0,0 +1,256 @@
+use futures::future::{self, Either};
+use futures::TryStreamExt;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+use crate::download::GetObjectOptions;
+use crate::errors::Error;
+use crate::multipart::MultipartUpload;
+use crate::oss::OSS;
+use crate::rt::{self, AsyncWriteExt, JoinHandle};
+use crate::utils::load_chunk_file;
+
+// Every part but the last has to be at least this large.
+const MIN_PART_SIZE: u64 = 100 * 1024;
+
+// Owns the upload and download tasks it spawns, so a service can stop them
+// cleanly on SIGTERM with `shutdown` instead of leaving multipart uploads
+// behind on the server, where their parts are billed until aborted.
+#[derive(Debug)]
+pub struct TransferManager {
+    oss: OSS,
+    state: Arc<State>,
+    handles: Mutex<Vec<JoinHandle<()>>>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    stopping: AtomicBool,
+    // Multipart uploads started but not yet completed or aborted, by upload id.
+    uploads: Mutex<HashMap<String, MultipartUpload>>,
+}
+
+impl State {
+    fn stopped(&self) -> Result<(), Error> {
+        if self.stopping.load(Ordering::SeqCst) {
+            Err(Error::E("transfer manager is shut down".to_owned()))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// The outcome of a transfer spawned by a `TransferManager`.
+#[derive(Debug)]
+pub struct Transfer {
+    rx: oneshot::Receiver<Result<(), Error>>,
+}
+
+impl Transfer {
+    // Waits for the transfer; it fails if it was cut short by `shutdown`.
+    pub async fn wait(self) -> Result<(), Error> {
+        self.rx
+            .await
+            .unwrap_or_else(|_| Err(Error::E("transfer was aborted".to_owned())))
+    }
+}
+
+impl TransferManager {
+    pub fn new(oss: &OSS) -> Self {
+        TransferManager {
+            oss: oss.clone(),
+            state: Arc::new(State::default()),
+            handles: Mutex::new(Vec::new()),
+        }
+    }
+
+    // Uploads the file at `path` as `object_name` in the background, one part
+    // of `part_size` bytes at a time. On shutdown the part in flight is
+    // finished and the upload aborted.
+    pub fn upload_file<S, P>(
+        &self,
+        object_name: S,
+        path: P,
+        part_size: u64,
+    ) -> Result<Transfer, Error>
+    where
+        S: AsRef<str>,
+        P: Into<PathBuf>,
+    {
+        if part_size < MIN_PART_SIZE {
+            return Err(Error::E(format!(
+                "part size {} is below the minimum of {}",
+                part_size, MIN_PART_SIZE
+            )));
+        }
+        let oss = self.oss.clone();
+        let state = self.state.clone();
+        let object_name = object_name.as_ref().to_owned();
+        let path = path.into();
+        self.spawn("transfer_upload", async move {
+            let size = rt::metadata(&path).await?.len();
+            let mut file = rt::open(&path).await?;
+            state.stopped()?;
+            let upload = oss
+                .start_multipart_upload(object_name.as_str(), None)
+                .await?;
+            let upload_id = upload.upload_id().to_owned();
+            state
+                .uploads
+                .lock()
+                .unwrap()
+                .insert(upload_id.clone(), upload.clone());
+            let parts = async {
+                let mut parts = Vec::new();
+                let mut offset = 0;
+                // An empty file still needs one (empty) part.
+                while offset < size || parts.is_empty() {
+                    state.stopped()?;
+                    let n = part_size.min(size - offset);
+                    let data = load_chunk_file(&mut file, offset, n).await?;
+                    parts.push(upload.part_future(parts.len() as u64 + 1, data).await?);
+                    offset += n;
+                }
+                Ok::<_, Error>(parts)
+            }
+            .await;
+            // Only the manager aborts it from here on if this task is dropped.
+            let upload = state.uploads.lock().unwrap().remove(&upload_id);
+            let upload = match upload {
+                Some(upload) => upload,
+                None => return Err(Error::E("transfer was aborted".to_owned())),
+            };
+            match parts {
+                Ok(parts) => upload.complete(parts).await.map(|_| ()),
+                Err(e) => Err(upload.abort_after(e).await),
+            }
+        })
+    }
+
+    // Downloads `object_name` to `path` in the background. On shutdown a
+    // download still running after the grace period is dropped, leaving a
+    // partial file.
+    pub fn download_file<S, P>(&self, object_name: S, path: P) -> Result<Transfer, Error>
+    where
+        S: AsRef<str>,
+        P: Into<PathBuf>,
+    {
+        let oss = self.oss.clone();
+        let state = self.state.clone();
+        let object_name = object_name.as_ref().to_owned();
+        let path = path.into();
+        self.spawn("transfer_download", async move {
+            let mut file = rt::create(&path).await?;
+            let stream = oss.get_object_stream(&object_name, &GetObjectOptions::default());
+            futures::pin_mut!(stream);
+            while let Some(chunk) = stream.try_next().await? {
+                state.stopped()?;
+                file.write_all(&chunk).await?;
+            }
+            file.flush().await?;
+            Ok(())
+        })
+    }
+
+    // Stops accepting transfers and waits up to `grace_period` for the
+    // running ones; uploads stop after their in-flight part. Tasks still
+    // running then are aborted, and so is every multipart upload left
+    // incomplete on the server. Returns the first error aborting one, the
+    // others are logged.
+    pub async fn shutdown(&self, grace_period: Duration) -> Result<(), Error> {
+        self.state.stopping.store(true, Ordering::SeqCst);
+        let mut handles = std::mem::take(&mut *self.handles.lock().unwrap());
+        let timed_out = {
+            let all = future::join_all(handles.iter_mut());
+            let grace = rt::sleep(grace_period);
+            futures::pin_mut!(grace);
+            matches!(future::select(all, grace).await, Either::Right(_))
+        };
+        if timed_out {
+            // Let the cancelled tasks drop their state before the uploads go.
+            future::join_all(handles.into_iter().map(JoinHandle::cancel)).await;
+        }
+        let uploads: Vec<MultipartUpload> = self
+            .state
+            .uploads
+            .lock()
+            .unwrap()
+            .drain()
+            .map(|(_, upload)| upload)
+            .collect();
+        let mut result = Ok(());
+        for upload in uploads {
+            if let Err(e) = upload.abort().await {
+                if result.is_ok() {
+                    result = Err(e);
+                } else {
+                    warn!("can not abort multipart upload: {}", e);
+                }
+            }
+        }
+        result
+    }
+
+    // Number of transfers spawned and not yet finished.
+    pub fn in_flight(&self) -> usize {
+        let mut handles = self.handles.lock().unwrap();
+        handles.retain(|h| !h.is_finished());
+        handles.len()
+    }
+
+    fn spawn<F>(&self, name: &str, fut: F) -> Result<Transfer, Error>
+    where
+        F: std::future::Future<Output = Result<(), Error>> + Send + 'static,
+    {
+        let mut handles = self.handles.lock().unwrap();
+        // Checked under the lock so nothing slips past a concurrent shutdown.
+        self.state.stopped()?;
+        let (tx, rx) = oneshot::channel();
+        handles.retain(|h| !h.is_finished());
+        handles.push(self.oss.spawn_named(name, async move {
+            let _ = tx.send(fut.await);
+        }));
+        Ok(Transfer { rx })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_manager() -> TransferManager {
+        TransferManager::new(&OSS::new(
+            "xxx".to_string(),
+            "xxx".to_string(),
+            "xxx.aliyuncs.com".to_string(),
+            "xxx".to_string(),
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_rejects_new_transfers() {
+        let manager = get_manager();
+        manager.shutdown(Duration::from_secs(1)).await.unwrap();
+        assert!(manager
+            .upload_file("a.bin", "a.bin", MIN_PART_SIZE)
+            .is_err());
+        assert!(manager.download_file("a.bin", "a.bin").is_err());
+        assert_eq!(manager.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_aborts_after_grace_period() {
+        let manager = get_manager();
+        let transfer = manager
+            .spawn("test", future::pending::<Result<(), Error>>())
+            .unwrap();
+        assert_eq!(manager.in_flight(), 1);
+        manager.shutdown(Duration::from_millis(10)).await.unwrap();
+        assert!(transfer.wait().await.is_err());
+        assert_eq!(manager.oss.active_tasks(), 0);
+    }
+}
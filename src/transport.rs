@@ -0,0 +1,161 @@
+use reqwest::{Client, Request, Response};
+use std::fmt;
+
+use crate::errors::Error;
+
+// The future returned by the pluggable parts of a client: transports,
+// signers, credential providers and CDN purge hooks. Futures on wasm32 hold
+// JavaScript values, which can not be sent between threads, so they are
+// only `Send` off wasm32.
+#[cfg(not(target_arch = "wasm32"))]
+pub type BoxFuture<'a, T> = futures::future::BoxFuture<'a, T>;
+#[cfg(target_arch = "wasm32")]
+pub type BoxFuture<'a, T> = futures::future::LocalBoxFuture<'a, T>;
+
+// `Send`, except on wasm32 where nothing has to be, see `BoxFuture`.
+#[cfg(not(target_arch = "wasm32"))]
+pub trait MaybeSend: Send {}
+#[cfg(not(target_arch = "wasm32"))]
+impl<T: Send> MaybeSend for T {}
+
+#[cfg(target_arch = "wasm32")]
+pub trait MaybeSend {}
+#[cfg(target_arch = "wasm32")]
+impl<T> MaybeSend for T {}
+
+// Sends the signed requests of a client and returns what came back. The
+// reqwest client is the default; set another with `OSSBuilder::transport`,
+// e.g. one answering from a script in tests, so no credentials or network
+// are needed. Interceptors, retries and metrics still apply on top of it.
+pub trait HttpTransport: fmt::Debug + Send + Sync {
+    fn execute(&self, request: Request) -> BoxFuture<'_, Result<Response, Error>>;
+}
+
+impl HttpTransport for Client {
+    fn execute(&self, request: Request) -> BoxFuture<'_, Result<Response, Error>> {
+        Box::pin(async move { Ok(Client::execute(self, request).await?) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::acl::ObjectAcl;
+    use crate::oss::OSS;
+    use crate::retry::RetryPolicy;
+    use std::collections::{HashMap, VecDeque};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    // Answers with the given statuses and bodies in order and keeps what was
+    // asked.
+    #[derive(Debug, Default)]
+    struct Script {
+        responses: Mutex<VecDeque<(u16, &'static str)>>,
+        requests: Arc<Mutex<Vec<String>>>,
+    }
+
+    fn script_oss(
+        responses: Vec<(u16, &'static str)>,
+        policy: RetryPolicy,
+    ) -> (OSS, Arc<Mutex<Vec<String>>>) {
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let oss_instance = OSS::builder(
+            "xxx".to_string(),
+            "xxx".to_string(),
+            "xxx.aliyuncs.com".to_string(),
+            "examplebucket".to_string(),
+        )
+        .retry_policy(RetryPolicy {
+            base_delay: Duration::from_millis(1),
+            ..policy
+        })
+        .transport(Script {
+            responses: Mutex::new(responses.into()),
+            requests: requests.clone(),
+        })
+        .build();
+        (oss_instance, requests)
+    }
+
+    impl HttpTransport for Script {
+        fn execute(&self, request: Request) -> BoxFuture<'_, Result<Response, Error>> {
+            self.requests.lock().unwrap().push(format!(
+                "{} {}",
+                request.method(),
+                request.url().as_str()
+            ));
+            let next = self.responses.lock().unwrap().pop_front();
+            Box::pin(async move {
+                let (status, body) = next.ok_or_else(|| Error::E("script is over".to_owned()))?;
+                let resp = http::Response::builder().status(status).body(body).unwrap();
+                Ok(Response::from(resp))
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transport() {
+        let (oss_instance, requests) = script_oss(
+            vec![(503, ""), (200, ""), (503, ""), (200, "")],
+            RetryPolicy::default(),
+        );
+
+        oss_instance
+            .put_object_acl("a.txt", ObjectAcl::Private)
+            .await
+            .unwrap();
+        assert_eq!(
+            *requests.lock().unwrap(),
+            vec![
+                "PUT http://examplebucket.xxx.aliyuncs.com/a.txt?acl".to_owned(),
+                "PUT http://examplebucket.xxx.aliyuncs.com/a.txt?acl".to_owned(),
+            ]
+        );
+
+        oss_instance.head_object("a.txt", None, None).await.unwrap();
+        assert_eq!(requests.lock().unwrap().len(), 4);
+
+        let err = oss_instance.delete_object("a.txt").await.unwrap_err();
+        assert!(err.to_string().contains("script is over"));
+    }
+
+    #[tokio::test]
+    async fn test_transport_resigns_once_whatever_the_attempts() {
+        let skewed = "<Error><Code>RequestTimeTooSkewed</Code></Error>";
+        let initiated = "<InitiateMultipartUploadResult><Bucket>examplebucket</Bucket>\
+                         <Key>a.txt</Key><UploadId>0004B9894A22E5B1888A1E29F823****</UploadId>\
+                         </InitiateMultipartUploadResult>";
+        let (oss_instance, requests) = script_oss(
+            vec![(403, skewed), (200, initiated)],
+            RetryPolicy::default(),
+        );
+        let upload_id = oss_instance
+            .initiate_multipart_upload("a.txt", None::<HashMap<&str, &str>>)
+            .await
+            .unwrap();
+        assert_eq!(upload_id, "0004B9894A22E5B1888A1E29F823****");
+        assert_eq!(requests.lock().unwrap().len(), 2);
+
+        let expired = "<Error><Code>SecurityTokenExpired</Code></Error>";
+        let (oss_instance, requests) = script_oss(
+            vec![(403, expired), (204, ""), (503, "")],
+            RetryPolicy::none(),
+        );
+        oss_instance.delete_object("a.txt").await.unwrap();
+        assert_eq!(requests.lock().unwrap().len(), 2);
+        let err = oss_instance.delete_object("a.txt").await.unwrap_err();
+        assert!(err.to_string().contains("503"));
+        assert_eq!(requests.lock().unwrap().len(), 3);
+
+        // Only once.
+        let (oss_instance, requests) =
+            script_oss(vec![(403, skewed), (403, skewed)], RetryPolicy::default());
+        let err = oss_instance
+            .initiate_multipart_upload("a.txt", None::<HashMap<&str, &str>>)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), Some("RequestTimeTooSkewed"));
+        assert_eq!(requests.lock().unwrap().len(), 2);
+    }
+}
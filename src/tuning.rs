@@ -0,0 +1,174 @@
+use futures::stream::{FuturesUnordered, StreamExt};
+use std::future::Future;
+use std::time::Duration;
+use web_time::Instant;
+
+use crate::errors::Error;
+
+// How many parts of a multipart transfer are in flight at once.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Concurrency {
+    Fixed(usize),
+    // Starts at 2 and adds a part while the time per byte stays flat, halving
+    // when it climbs, never going above `max`.
+    Auto { max: usize },
+}
+
+impl Default for Concurrency {
+    fn default() -> Self {
+        Concurrency::Fixed(8)
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Tuner {
+    limit: usize,
+    max: usize,
+    auto: bool,
+    // Lowest seconds per byte seen so far.
+    best: Option<f64>,
+}
+
+impl Tuner {
+    pub(crate) fn new(concurrency: Concurrency) -> Self {
+        match concurrency {
+            Concurrency::Fixed(n) => Tuner {
+                limit: n.max(1),
+                max: n.max(1),
+                auto: false,
+                best: None,
+            },
+            Concurrency::Auto { max } => Tuner {
+                limit: 2.min(max.max(1)),
+                max: max.max(1),
+                auto: true,
+                best: None,
+            },
+        }
+    }
+
+    pub(crate) fn limit(&self) -> usize {
+        self.limit
+    }
+
+    // Records a finished part. Latency is weighted by part size so that a
+    // short last part doesn't read as a speedup.
+    pub(crate) fn observe(&mut self, bytes: u64, elapsed: Duration) {
+        if !self.auto {
+            return;
+        }
+        let cost = elapsed.as_secs_f64() / bytes.max(1) as f64;
+        let best = self.best.map_or(cost, |best| best.min(cost));
+        self.best = Some(best);
+        if cost <= best * 1.25 {
+            self.limit = (self.limit + 1).min(self.max);
+        } else if cost > best * 2.0 {
+            self.limit = (self.limit / 2).max(1);
+        }
+    }
+}
+
+// Runs `f` over `items` with the concurrency tuned from the observed time per
+// `weight` byte. Results are in the order of `items`; the first error stops
+// scheduling and drops the parts in flight.
+pub(crate) async fn run_tuned<I, T, W, F, Fut>(
+    items: Vec<I>,
+    concurrency: Concurrency,
+    weight: W,
+    f: F,
+) -> Result<Vec<T>, Error>
+where
+    W: Fn(&I) -> u64,
+    F: Fn(I) -> Fut,
+    Fut: Future<Output = Result<T, Error>>,
+{
+    let mut tuner = Tuner::new(concurrency);
+    let mut results: Vec<Option<T>> = items.iter().map(|_| None).collect();
+    let mut pending = items.into_iter().enumerate();
+    let mut in_flight = FuturesUnordered::new();
+    loop {
+        while in_flight.len() < tuner.limit() {
+            let (i, item) = match pending.next() {
+                Some(next) => next,
+                None => break,
+            };
+            let bytes = weight(&item);
+            let fut = f(item);
+            in_flight.push(async move {
+                let start = Instant::now();
+                (i, bytes, fut.await, start.elapsed())
+            });
+        }
+        match in_flight.next().await {
+            Some((i, bytes, result, elapsed)) => {
+                results[i] = Some(result?);
+                tuner.observe(bytes, elapsed);
+                debug!(
+                    "part {} done in {:?}, concurrency {}",
+                    i,
+                    elapsed,
+                    tuner.limit()
+                );
+            }
+            None => break,
+        }
+    }
+    Ok(results.into_iter().flatten().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tuner_grows_while_flat_and_backs_off() {
+        let mut tuner = Tuner::new(Concurrency::Auto { max: 4 });
+        assert_eq!(tuner.limit(), 2);
+        let mb = 1024 * 1024;
+        tuner.observe(mb, Duration::from_millis(100));
+        tuner.observe(mb, Duration::from_millis(110));
+        assert_eq!(tuner.limit(), 4);
+        // A short last part at the same throughput is not a slowdown.
+        tuner.observe(mb / 10, Duration::from_millis(11));
+        assert_eq!(tuner.limit(), 4);
+        tuner.observe(mb, Duration::from_millis(300));
+        assert_eq!(tuner.limit(), 2);
+
+        let mut tuner = Tuner::new(Concurrency::Fixed(3));
+        tuner.observe(mb, Duration::from_millis(100));
+        tuner.observe(mb, Duration::from_secs(10));
+        assert_eq!(tuner.limit(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_run_tuned_keeps_order() {
+        let items: Vec<u64> = (1..=6).collect();
+        let result = run_tuned(
+            items,
+            Concurrency::Auto { max: 3 },
+            |n| *n,
+            |n| async move {
+                tokio::time::sleep(Duration::from_millis(7 - n)).await;
+                Ok(n * 10)
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(result, [10, 20, 30, 40, 50, 60]);
+
+        let result = run_tuned(
+            vec![1u64, 2],
+            Concurrency::Fixed(1),
+            |_| 1,
+            |n| async move {
+                if n == 2 {
+                    Err(Error::E("boom".to_owned()))
+                } else {
+                    Ok(n)
+                }
+            },
+        )
+        .await;
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,426 @@
+use reqwest::header::{HeaderMap, CONTENT_TYPE, DATE};
+use reqwest::{Method, Response};
+use serde_derive::Deserialize;
+use serde_xml_rs::from_str;
+
+use crate::config::SignatureVersion;
+use crate::errors::Error;
+use crate::oss::OSS;
+use crate::utils::xml_escape;
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct UdfInfo {
+    name: String,
+    #[serde(default)]
+    owner_id: String,
+    #[serde(rename = "ID", default)]
+    id: String,
+    #[serde(default)]
+    description: String,
+    #[serde(rename = "ACL", default)]
+    acl: String,
+    #[serde(default)]
+    creation_date: String,
+}
+
+impl UdfInfo {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn owner_id(&self) -> &str {
+        &self.owner_id
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn acl(&self) -> &str {
+        &self.acl
+    }
+
+    pub fn creation_date(&self) -> &str {
+        &self.creation_date
+    }
+}
+
+// How a UDF application is deployed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct UdfApplicationConfig {
+    pub image_version: u32,
+    pub instance_num: u32,
+    // ECS instance type, e.g. ecs.n1.small.
+    pub instance_type: String,
+}
+
+impl Default for UdfApplicationConfig {
+    fn default() -> Self {
+        UdfApplicationConfig {
+            image_version: 1,
+            instance_num: 1,
+            instance_type: "ecs.n1.small".to_owned(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+struct Flavor {
+    #[serde(default)]
+    instance_type: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+pub struct UdfApplicationInfo {
+    name: String,
+    #[serde(rename = "ID", default)]
+    id: String,
+    #[serde(default)]
+    region: String,
+    #[serde(default)]
+    image_version: u32,
+    #[serde(default)]
+    instance_num: u32,
+    #[serde(default)]
+    flavor: Flavor,
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    creation_date: String,
+}
+
+impl UdfApplicationInfo {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn region(&self) -> &str {
+        &self.region
+    }
+
+    pub fn image_version(&self) -> u32 {
+        self.image_version
+    }
+
+    pub fn instance_num(&self) -> u32 {
+        self.instance_num
+    }
+
+    pub fn instance_type(&self) -> &str {
+        &self.flavor.instance_type
+    }
+
+    // e.g. creating, running or failed.
+    pub fn status(&self) -> &str {
+        &self.status
+    }
+
+    pub fn creation_date(&self) -> &str {
+        &self.creation_date
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct UdfInfoList {
+    #[serde(rename = "UDFInfo", default)]
+    udfs: Vec<UdfInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UdfApplicationInfoList {
+    #[serde(rename = "UDFApplicationInfo", default)]
+    applications: Vec<UdfApplicationInfo>,
+}
+
+impl OSS {
+    pub async fn create_udf<S>(&self, name: S, id: S, description: S) -> Result<(), Error>
+    where
+        S: AsRef<str>,
+    {
+        self.with_context("create_udf", "", async {
+            let body = format!(
+                "<CreateUDFConfiguration><Name>{}</Name><ID>{}</ID>\
+                 <Description>{}</Description></CreateUDFConfiguration>",
+                xml_escape(name.as_ref()),
+                xml_escape(id.as_ref()),
+                xml_escape(description.as_ref())
+            );
+            let params = [("udf", Some(String::new()))];
+            let resp = self.udf_request(Method::POST, &params, body).await?;
+            self.udf_status(resp, "create udf").await.map(|_| ())
+        })
+        .await
+    }
+
+    pub async fn get_udf<S>(&self, name: S) -> Result<UdfInfo, Error>
+    where
+        S: AsRef<str>,
+    {
+        self.with_context("get_udf", "", async {
+            let params = [
+                ("udf", Some(String::new())),
+                ("udfName", Some(name.as_ref().to_owned())),
+            ];
+            let resp = self
+                .udf_request(Method::GET, &params, String::new())
+                .await?;
+            let resp = self.udf_status(resp, "get udf").await?;
+            Ok(from_str(&self.xml_text(resp).await?)?)
+        })
+        .await
+    }
+
+    pub async fn list_udfs(&self) -> Result<Vec<UdfInfo>, Error> {
+        self.with_context("list_udfs", "", async {
+            let params = [("udf", Some(String::new()))];
+            let resp = self
+                .udf_request(Method::GET, &params, String::new())
+                .await?;
+            let resp = self.udf_status(resp, "list udfs").await?;
+            let list: UdfInfoList = from_str(&self.xml_text(resp).await?)?;
+            Ok(list.udfs)
+        })
+        .await
+    }
+
+    pub async fn delete_udf<S>(&self, name: S) -> Result<(), Error>
+    where
+        S: AsRef<str>,
+    {
+        self.with_context("delete_udf", "", async {
+            let params = [
+                ("udf", Some(String::new())),
+                ("udfName", Some(name.as_ref().to_owned())),
+                ("comp", Some("delete".to_owned())),
+            ];
+            let resp = self
+                .udf_request(Method::POST, &params, String::new())
+                .await?;
+            self.udf_status(resp, "delete udf").await.map(|_| ())
+        })
+        .await
+    }
+
+    // Deploys the UDF `name` on instances as configured.
+    pub async fn create_udf_application<S>(
+        &self,
+        name: S,
+        config: &UdfApplicationConfig,
+    ) -> Result<(), Error>
+    where
+        S: AsRef<str>,
+    {
+        self.with_context("create_udf_application", "", async {
+            let body = format!(
+                "<CreateUDFApplicationConfiguration><ImageVersion>{}</ImageVersion>\
+                 <InstanceNum>{}</InstanceNum><Flavor><InstanceType>{}</InstanceType>\
+                 </Flavor></CreateUDFApplicationConfiguration>",
+                config.image_version,
+                config.instance_num,
+                xml_escape(&config.instance_type)
+            );
+            let params = [
+                ("udfApplication", Some(String::new())),
+                ("udfName", Some(name.as_ref().to_owned())),
+            ];
+            let resp = self.udf_request(Method::POST, &params, body).await?;
+            self.udf_status(resp, "create udf application")
+                .await
+                .map(|_| ())
+        })
+        .await
+    }
+
+    pub async fn get_udf_application<S>(&self, name: S) -> Result<UdfApplicationInfo, Error>
+    where
+        S: AsRef<str>,
+    {
+        self.with_context("get_udf_application", "", async {
+            let params = [
+                ("udfApplication", Some(String::new())),
+                ("udfName", Some(name.as_ref().to_owned())),
+            ];
+            let resp = self
+                .udf_request(Method::GET, &params, String::new())
+                .await?;
+            let resp = self.udf_status(resp, "get udf application").await?;
+            Ok(from_str(&self.xml_text(resp).await?)?)
+        })
+        .await
+    }
+
+    pub async fn list_udf_applications(&self) -> Result<Vec<UdfApplicationInfo>, Error> {
+        self.with_context("list_udf_applications", "", async {
+            let params = [("udfApplication", Some(String::new()))];
+            let resp = self
+                .udf_request(Method::GET, &params, String::new())
+                .await?;
+            let resp = self.udf_status(resp, "list udf applications").await?;
+            let list: UdfApplicationInfoList = from_str(&self.xml_text(resp).await?)?;
+            Ok(list.applications)
+        })
+        .await
+    }
+
+    pub async fn delete_udf_application<S>(&self, name: S) -> Result<(), Error>
+    where
+        S: AsRef<str>,
+    {
+        self.with_context("delete_udf_application", "", async {
+            let params = [
+                ("udfApplication", Some(String::new())),
+                ("udfName", Some(name.as_ref().to_owned())),
+                ("comp", Some("delete".to_owned())),
+            ];
+            let resp = self
+                .udf_request(Method::POST, &params, String::new())
+                .await?;
+            self.udf_status(resp, "delete udf application")
+                .await
+                .map(|_| ())
+        })
+        .await
+    }
+
+    // The application's log from `since` (a Unix timestamp) on, or its last
+    // `tail` lines.
+    pub async fn get_udf_application_log<S>(
+        &self,
+        name: S,
+        since: Option<i64>,
+        tail: Option<u64>,
+    ) -> Result<String, Error>
+    where
+        S: AsRef<str>,
+    {
+        self.with_context("get_udf_application_log", "", async {
+            let params = [
+                ("udfApplicationLog", Some(String::new())),
+                ("udfName", Some(name.as_ref().to_owned())),
+                ("since", since.map(|s| s.to_string())),
+                ("tail", tail.map(|t| t.to_string())),
+            ];
+            let resp = self
+                .udf_request(Method::GET, &params, String::new())
+                .await?;
+            let resp = self.udf_status(resp, "get udf application log").await?;
+            Ok(resp.text().await?)
+        })
+        .await
+    }
+
+    // UDFs belong to the account, not a bucket: requests go to the bare
+    // endpoint and are signed with an empty bucket.
+    async fn udf_request(
+        &self,
+        method: Method,
+        params: &[(&str, Option<String>)],
+        body: String,
+    ) -> Result<Response, Error> {
+        let (query, resources_str) = self.query_and_resources(params);
+        let signed = match self.signature_version() {
+            SignatureVersion::V1 => &resources_str,
+            SignatureVersion::V4 => &query,
+        };
+        let url = &format!("{}/?{}", service_url(self.endpoint()), query);
+        let (method_ref, body) = (&method, &body);
+        self.retrying(&method, "", || async move {
+            let mut headers = HeaderMap::new();
+            headers.insert(DATE, self.date().parse()?);
+            if !body.is_empty() {
+                headers.insert(CONTENT_TYPE, "application/xml".parse()?);
+            }
+            let authorization = self
+                .sign(method_ref.as_str(), "", "", signed, &mut headers)
+                .await?;
+            headers.insert("Authorization", authorization.parse()?);
+
+            let mut req = self
+                .client
+                .request(method_ref.clone(), url)
+                .headers(headers);
+            if !body.is_empty() {
+                req = req.body(body.clone());
+            }
+            self.send(req).await
+        })
+        .await
+    }
+}
+
+impl OSS {
+    async fn udf_status(&self, resp: Response, action: &str) -> Result<Response, Error> {
+        if resp.status().is_success() {
+            Ok(resp)
+        } else {
+            Err(self
+                .api_error(resp, |reason| {
+                    Error::E(format!("can not {}, reason: {:?}", action, reason))
+                })
+                .await)
+        }
+    }
+}
+
+fn service_url(endpoint: &str) -> String {
+    if endpoint.starts_with("https://") || endpoint.starts_with("http://") {
+        endpoint.trim_end_matches('/').to_owned()
+    } else {
+        format!("http://{}", endpoint.trim_end_matches('/'))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_udf_lists() {
+        let udfs: UdfInfoList = from_str(
+            "<UDFInfoList><UDFInfo><Name>udf-1</Name><OwnerId>1234</OwnerId><ID>acs:oss:cn-hangzhou:1234:udf/udf-1</ID>\
+             <Description>thumbnails</Description><ACL>private</ACL><CreationDate>2017-09-01T04:38:53.000Z</CreationDate>\
+             </UDFInfo></UDFInfoList>",
+        )
+        .unwrap();
+        assert_eq!(udfs.udfs.len(), 1);
+        assert_eq!(udfs.udfs[0].name(), "udf-1");
+        assert_eq!(udfs.udfs[0].acl(), "private");
+
+        let apps: UdfApplicationInfoList = from_str(
+            "<UDFApplicationInfoList><UDFApplicationInfo><Name>udf-1</Name><ID>acs:oss:cn-hangzhou:1234:udf/udf-1</ID>\
+             <Region>oss-cn-hangzhou</Region><ImageVersion>2</ImageVersion><InstanceNum>3</InstanceNum>\
+             <Flavor><InstanceType>ecs.n1.small</InstanceType></Flavor><Status>running</Status>\
+             <CreationDate>2017-09-01T04:38:53.000Z</CreationDate></UDFApplicationInfo></UDFApplicationInfoList>",
+        )
+        .unwrap();
+        assert_eq!(apps.applications[0].image_version(), 2);
+        assert_eq!(apps.applications[0].instance_type(), "ecs.n1.small");
+        assert_eq!(apps.applications[0].status(), "running");
+
+        let empty: UdfInfoList = from_str("<UDFInfoList></UDFInfoList>").unwrap();
+        assert!(empty.udfs.is_empty());
+    }
+
+    #[test]
+    fn test_service_url() {
+        assert_eq!(
+            service_url("oss-cn-hangzhou.aliyuncs.com"),
+            "http://oss-cn-hangzhou.aliyuncs.com"
+        );
+        assert_eq!(
+            service_url("https://oss-cn-hangzhou.aliyuncs.com/"),
+            "https://oss-cn-hangzhou.aliyuncs.com"
+        );
+    }
+}
@@ -1,20 +1,26 @@
 use super::errors::Error;
+use bytes::Bytes;
+use futures::future::{self, Either};
+use futures::stream::{self, Stream, TryStreamExt};
+use md5::{Digest, Md5};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
 use reqwest::header::{HeaderMap, HeaderName};
+use reqwest::Body;
 use std::collections::HashMap;
+use std::future::Future;
+use std::io::{self, SeekFrom};
+use std::sync::{Arc, Mutex};
 use std::vec;
-use tokio::fs::File;
-use tokio::io::BufReader;
-use tokio::io::{AsyncReadExt, AsyncSeekExt, SeekFrom};
+use tokio_util::sync::CancellationToken;
 
-#[inline]
-pub async fn load_file(f: &mut File) -> Result<Vec<u8>, Error> {
-    let mut f = BufReader::new(f);
-    let mut s = Vec::new();
-    f.read_to_end(&mut s).await?;
-    Ok(s)
-}
+use crate::progress::Progress;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::rt::{AsyncReadExt, AsyncSeekExt, File};
+use crate::throttle::RateLimiter;
 
+#[cfg(not(target_arch = "wasm32"))]
 #[inline]
+#[cfg_attr(not(feature = "transfer-manager"), allow(dead_code))]
 pub async fn load_chunk_file(f: &mut File, offset: u64, size: u64) -> Result<Vec<u8>, Error> {
     let mut buf = Vec::with_capacity(size as usize);
     f.seek(SeekFrom::Start(offset)).await?;
@@ -22,6 +28,139 @@ pub async fn load_chunk_file(f: &mut File, offset: u64, size: u64) -> Result<Vec
     Ok(buf)
 }
 
+// Read size of `file_body`, only this much of the file is in memory at once.
+#[cfg(not(target_arch = "wasm32"))]
+const FILE_BODY_CHUNK: u64 = 256 * 1024;
+
+// A request body streaming `size` bytes of `f` from `offset` on. The body
+// fails if the file turns out shorter.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn file_body(
+    f: File,
+    offset: u64,
+    size: u64,
+    crc: Option<Arc<Mutex<Crc64>>>,
+    progress: Option<Arc<Progress>>,
+    limiter: Option<Arc<RateLimiter>>,
+) -> Result<Body, Error> {
+    Ok(stream_body(
+        file_chunks(f, offset, size).await?,
+        crc,
+        progress,
+        limiter,
+    ))
+}
+
+// The base64 MD5 of `size` bytes of `f` from `offset` on, for a Content-MD5
+// header sent ahead of a streamed body.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn file_md5(f: File, offset: u64, size: u64) -> Result<String, Error> {
+    let mut md5 = Md5::new();
+    file_chunks(f, offset, size)
+        .await?
+        .try_for_each(|chunk| {
+            md5.update(&chunk);
+            futures::future::ready(Ok(()))
+        })
+        .await?;
+    Ok(base64::encode(md5.finalize()))
+}
+
+// The Content-MD5 header value for `buf`.
+pub(crate) fn content_md5(buf: &[u8]) -> String {
+    base64::encode(Md5::digest(buf))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn file_chunks(
+    mut f: File,
+    offset: u64,
+    size: u64,
+) -> Result<impl Stream<Item = io::Result<Bytes>>, Error> {
+    f.seek(SeekFrom::Start(offset)).await?;
+    Ok(stream::try_unfold((f, size), |(mut f, left)| async move {
+        if left == 0 {
+            return Ok(None);
+        }
+        let mut buf = vec![0; FILE_BODY_CHUNK.min(left) as usize];
+        let n = f.read(&mut buf).await?;
+        if n == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "file ended before the expected size",
+            ));
+        }
+        buf.truncate(n);
+        Ok(Some((Bytes::from(buf), (f, left - n as u64))))
+    }))
+}
+
+// Runs `fut` unless `cancel` fires first, in which case it is dropped.
+pub(crate) async fn until_cancelled<F, T>(cancel: &CancellationToken, fut: F) -> Result<T, Error>
+where
+    F: Future<Output = Result<T, Error>>,
+{
+    let cancelled = cancel.cancelled();
+    futures::pin_mut!(fut, cancelled);
+    match future::select(fut, cancelled).await {
+        Either::Left((result, _)) => result,
+        Either::Right(_) => Err(Error::E("transfer was cancelled".to_owned())),
+    }
+}
+
+// Bounds of x-oss-traffic-limit, 100 KB/s to 100 MB/s in bits per second.
+const TRAFFIC_LIMIT_RANGE: std::ops::RangeInclusive<u64> = 819_200..=838_860_800;
+
+// Sets x-oss-traffic-limit, which has OSS throttle the request body or
+// response to `limit` bits per second.
+pub(crate) fn insert_traffic_limit(
+    headers: &mut HeaderMap,
+    limit: Option<u64>,
+) -> Result<(), Error> {
+    if let Some(limit) = limit {
+        if !TRAFFIC_LIMIT_RANGE.contains(&limit) {
+            return Err(Error::E(format!(
+                "traffic limit {} is not within {} to {} bits per second",
+                limit,
+                TRAFFIC_LIMIT_RANGE.start(),
+                TRAFFIC_LIMIT_RANGE.end()
+            )));
+        }
+        headers.insert("x-oss-traffic-limit", limit.into());
+    }
+    Ok(())
+}
+
+// RFC 3986 unreserved characters are left as is.
+pub(crate) const QUERY_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+// Like the query set, but keeps the slashes of object keys.
+pub(crate) const PATH_ENCODE_SET: &AsciiSet = &QUERY_ENCODE_SET.remove(b'/');
+
+#[inline]
+pub fn url_encode(s: &str) -> String {
+    utf8_percent_encode(s, QUERY_ENCODE_SET).to_string()
+}
+
+// Encodes an object key for the URL path, `/` separators are kept.
+pub fn path_encode(s: &str) -> String {
+    utf8_percent_encode(s, PATH_ENCODE_SET).to_string()
+}
+
+pub fn url_decode(s: &str) -> String {
+    percent_decode_str(s).decode_utf8_lossy().into_owned()
+}
+
+pub fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
 pub fn to_headers<S>(hashmap: HashMap<S, S>) -> Result<HeaderMap, Error>
 where
     S: AsRef<str>,
@@ -35,6 +174,145 @@ where
     Ok(headers)
 }
 
+// CRC-64/ECMA-182 as used by OSS for x-oss-hash-crc64ecma (reflected, all
+// ones init and xor out, the same parameters as CRC-64/XZ).
+const CRC64_ECMA_POLY: u64 = 0xC96C_5795_D787_0F42;
+
+const fn crc64_table() -> [u64; 256] {
+    let mut table = [0u64; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u64;
+        let mut j = 0;
+        while j < 8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ CRC64_ECMA_POLY
+            } else {
+                crc >> 1
+            };
+            j += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+static CRC64_TABLE: [u64; 256] = crc64_table();
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Crc64(u64);
+
+impl Crc64 {
+    pub fn new() -> Self {
+        Crc64(0)
+    }
+
+    pub fn update(&mut self, buf: &[u8]) {
+        let mut crc = !self.0;
+        for b in buf {
+            crc = CRC64_TABLE[((crc ^ *b as u64) & 0xff) as usize] ^ (crc >> 8);
+        }
+        self.0 = !crc;
+    }
+
+    pub fn value(&self) -> u64 {
+        self.0
+    }
+
+    // The CRC of the concatenation of two blocks, from their CRCs and the
+    // length of the second, as zlib's crc32_combine. Lets the CRCs of
+    // multipart parts be checked against the one of the whole object.
+    pub fn combine(crc1: u64, crc2: u64, len2: u64) -> u64 {
+        if len2 == 0 {
+            return crc1;
+        }
+        // Operators appending one, two and four zero bits.
+        let mut odd = [0u64; 64];
+        odd[0] = CRC64_ECMA_POLY;
+        for (n, row) in odd.iter_mut().enumerate().skip(1) {
+            *row = 1 << (n - 1);
+        }
+        let mut even = [0u64; 64];
+        gf2_matrix_square(&mut even, &odd);
+        gf2_matrix_square(&mut odd, &even);
+
+        let mut crc1 = crc1;
+        let mut len2 = len2;
+        loop {
+            gf2_matrix_square(&mut even, &odd);
+            if len2 & 1 == 1 {
+                crc1 = gf2_matrix_times(&even, crc1);
+            }
+            len2 >>= 1;
+            if len2 == 0 {
+                break;
+            }
+            gf2_matrix_square(&mut odd, &even);
+            if len2 & 1 == 1 {
+                crc1 = gf2_matrix_times(&odd, crc1);
+            }
+            len2 >>= 1;
+            if len2 == 0 {
+                break;
+            }
+        }
+        crc1 ^ crc2
+    }
+}
+
+fn gf2_matrix_times(mat: &[u64; 64], mut vec: u64) -> u64 {
+    let mut sum = 0;
+    let mut i = 0;
+    while vec != 0 {
+        if vec & 1 == 1 {
+            sum ^= mat[i];
+        }
+        vec >>= 1;
+        i += 1;
+    }
+    sum
+}
+
+fn gf2_matrix_square(square: &mut [u64; 64], mat: &[u64; 64]) {
+    for (n, row) in square.iter_mut().enumerate() {
+        *row = gf2_matrix_times(mat, mat[n]);
+    }
+}
+
+// A body streaming `stream` that feeds every chunk it sends to `crc`, to be
+// compared with the x-oss-hash-crc64ecma header of the response, counts it
+// towards `progress` and holds it back as long as `limiter` says.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn stream_body<S>(
+    stream: S,
+    crc: Option<Arc<Mutex<Crc64>>>,
+    progress: Option<Arc<Progress>>,
+    limiter: Option<Arc<RateLimiter>>,
+) -> Body
+where
+    S: Stream<Item = io::Result<Bytes>> + Send + 'static,
+{
+    let stream = stream.and_then(move |chunk| {
+        let limiter = limiter.clone();
+        async move {
+            if let Some(limiter) = limiter {
+                limiter.acquire(chunk.len() as u64).await;
+            }
+            Ok(chunk)
+        }
+    });
+    Body::wrap_stream(stream.inspect_ok(move |chunk| {
+        if let Some(crc) = &crc {
+            crc.lock().unwrap().update(chunk);
+        }
+        if let Some(progress) = &progress {
+            progress.advance(chunk.len() as u64);
+        }
+    }))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
 #[derive(Debug, Clone)]
 pub struct FileChunk {
     pub number: u64,
@@ -44,8 +322,9 @@ pub struct FileChunk {
 
 // split_file_by_part_size splits big file into parts by the size of parts.
 // Splits the file by the part size. Returns the FileChunk when error is nil.
+#[cfg(not(target_arch = "wasm32"))]
 pub async fn split_file_by_part_size(f: &File, chunk_size: u64) -> Result<Vec<FileChunk>, Error> {
-    if chunk_size <= 0 {
+    if chunk_size == 0 {
         return Err(Error::E("chunk_size invalid".to_string()));
     }
 
@@ -67,7 +346,7 @@ pub async fn split_file_by_part_size(f: &File, chunk_size: u64) -> Result<Vec<Fi
             size: chunk_size,
         };
         chunks.push(chunk);
-        i = i + 1;
+        i += 1;
     }
 
     if size % chunk_size > 0 {
@@ -86,9 +365,53 @@ pub async fn split_file_by_part_size(f: &File, chunk_size: u64) -> Result<Vec<Fi
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_crc64() {
+        let mut crc = Crc64::new();
+        crc.update(b"1234");
+        crc.update(b"56789");
+        assert_eq!(crc.value(), 0x995D_C9BB_DF19_39FA);
+        assert_eq!(Crc64::new().value(), 0);
+
+        let mut head = Crc64::new();
+        head.update(b"1234");
+        let mut tail = Crc64::new();
+        tail.update(b"56789");
+        assert_eq!(
+            Crc64::combine(head.value(), tail.value(), 5),
+            0x995D_C9BB_DF19_39FA
+        );
+        assert_eq!(Crc64::combine(head.value(), 0, 0), head.value());
+    }
+
+    #[tokio::test]
+    async fn test_until_cancelled() {
+        let cancel = CancellationToken::new();
+        assert_eq!(until_cancelled(&cancel, async { Ok(1) }).await.unwrap(), 1);
+        cancel.cancel();
+        let pending = future::pending::<Result<(), Error>>();
+        assert!(until_cancelled(&cancel, pending).await.is_err());
+    }
+
+    #[test]
+    fn test_insert_traffic_limit() {
+        let mut headers = HeaderMap::new();
+        insert_traffic_limit(&mut headers, None).unwrap();
+        assert!(headers.is_empty());
+        insert_traffic_limit(&mut headers, Some(819_200)).unwrap();
+        assert_eq!(headers["x-oss-traffic-limit"], "819200");
+        assert!(insert_traffic_limit(&mut headers, Some(1024)).is_err());
+    }
+
+    #[test]
+    fn test_content_md5() {
+        assert_eq!(content_md5(b""), "1B2M2Y8AsgTpgAmY7PhCfg==");
+        assert_eq!(content_md5(b"0123456789"), "eB5eJF1ptWaXm4bijSPyxw==");
+    }
+
     #[tokio::test]
     async fn test_chunk_file() {
-        let f = tokio::fs::File::open("/tmp/tmp.txt").await.unwrap();
+        let f = crate::rt::open("/tmp/tmp.txt").await.unwrap();
         let res = split_file_by_part_size(&f, 1024).await;
         // println!("res: {:?}", res.unwrap());
         assert!(res.is_ok());
@@ -96,8 +419,36 @@ mod tests {
 
     #[tokio::test]
     async fn test_load_chunk_file() {
-        let mut f = tokio::fs::File::open("/tmp/tmp.txt").await.unwrap();
+        let mut f = crate::rt::open("/tmp/tmp.txt").await.unwrap();
         let data = load_chunk_file(&mut f, 0, 100).await.unwrap();
         println!("data: {:?}", data);
     }
+
+    #[tokio::test]
+    async fn test_file_chunks() {
+        use futures::TryStreamExt;
+
+        let path = std::env::temp_dir().join("oss-sdk-file-chunks.bin");
+        let data: Vec<u8> = (0..600 * 1024).map(|i| i as u8).collect();
+        std::fs::write(&path, &data).unwrap();
+
+        let f = crate::rt::open(&path).await.unwrap();
+        let chunks: Vec<Bytes> = file_chunks(f, 100, 500 * 1024)
+            .await
+            .unwrap()
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks.concat(), &data[100..100 + 500 * 1024]);
+
+        let f = crate::rt::open(&path).await.unwrap();
+        let short: Result<Vec<Bytes>, _> = file_chunks(f, 0, data.len() as u64 + 1)
+            .await
+            .unwrap()
+            .try_collect()
+            .await;
+        assert!(short.is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
 }
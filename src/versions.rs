@@ -0,0 +1,237 @@
+use quick_xml::{events::Event, Reader};
+use reqwest::header::HeaderMap;
+use std::collections::HashMap;
+
+use crate::errors::Error;
+use crate::object::DeleteObjectsResult;
+use crate::oss::OSS;
+
+// What a DeleteObject without a version id leaves behind on a versioned
+// bucket. While it is the latest version of its key the object reads as
+// deleted, the versions before it are kept.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DeleteMarker {
+    key: String,
+    version_id: String,
+    is_latest: bool,
+    last_modified: String,
+}
+
+impl DeleteMarker {
+    pub fn new(key: String, version_id: String, is_latest: bool, last_modified: String) -> Self {
+        DeleteMarker {
+            key,
+            version_id,
+            is_latest,
+            last_modified,
+        }
+    }
+
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn version_id(&self) -> &str {
+        &self.version_id
+    }
+
+    pub fn is_latest(&self) -> bool {
+        self.is_latest
+    }
+
+    pub fn last_modified(&self) -> &str {
+        &self.last_modified
+    }
+}
+
+// One page of a ListObjectVersions response, only the delete markers are
+// kept.
+struct VersionsPage {
+    delete_markers: Vec<DeleteMarker>,
+    // Key and version id markers of the next page, None on the last one.
+    next: Option<(String, String)>,
+}
+
+impl OSS {
+    // Every delete marker under `prefix`, across all pages.
+    // https://help.aliyun.com/document_detail/112602.html
+    pub async fn list_delete_markers<S: AsRef<str>>(
+        &self,
+        prefix: S,
+    ) -> Result<Vec<DeleteMarker>, Error> {
+        let prefix = prefix.as_ref();
+        self.with_context("list_delete_markers", prefix, async {
+            let mut markers = Vec::new();
+            let mut next: Option<(String, String)> = None;
+            loop {
+                let mut params = vec![
+                    ("versions", Some(String::new())),
+                    ("prefix", Some(prefix.to_owned())),
+                    ("max-keys", Some("1000".to_owned())),
+                ];
+                if let Some((key_marker, version_id_marker)) = next {
+                    params.push(("key-marker", Some(key_marker)));
+                    params.push(("version-id-marker", Some(version_id_marker)));
+                }
+                let resp = self.signed_get("", &params, HeaderMap::new()).await?;
+                let page = parse_versions_page(&self.xml_text(resp).await?)?;
+                markers.extend(page.delete_markers);
+                next = page.next;
+                if next.is_none() {
+                    return Ok(markers);
+                }
+            }
+        })
+        .await
+    }
+
+    // Deletes every delete marker under `prefix` for good. Where the marker
+    // was the latest version the version before it becomes current again,
+    // which undeletes the object.
+    pub async fn remove_delete_markers<S: AsRef<str>>(
+        &self,
+        prefix: S,
+    ) -> Result<DeleteObjectsResult, Error> {
+        let prefix = prefix.as_ref();
+        let markers = self.list_delete_markers(prefix).await?;
+        let objects = markers
+            .into_iter()
+            .map(|m| (m.key, Some(m.version_id)))
+            .collect();
+        self.with_context(
+            "remove_delete_markers",
+            prefix,
+            self.delete_object_versions(objects, false),
+        )
+        .await
+    }
+}
+
+fn parse_versions_page(xml_str: &str) -> Result<VersionsPage, Error> {
+    let mut reader = Reader::from_str(xml_str);
+    reader.trim_text(true);
+    let mut buf = Vec::new();
+
+    let mut delete_markers = Vec::new();
+    let mut top: HashMap<Vec<u8>, String> = HashMap::new();
+    let mut fields: HashMap<Vec<u8>, String> = HashMap::new();
+    // Inside a Version or DeleteMarker element.
+    let mut in_entry = false;
+
+    loop {
+        match reader.read_event(&mut buf) {
+            Ok(Event::Start(ref e)) => match e.name() {
+                b"ListVersionsResult" => (),
+                b"Version" | b"DeleteMarker" => {
+                    in_entry = true;
+                    fields.clear();
+                }
+                b"Owner" => (),
+                name => {
+                    let name = name.to_vec();
+                    let text = reader.read_text(e.name(), &mut Vec::new())?;
+                    if in_entry {
+                        fields.insert(name, text);
+                    } else {
+                        top.insert(name, text);
+                    }
+                }
+            },
+            Ok(Event::End(ref e)) => match e.name() {
+                b"Version" => in_entry = false,
+                b"DeleteMarker" => {
+                    in_entry = false;
+                    let mut field = |name: &[u8]| fields.remove(name).unwrap_or_default();
+                    delete_markers.push(DeleteMarker::new(
+                        field(b"Key"),
+                        field(b"VersionId"),
+                        field(b"IsLatest") == "true",
+                        field(b"LastModified"),
+                    ));
+                }
+                _ => (),
+            },
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(e.into()),
+            _ => (),
+        }
+        buf.clear();
+    }
+
+    let next = if top.get(&b"IsTruncated"[..]).map(|t| t.as_str()) == Some("true") {
+        Some((
+            top.remove(&b"NextKeyMarker"[..]).unwrap_or_default(),
+            top.remove(&b"NextVersionIdMarker"[..]).unwrap_or_default(),
+        ))
+    } else {
+        None
+    };
+    Ok(VersionsPage {
+        delete_markers,
+        next,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_versions_page() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ListVersionsResult>
+  <Name>examplebucket</Name>
+  <Prefix>photos/</Prefix>
+  <KeyMarker></KeyMarker>
+  <VersionIdMarker></VersionIdMarker>
+  <MaxKeys>2</MaxKeys>
+  <Delimiter></Delimiter>
+  <IsTruncated>true</IsTruncated>
+  <NextKeyMarker>photos/b.png</NextKeyMarker>
+  <NextVersionIdMarker>CAEQMxiBgMCZov2B0BYiIDY4MDllOTc2YmY5MjQxMzdiOGI3OTlhNTU0ODIx****</NextVersionIdMarker>
+  <DeleteMarker>
+    <Key>photos/a.png</Key>
+    <VersionId>CAEQMxiBgICAof2D0BYiIDJhMGE3N2M1YTI1NDQzOGY5NTkyNTI3MGYyMzJm****</VersionId>
+    <IsLatest>true</IsLatest>
+    <LastModified>2019-04-09T07:27:28.000Z</LastModified>
+    <Owner>
+      <ID>1234512528586****</ID>
+      <DisplayName>12345125285864390</DisplayName>
+    </Owner>
+  </DeleteMarker>
+  <Version>
+    <Key>photos/a.png</Key>
+    <VersionId>CAEQMxiBgMDNoP2D0BYiIDE3MWUxNzgxZDQxNTRiODI5OGYwZGMwNGY3MzZjNDVk****</VersionId>
+    <IsLatest>false</IsLatest>
+    <LastModified>2019-04-09T07:27:28.000Z</LastModified>
+    <ETag>"250F8A0AE989679A22926A875F0A2****"</ETag>
+    <Type>Normal</Type>
+    <Size>93731</Size>
+    <StorageClass>Standard</StorageClass>
+    <Owner>
+      <ID>1234512528586****</ID>
+      <DisplayName>12345125285864390</DisplayName>
+    </Owner>
+  </Version>
+</ListVersionsResult>"#;
+        let page = parse_versions_page(xml).unwrap();
+        assert_eq!(
+            page.delete_markers,
+            vec![DeleteMarker::new(
+                "photos/a.png".to_owned(),
+                "CAEQMxiBgICAof2D0BYiIDJhMGE3N2M1YTI1NDQzOGY5NTkyNTI3MGYyMzJm****".to_owned(),
+                true,
+                "2019-04-09T07:27:28.000Z".to_owned(),
+            )]
+        );
+        let (key_marker, version_id_marker) = page.next.unwrap();
+        assert_eq!(key_marker, "photos/b.png");
+        assert!(version_id_marker.starts_with("CAEQMxiBgMCZov2B0BYi"));
+
+        let last = xml.replace(
+            "<IsTruncated>true</IsTruncated>",
+            "<IsTruncated>false</IsTruncated>",
+        );
+        assert!(parse_versions_page(&last).unwrap().next.is_none());
+    }
+}
@@ -0,0 +1,123 @@
+use futures::TryStreamExt;
+use quick_xml::{events::Event, Reader};
+use reqwest::Response;
+
+use crate::errors::{Error, XmlLimitError};
+use crate::oss::OSS;
+
+// Bounds on the XML responses the client parses, so a listing full of
+// hostile key names or a misbehaving proxy can not make it buffer or walk
+// unbounded input. Exceeding one fails the request with `Error::XmlLimit`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct XmlLimits {
+    max_size: usize,
+    max_depth: usize,
+}
+
+impl XmlLimits {
+    pub fn new(max_size: usize, max_depth: usize) -> Self {
+        XmlLimits {
+            max_size,
+            max_depth,
+        }
+    }
+
+    // Bytes of response body.
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    // Levels of nested elements, OSS responses use fewer than 5.
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+}
+
+// A full page of 1000 keys of 1023 bytes each stays well below the size.
+impl Default for XmlLimits {
+    fn default() -> Self {
+        XmlLimits::new(16 * 1024 * 1024, 32)
+    }
+}
+
+impl OSS {
+    // Reads an XML response body within the client's limits.
+    pub(crate) async fn xml_text(&self, resp: Response) -> Result<String, Error> {
+        read_xml(resp, self.xml_limits()).await
+    }
+}
+
+async fn read_xml(resp: Response, limits: XmlLimits) -> Result<String, Error> {
+    let too_large = || {
+        Error::XmlLimit(XmlLimitError::Size {
+            limit: limits.max_size,
+        })
+    };
+    if resp.content_length().unwrap_or_default() > limits.max_size as u64 {
+        return Err(too_large());
+    }
+    let mut body = Vec::new();
+    let chunks = resp.bytes_stream();
+    futures::pin_mut!(chunks);
+    while let Some(chunk) = chunks.try_next().await? {
+        if body.len() + chunk.len() > limits.max_size {
+            return Err(too_large());
+        }
+        body.extend_from_slice(&chunk);
+    }
+    let xml = String::from_utf8(body)?;
+    check_xml(&xml, limits)?;
+    Ok(xml)
+}
+
+// Rejects documents nested deeper than allowed, and any DOCTYPE: OSS never
+// sends one and it is where entity definitions would come from.
+pub(crate) fn check_xml(xml: &str, limits: XmlLimits) -> Result<(), Error> {
+    let mut reader = Reader::from_str(xml);
+    let mut buf = Vec::new();
+    let mut depth = 0;
+    loop {
+        match reader.read_event(&mut buf)? {
+            Event::Start(_) => {
+                depth += 1;
+                if depth > limits.max_depth {
+                    return Err(Error::XmlLimit(XmlLimitError::Depth {
+                        limit: limits.max_depth,
+                    }));
+                }
+            }
+            Event::End(_) => depth -= 1,
+            Event::DocType(_) => return Err(Error::XmlLimit(XmlLimitError::DocType)),
+            Event::Eof => return Ok(()),
+            _ => (),
+        }
+        buf.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_xml() {
+        let limits = XmlLimits::new(1024, 3);
+        assert!(check_xml(
+            "<?xml version=\"1.0\"?><ListBucketResult><Contents><Key>a</Key></Contents></ListBucketResult>",
+            limits
+        )
+        .is_ok());
+        assert!(matches!(
+            check_xml("<a><b><c><d/></c></b></a>", limits),
+            Ok(())
+        ));
+        assert!(matches!(
+            check_xml("<a><b><c><d></d></c></b></a>", limits),
+            Err(Error::XmlLimit(XmlLimitError::Depth { limit: 3 }))
+        ));
+        assert!(matches!(
+            check_xml("<!DOCTYPE a [<!ENTITY x \"xxxxxxxx\">]><a>&x;</a>", limits),
+            Err(Error::XmlLimit(XmlLimitError::DocType))
+        ));
+    }
+}